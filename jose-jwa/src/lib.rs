@@ -25,7 +25,7 @@ use serde::{Deserialize, Serialize};
 
 /// Possible types of algorithms that can exist in an "alg" descriptor.
 ///
-/// Currently only signing algorithms are represented.
+/// Signing and key management algorithms are represented.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[allow(missing_docs)]
 #[serde(untagged)]
@@ -33,6 +33,9 @@ use serde::{Deserialize, Serialize};
 pub enum Algorithm {
     /// Algorithms used for digital signatures and MACs
     Signing(Signing),
+
+    /// Algorithms used to determine the content encryption key
+    KeyManagement(KeyManagement),
 }
 
 impl From<Signing> for Algorithm {
@@ -42,6 +45,13 @@ impl From<Signing> for Algorithm {
     }
 }
 
+impl From<KeyManagement> for Algorithm {
+    #[inline(always)]
+    fn from(alg: KeyManagement) -> Self {
+        Self::KeyManagement(alg)
+    }
+}
+
 /// Algorithms used for signing, as defined in [RFC7518] section 3.1.
 ///
 /// [RFC7518]: https://www.rfc-editor.org/rfc/rfc7518
@@ -105,6 +115,124 @@ impl fmt::Display for Signing {
     }
 }
 
+/// Algorithms used for key management, as defined in [RFC7518] section 4.1.
+///
+/// [RFC7518]: https://www.rfc-editor.org/rfc/rfc7518
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyManagement {
+    /// RSAES-PKCS1-v1_5 (Recommended-)
+    #[serde(rename = "RSA1_5")]
+    Rsa1_5,
+
+    /// RSAES OAEP using default parameters (Recommended+)
+    #[serde(rename = "RSA-OAEP")]
+    RsaOaep,
+
+    /// RSAES OAEP using SHA-256 and MGF1 with SHA-256 (Optional)
+    #[serde(rename = "RSA-OAEP-256")]
+    RsaOaep256,
+
+    /// AES Key Wrap using 128-bit key (Recommended)
+    #[serde(rename = "A128KW")]
+    A128Kw,
+
+    /// AES Key Wrap using 192-bit key (Optional)
+    #[serde(rename = "A192KW")]
+    A192Kw,
+
+    /// AES Key Wrap using 256-bit key (Recommended)
+    #[serde(rename = "A256KW")]
+    A256Kw,
+
+    /// Direct use of a shared symmetric key (Recommended)
+    #[serde(rename = "dir")]
+    Direct,
+
+    /// ECDH-ES using Concat KDF (Recommended+)
+    #[serde(rename = "ECDH-ES")]
+    EcdhEs,
+
+    /// ECDH-ES using Concat KDF and "A128KW" wrapping (Recommended)
+    #[serde(rename = "ECDH-ES+A128KW")]
+    EcdhEsA128Kw,
+
+    /// ECDH-ES using Concat KDF and "A192KW" wrapping (Optional)
+    #[serde(rename = "ECDH-ES+A192KW")]
+    EcdhEsA192Kw,
+
+    /// ECDH-ES using Concat KDF and "A256KW" wrapping (Recommended)
+    #[serde(rename = "ECDH-ES+A256KW")]
+    EcdhEsA256Kw,
+
+    /// Key wrapping with AES GCM using 128-bit key (Optional)
+    #[serde(rename = "A128GCMKW")]
+    A128GcmKw,
+
+    /// Key wrapping with AES GCM using 192-bit key (Optional)
+    #[serde(rename = "A192GCMKW")]
+    A192GcmKw,
+
+    /// Key wrapping with AES GCM using 256-bit key (Optional)
+    #[serde(rename = "A256GCMKW")]
+    A256GcmKw,
+
+    /// PBES2 with HMAC SHA-256 and "A128KW" wrapping (Optional)
+    #[serde(rename = "PBES2-HS256+A128KW")]
+    Pbes2Hs256A128Kw,
+
+    /// PBES2 with HMAC SHA-384 and "A192KW" wrapping (Optional)
+    #[serde(rename = "PBES2-HS384+A192KW")]
+    Pbes2Hs384A192Kw,
+
+    /// PBES2 with HMAC SHA-512 and "A256KW" wrapping (Optional)
+    #[serde(rename = "PBES2-HS512+A256KW")]
+    Pbes2Hs512A256Kw,
+}
+
+impl fmt::Display for KeyManagement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.serialize(f)
+    }
+}
+
+/// Algorithms used for content encryption, as defined in [RFC7518] section 5.1.
+///
+/// [RFC7518]: https://www.rfc-editor.org/rfc/rfc7518
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encryption {
+    /// AES_128_CBC_HMAC_SHA_256 authenticated encryption (Required)
+    #[serde(rename = "A128CBC-HS256")]
+    A128CbcHs256,
+
+    /// AES_192_CBC_HMAC_SHA_384 authenticated encryption (Optional)
+    #[serde(rename = "A192CBC-HS384")]
+    A192CbcHs384,
+
+    /// AES_256_CBC_HMAC_SHA_512 authenticated encryption (Required)
+    #[serde(rename = "A256CBC-HS512")]
+    A256CbcHs512,
+
+    /// AES GCM using 128-bit key (Recommended)
+    #[serde(rename = "A128GCM")]
+    A128Gcm,
+
+    /// AES GCM using 192-bit key (Optional)
+    #[serde(rename = "A192GCM")]
+    A192Gcm,
+
+    /// AES GCM using 256-bit key (Recommended)
+    #[serde(rename = "A256GCM")]
+    A256Gcm,
+}
+
+impl fmt::Display for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.serialize(f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate std;
@@ -134,4 +262,81 @@ mod tests {
             input
         );
     }
+
+    #[test]
+    fn key_management_roundtrip() {
+        use KeyManagement::*;
+
+        let input = vec![
+            Rsa1_5,
+            RsaOaep,
+            RsaOaep256,
+            A128Kw,
+            A192Kw,
+            A256Kw,
+            Direct,
+            EcdhEs,
+            EcdhEsA128Kw,
+            EcdhEsA192Kw,
+            EcdhEsA256Kw,
+            A128GcmKw,
+            A192GcmKw,
+            A256GcmKw,
+            Pbes2Hs256A128Kw,
+            Pbes2Hs384A192Kw,
+            Pbes2Hs512A256Kw,
+        ];
+        let ser = serde_json::to_string(&input).expect("serialization failed");
+
+        assert_eq!(
+            ser,
+            concat!(
+                r#"["RSA1_5","RSA-OAEP","RSA-OAEP-256","A128KW","A192KW","A256KW","dir","#,
+                r#""ECDH-ES","ECDH-ES+A128KW","ECDH-ES+A192KW","ECDH-ES+A256KW","#,
+                r#""A128GCMKW","A192GCMKW","A256GCMKW","#,
+                r#""PBES2-HS256+A128KW","PBES2-HS384+A192KW","PBES2-HS512+A256KW"]"#,
+            )
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Vec<KeyManagement>>(&ser).expect("deserialization failed"),
+            input
+        );
+    }
+
+    #[test]
+    fn algorithm_untagged() {
+        let alg: Algorithm = serde_json::from_str(r#""RS256""#).expect("signing");
+        assert_eq!(alg, Algorithm::Signing(Signing::Rs256));
+
+        let alg: Algorithm = serde_json::from_str(r#""RSA-OAEP""#).expect("key management");
+        assert_eq!(alg, Algorithm::KeyManagement(KeyManagement::RsaOaep));
+
+        assert!(serde_json::from_str::<Algorithm>(r#""A128GCM""#).is_err());
+    }
+
+    #[test]
+    fn encryption_roundtrip() {
+        use Encryption::*;
+
+        let input = vec![
+            A128CbcHs256,
+            A192CbcHs384,
+            A256CbcHs512,
+            A128Gcm,
+            A192Gcm,
+            A256Gcm,
+        ];
+        let ser = serde_json::to_string(&input).expect("serialization failed");
+
+        assert_eq!(
+            ser,
+            r#"["A128CBC-HS256","A192CBC-HS384","A256CBC-HS512","A128GCM","A192GCM","A256GCM"]"#
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Vec<Encryption>>(&ser).expect("deserialization failed"),
+            input
+        );
+    }
 }
@@ -0,0 +1,70 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use core::fmt::Display;
+use core::{convert::Infallible, str::FromStr};
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_b64::stream::Error;
+
+use crate::{Compact, Flattened, General, Jwe};
+
+impl FromStr for Jwe {
+    type Err = Error<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Compact::from_str(s)?.into())
+    }
+}
+
+impl FromStr for General {
+    type Err = Error<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Compact::from_str(s)?.into())
+    }
+}
+
+impl FromStr for Flattened {
+    type Err = Error<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Compact::from_str(s)?.into())
+    }
+}
+
+impl FromStr for Compact {
+    type Err = Error<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut iter = s.split('.');
+
+        let prot = iter.next().ok_or(Error::Length)?;
+        let ekey = iter.next().ok_or(Error::Length)?;
+        let init = iter.next().ok_or(Error::Length)?;
+        let ciph = iter.next().ok_or(Error::Length)?;
+        let tag = iter.next().ok_or(Error::Length)?;
+        if iter.next().is_some() {
+            return Err(Error::Length);
+        }
+
+        Ok(Self {
+            protected: prot.parse()?,
+            encrypted_key: ekey.parse().map_err(|e: Error<Infallible>| e.cast())?,
+            iv: init.parse().map_err(|e: Error<Infallible>| e.cast())?,
+            ciphertext: ciph.parse().map_err(|e: Error<Infallible>| e.cast())?,
+            tag: tag.parse().map_err(|e: Error<Infallible>| e.cast())?,
+        })
+    }
+}
+
+impl Display for Compact {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let prot = Base64UrlUnpadded::encode_string(self.protected.as_ref());
+        let ekey = Base64UrlUnpadded::encode_string(&self.encrypted_key);
+        let init = Base64UrlUnpadded::encode_string(&self.iv);
+        let ciph = Base64UrlUnpadded::encode_string(&self.ciphertext);
+        let tag = Base64UrlUnpadded::encode_string(&self.tag);
+        write!(f, "{prot}.{ekey}.{init}.{ciph}.{tag}")
+    }
+}
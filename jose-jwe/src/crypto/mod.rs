@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! JWE Cryptographic Implementation
+//!
+//! Every JWE key management mode is a way of establishing the Content
+//! Encryption Key (CEK) with a recipient: the key may be used directly
+//! (`dir`), agreed (e.g. `ECDH-ES`), or used to wrap or transport a random
+//! CEK. The traits in this module hide this distinction, so that any kind of
+//! key can be used to encrypt or decrypt a JWE.
+
+pub mod rcrypto;
+
+use alloc::{vec, vec::Vec};
+
+use jose_b64::serde::{Bytes, Json};
+use jose_b64::stream::Update;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{Flattened, General, Jwe, Protected, Recipient, Unprotected, Zip};
+
+/// Encryption state
+pub trait Encryptor: Update {
+    #[allow(missing_docs)]
+    type FinishError: From<Self::Error>;
+
+    /// Finish processing the plaintext and create the JWE.
+    fn finish(self) -> Result<Flattened, Self::FinishError>;
+}
+
+/// An encryption key
+pub trait EncryptingKey<'a> {
+    #[allow(missing_docs)]
+    type StartError: From<<Self::Encryptor as Update>::Error>;
+
+    /// The state object used during encryption.
+    type Encryptor: Encryptor;
+
+    /// Begin the encryption process.
+    ///
+    /// The `alg` and `enc` parameters are taken from the headers. Any header
+    /// parameters required by the key management algorithm are added to the
+    /// protected header, if present, and otherwise to the unprotected header.
+    fn encrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        prot: Option<Protected>,
+        head: Option<Unprotected>,
+    ) -> Result<Self::Encryptor, Self::StartError>;
+}
+
+/// Decryption state
+pub trait Decryptor<'a>: Update {
+    #[allow(missing_docs)]
+    type FinishError: From<Self::Error>;
+
+    /// Finish processing the ciphertext and return the plaintext.
+    ///
+    /// The plaintext is only returned if the authentication tag is valid.
+    /// Since the tag can only be verified once all of the ciphertext has been
+    /// processed, implementations must buffer either the ciphertext or the
+    /// plaintext until then.
+    fn finish(self) -> Result<Vec<u8>, Self::FinishError>;
+}
+
+impl<'a, T: Decryptor<'a>> Decryptor<'a> for Vec<T>
+where
+    T::FinishError: Default,
+{
+    type FinishError = T::FinishError;
+
+    fn finish(self) -> Result<Vec<u8>, Self::FinishError> {
+        let mut last = T::FinishError::default();
+
+        for x in self {
+            match x.finish() {
+                Ok(plaintext) => return Ok(plaintext),
+                Err(e) => last = e,
+            }
+        }
+
+        Err(last)
+    }
+}
+
+/// A decryption key
+pub trait DecryptingKey<'a, T> {
+    #[allow(missing_docs)]
+    type StartError: From<<Self::Decryptor as Update>::Error>;
+
+    /// The state object used during decryption.
+    type Decryptor: Decryptor<'a>;
+
+    /// Begin the decryption process.
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        val: T,
+    ) -> Result<Self::Decryptor, Self::StartError>;
+}
+
+/// A view of a JWE for a single recipient
+///
+/// This contains everything, except the ciphertext, needed to decrypt a JWE
+/// for one of its recipients.
+#[derive(Copy, Clone, Debug)]
+pub struct Envelope<'a> {
+    /// The JWE Protected Header
+    pub protected: Option<&'a Json<Protected>>,
+
+    /// The JWE Shared Unprotected Header
+    pub unprotected: Option<&'a Unprotected>,
+
+    /// The recipient
+    pub recipient: &'a Recipient,
+
+    /// The JWE Additional Authenticated Data
+    pub aad: Option<&'a Bytes>,
+
+    /// The JWE Initialization Vector
+    pub iv: Option<&'a Bytes>,
+
+    /// The JWE Authentication Tag
+    pub tag: Option<&'a Bytes>,
+}
+
+impl Envelope<'_> {
+    /// Returns the union of the protected, shared and per-recipient headers.
+    pub fn header(&self) -> Unprotected {
+        let mut head = self.protected.map(|x| x.oth.clone()).unwrap_or_default();
+
+        if let Some(x) = self.unprotected {
+            head = head.union(x);
+        }
+
+        if let Some(x) = &self.recipient.header {
+            head = head.union(x);
+        }
+
+        head
+    }
+
+    /// Returns the compression algorithm applied to the plaintext.
+    pub fn zip(&self) -> Option<Zip> {
+        self.protected.and_then(|x| x.zip)
+    }
+
+    /// Returns the Additional Authenticated Data used during encryption.
+    pub fn additional_data(&self) -> Vec<u8> {
+        crate::additional_data(self.protected, self.aad)
+    }
+}
+
+impl<'a> From<&'a Flattened> for Envelope<'a> {
+    fn from(value: &'a Flattened) -> Self {
+        Self {
+            protected: value.protected.as_ref(),
+            unprotected: value.unprotected.as_ref(),
+            recipient: &value.recipient,
+            aad: value.aad.as_ref(),
+            iv: value.iv.as_ref(),
+            tag: value.tag.as_ref(),
+        }
+    }
+}
+
+impl General {
+    /// Returns a view of this JWE for each recipient.
+    pub fn envelopes(&self) -> impl Iterator<Item = Envelope<'_>> {
+        self.recipients.iter().map(move |recipient| Envelope {
+            protected: self.protected.as_ref(),
+            unprotected: self.unprotected.as_ref(),
+            recipient,
+            aad: self.aad.as_ref(),
+            iv: self.iv.as_ref(),
+            tag: self.tag.as_ref(),
+        })
+    }
+}
+
+impl<'a, T: DecryptingKey<'a, Envelope<'a>>> DecryptingKey<'a, &'a Flattened> for T
+where
+    <T::Decryptor as Decryptor<'a>>::FinishError: Default,
+{
+    type StartError = T::StartError;
+    type Decryptor = Vec<T::Decryptor>;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        flattened: &'a Flattened,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        Ok(vec![self.decrypt(rng, Envelope::from(flattened))?])
+    }
+}
+
+/// Begins decryption for every recipient whose CEK can be determined.
+///
+/// This fails, returning the last error, if there is no such recipient.
+impl<'a, T: DecryptingKey<'a, Envelope<'a>>> DecryptingKey<'a, &'a General> for T
+where
+    <T::Decryptor as Decryptor<'a>>::FinishError: Default,
+    T::StartError: Default,
+{
+    type StartError = T::StartError;
+    type Decryptor = Vec<T::Decryptor>;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        general: &'a General,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        let mut all = Vec::new();
+        let mut last = T::StartError::default();
+
+        for envelope in general.envelopes() {
+            match self.decrypt(rng, envelope) {
+                Ok(x) => all.push(x),
+                Err(e) => last = e,
+            }
+        }
+
+        if all.is_empty() {
+            return Err(last);
+        }
+
+        Ok(all)
+    }
+}
+
+impl<'a, T, D, E> DecryptingKey<'a, &'a Jwe> for T
+where
+    T: DecryptingKey<'a, &'a Flattened, Decryptor = D, StartError = E>,
+    T: DecryptingKey<'a, &'a General, Decryptor = D, StartError = E>,
+    E: From<D::Error>,
+    D: Decryptor<'a>,
+{
+    type StartError = E;
+    type Decryptor = D;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        jwe: &'a Jwe,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        match jwe {
+            Jwe::General(general) => self.decrypt(rng, general),
+            Jwe::Flattened(flattened) => self.decrypt(rng, flattened),
+        }
+    }
+}
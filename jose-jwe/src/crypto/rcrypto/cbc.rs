@@ -0,0 +1,231 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+
+use alloc::{boxed::Box, vec::Vec};
+
+use aes::{Aes128, Aes192, Aes256};
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{Block, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use cbc::{Decryptor, Encryptor};
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha384, Sha512};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use jose_jwa::Encryption;
+
+use super::{cek::Seal, Error};
+
+const BLOCK: usize = 16;
+
+/// Computes the authentication tag (RFC 7518 Section 5.2.2.1).
+///
+/// The MAC covers `AAD || IV || ciphertext || AL`, where `AL` is the length
+/// of the AAD in bits as a 64-bit big-endian integer. The tag is the first
+/// half of the MAC output, i.e. the same length as the MAC key.
+fn tag<M: Mac + KeyInit>(key: &[u8], iv: &[u8], aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+    let al = (aad.len() as u64).checked_mul(8).ok_or(Error::Invalid)?;
+
+    let mut mac = <M as KeyInit>::new_from_slice(key).map_err(|_| Error::Invalid)?;
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ct);
+    mac.update(&al.to_be_bytes());
+
+    let mut out = mac.finalize().into_bytes().to_vec();
+    out.truncate(key.len());
+    Ok(out)
+}
+
+fn seal<C, M>(key: &[u8], iv: &[u8], aad: &[u8], buf: &mut Vec<u8>) -> Result<Vec<u8>, Error>
+where
+    C: KeyIvInit + BlockEncryptMut,
+    M: Mac + KeyInit,
+{
+    if iv.len() != BLOCK {
+        return Err(Error::Invalid);
+    }
+
+    // The first half of the key is for the MAC; the second for encryption.
+    let (mac, enc) = key.split_at(key.len() / 2);
+    let cipher = C::new_from_slices(enc, iv).map_err(|_| Error::Invalid)?;
+
+    // PKCS #7 always adds between 1 and 16 bytes of padding.
+    let len = buf.len();
+    buf.resize(len + BLOCK - len % BLOCK, 0);
+    cipher
+        .encrypt_padded_mut::<Pkcs7>(buf, len)
+        .map_err(|_| Error::Invalid)?;
+
+    tag::<M>(mac, iv, aad, buf)
+}
+
+fn open<C, M>(key: &[u8], iv: &[u8], aad: &[u8], buf: &mut Vec<u8>, tag: &[u8]) -> Result<(), Error>
+where
+    C: KeyIvInit + BlockDecryptMut,
+    M: Mac + KeyInit,
+{
+    if iv.len() != BLOCK {
+        return Err(Error::Invalid);
+    }
+
+    let (mac, enc) = key.split_at(key.len() / 2);
+
+    // Verify the tag before decrypting so that padding errors cannot be used
+    // as an oracle. The comparison itself is constant time.
+    let expected = self::tag::<M>(mac, iv, aad, buf)?;
+    if !bool::from(expected.ct_eq(tag)) {
+        return Err(Error::Invalid);
+    }
+
+    let cipher = C::new_from_slices(enc, iv).map_err(|_| Error::Invalid)?;
+    let len = cipher
+        .decrypt_padded_mut::<Pkcs7>(buf)
+        .map_err(|_| Error::Invalid)?
+        .len();
+
+    buf.truncate(len);
+    Ok(())
+}
+
+/// Streaming AES CBC with HMAC SHA-2 encryption.
+///
+/// Each block of plaintext is encrypted as soon as it is complete, so at most
+/// one block is held back until the end of the stream, when it is padded.
+struct Sealer<C, M> {
+    cipher: C,
+    mac: M,
+    len: usize,
+    al: u64,
+    block: Zeroizing<[u8; BLOCK]>,
+    used: usize,
+}
+
+impl<C, M> Sealer<C, M>
+where
+    C: KeyIvInit + BlockEncryptMut,
+    M: Mac + KeyInit,
+{
+    fn new(key: &[u8], iv: &[u8], aad: &[u8]) -> Result<Self, Error> {
+        if iv.len() != BLOCK {
+            return Err(Error::Invalid);
+        }
+
+        let al = (aad.len() as u64).checked_mul(8).ok_or(Error::Invalid)?;
+        let (mac, enc) = key.split_at(key.len() / 2);
+
+        let mut sealer = Self {
+            cipher: C::new_from_slices(enc, iv).map_err(|_| Error::Invalid)?,
+            mac: <M as KeyInit>::new_from_slice(mac).map_err(|_| Error::Invalid)?,
+            len: mac.len(),
+            al,
+            block: Default::default(),
+            used: 0,
+        };
+
+        sealer.mac.update(aad);
+        sealer.mac.update(iv);
+        Ok(sealer)
+    }
+
+    /// Encrypts the buffered block.
+    fn encrypt(&mut self, out: &mut Vec<u8>) {
+        let mut block = Block::<C>::default();
+        block.copy_from_slice(&self.block[..]);
+        self.cipher.encrypt_block_mut(&mut block);
+
+        self.mac.update(&block);
+        out.extend(block);
+        self.used = 0;
+    }
+}
+
+impl<C, M> Seal for Sealer<C, M>
+where
+    C: KeyIvInit + BlockEncryptMut + Send + Sync,
+    M: Mac + KeyInit + Send + Sync,
+{
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        for byte in chunk {
+            self.block[self.used] = *byte;
+            self.used += 1;
+
+            if self.used == BLOCK {
+                self.encrypt(out);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, out: &mut Vec<u8>) -> Vec<u8> {
+        // PKCS #7 always adds between 1 and 16 bytes of padding.
+        let pad = BLOCK - self.used;
+        self.block[self.used..].fill(pad as u8);
+        self.encrypt(out);
+
+        let Self {
+            mut mac, len, al, ..
+        } = *self;
+        mac.update(&al.to_be_bytes());
+
+        let mut tag = mac.finalize().into_bytes().to_vec();
+        tag.truncate(len);
+        tag
+    }
+}
+
+/// Begins encrypting a stream using AES CBC with HMAC SHA-2.
+pub(super) fn seal_stream(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+) -> Result<Box<dyn Seal>, Error> {
+    type S<C, M> = Sealer<Encryptor<C>, Hmac<M>>;
+
+    Ok(match enc {
+        Encryption::A128CbcHs256 => Box::new(S::<Aes128, Sha256>::new(key, iv, aad)?),
+        Encryption::A192CbcHs384 => Box::new(S::<Aes192, Sha384>::new(key, iv, aad)?),
+        Encryption::A256CbcHs512 => Box::new(S::<Aes256, Sha512>::new(key, iv, aad)?),
+        _ => return Err(Error::AlgMismatch),
+    })
+}
+
+/// Encrypts `buf` in place using AES CBC with HMAC SHA-2, returning the
+/// authentication tag.
+pub(super) fn encrypt(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<Vec<u8>, Error> {
+    match enc {
+        Encryption::A128CbcHs256 => seal::<Encryptor<Aes128>, Hmac<Sha256>>(key, iv, aad, buf),
+        Encryption::A192CbcHs384 => seal::<Encryptor<Aes192>, Hmac<Sha384>>(key, iv, aad, buf),
+        Encryption::A256CbcHs512 => seal::<Encryptor<Aes256>, Hmac<Sha512>>(key, iv, aad, buf),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Verifies the authentication tag and decrypts `buf` in place using AES CBC
+/// with HMAC SHA-2.
+pub(super) fn decrypt(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buf: &mut Vec<u8>,
+    tag: &[u8],
+) -> Result<(), Error> {
+    match enc {
+        Encryption::A128CbcHs256 => open::<Decryptor<Aes128>, Hmac<Sha256>>(key, iv, aad, buf, tag),
+        Encryption::A192CbcHs384 => open::<Decryptor<Aes192>, Hmac<Sha384>>(key, iv, aad, buf, tag),
+        Encryption::A256CbcHs512 => open::<Decryptor<Aes256>, Hmac<Sha512>>(key, iv, aad, buf, tag),
+        _ => Err(Error::AlgMismatch),
+    }
+}
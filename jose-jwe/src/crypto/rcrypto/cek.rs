@@ -0,0 +1,391 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use jose_jwa::{Encryption, KeyManagement};
+use jose_jwk::crypto::{rcrypto::Key, KeyInfo};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::Error;
+#[cfg(feature = "rcrypto-pbes2")]
+use super::Password;
+use crate::Unprotected;
+
+/// Returns the key length (in bytes) used by a content encryption algorithm.
+pub(super) fn key_len(enc: Encryption) -> Result<usize, Error> {
+    match enc {
+        Encryption::A128CbcHs256 => Ok(32),
+        Encryption::A192CbcHs384 => Ok(48),
+        Encryption::A256CbcHs512 => Ok(64),
+        Encryption::A128Gcm => Ok(16),
+        Encryption::A192Gcm => Ok(24),
+        Encryption::A256Gcm => Ok(32),
+        _ => Err(Error::Unsupported),
+    }
+}
+
+/// Returns the initialization vector length (in bytes) used by a content
+/// encryption algorithm.
+fn iv_len(enc: Encryption) -> Result<usize, Error> {
+    match enc {
+        Encryption::A128CbcHs256 => Ok(16),
+        Encryption::A192CbcHs384 => Ok(16),
+        Encryption::A256CbcHs512 => Ok(16),
+        Encryption::A128Gcm => Ok(12),
+        Encryption::A192Gcm => Ok(12),
+        Encryption::A256Gcm => Ok(12),
+        _ => Err(Error::Unsupported),
+    }
+}
+
+/// A streaming content encryption state.
+pub(super) trait Seal: Send + Sync {
+    /// Encrypts the next chunk of plaintext, appending the ciphertext produced
+    /// so far to `out`.
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// Finishes encryption, appending any remaining ciphertext to `out` and
+    /// returning the authentication tag.
+    fn finish(self: Box<Self>, out: &mut Vec<u8>) -> Vec<u8>;
+}
+
+/// Limits applied when decrypting a CEK.
+///
+/// Some key management algorithms take parameters from the (attacker
+/// controlled) JWE header which determine how much work is performed. These
+/// limits bound that work.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Limits {
+    /// The maximum PBES2 iteration count (i.e. `p2c`) accepted.
+    pub p2c: u32,
+
+    /// The maximum size (in bytes) of decompressed plaintext (i.e. `zip`).
+    pub zip: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            p2c: 100_000,
+            zip: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// A Content Encryption Key (CEK)
+///
+/// A CEK is the symmetric key used to encrypt the JWE plaintext. It is bound
+/// to a single content encryption algorithm (i.e. `enc`).
+pub struct Cek {
+    enc: Encryption,
+    key: Zeroizing<Box<[u8]>>,
+}
+
+impl Cek {
+    /// Creates a CEK from existing key material.
+    ///
+    /// This fails if the key length does not match the algorithm.
+    pub fn new(enc: Encryption, key: Zeroizing<Box<[u8]>>) -> Result<Self, Error> {
+        if key.len() != key_len(enc)? {
+            return Err(Error::Invalid);
+        }
+
+        Ok(Self { enc, key })
+    }
+
+    /// Generates a random CEK for the algorithm.
+    pub fn generate(enc: Encryption, rng: &mut (impl CryptoRng + RngCore)) -> Result<Self, Error> {
+        let mut key = Zeroizing::new(vec![0u8; key_len(enc)?].into_boxed_slice());
+        rng.fill_bytes(&mut key);
+        Ok(Self { enc, key })
+    }
+
+    /// The content encryption algorithm this key is used with.
+    pub fn enc(&self) -> Encryption {
+        self.enc
+    }
+
+    /// Generates a random initialization vector suitable for this key.
+    pub fn iv(&self, rng: &mut (impl CryptoRng + RngCore)) -> Vec<u8> {
+        let mut iv = vec![0u8; iv_len(self.enc).unwrap_or_default()];
+        rng.fill_bytes(&mut iv);
+        iv
+    }
+
+    /// Determines the CEK for a direct key management mode.
+    ///
+    /// In these modes (i.e. `dir` and `ECDH-ES`) the CEK is not chosen at
+    /// random but shared with or agreed with the single recipient, and the JWE
+    /// Encrypted Key is empty.
+    /// Any additional header parameters required by the key management
+    /// algorithm are written to `head`. Those supplied by the sender (e.g.
+    /// `apu` and `apv`) are read from it, so it should contain every header
+    /// parameter for the recipient.
+    #[allow(unused_variables)]
+    pub fn agree(
+        key: &Key,
+        alg: KeyManagement,
+        enc: Encryption,
+        head: &mut Unprotected,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Self, Error> {
+        if !key.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        let key = match key {
+            Key::Oct(key) if alg == KeyManagement::Direct => Ok(key.clone()),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(key) => super::ecdh::agree(alg, enc, key, head, rng),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(key) => super::ecdh::agree(alg, enc, key, head, rng),
+
+            _ => Err(Error::Unsupported),
+        }?;
+
+        Self::new(enc, key)
+    }
+
+    /// Encrypts this CEK for a recipient, returning the JWE Encrypted Key.
+    ///
+    /// Any additional header parameters required by the key management
+    /// algorithm are written to `head`. As for [`Cek::agree()`], those
+    /// supplied by the sender are read from it.
+    #[allow(unused_variables)]
+    pub fn wrap(
+        &self,
+        key: &Key,
+        alg: KeyManagement,
+        head: &mut Unprotected,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Vec<u8>, Error> {
+        if !key.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        match key {
+            Key::Oct(kek) => match alg {
+                #[cfg(feature = "rcrypto-aes-kw")]
+                KeyManagement::A128Kw | KeyManagement::A192Kw | KeyManagement::A256Kw => {
+                    super::kw::wrap(kek, &self.key)
+                }
+
+                #[cfg(feature = "rcrypto-aes-gcm")]
+                KeyManagement::A128GcmKw | KeyManagement::A192GcmKw | KeyManagement::A256GcmKw => {
+                    super::gcm::wrap(alg, kek, &self.key, head, rng)
+                }
+
+                _ => Err(Error::Unsupported),
+            },
+
+            #[cfg(feature = "rcrypto-rsa")]
+            Key::Rsa(key) => super::rsa::wrap(alg, key, &self.key, rng),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(key) => super::ecdh::wrap(alg, self.enc, key, &self.key, head, rng),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(key) => super::ecdh::wrap(alg, self.enc, key, &self.key, head, rng),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Unsupported),
+        }
+    }
+
+    /// Encrypts this CEK with a password (i.e. PBES2), returning the JWE
+    /// Encrypted Key.
+    ///
+    /// The generated salt (i.e. `p2s`) and, unless already specified, the
+    /// iteration count (i.e. `p2c`) are written to `head`.
+    #[cfg(feature = "rcrypto-pbes2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rcrypto-pbes2")))]
+    pub fn wrap_password(
+        &self,
+        pw: &Password,
+        alg: KeyManagement,
+        head: &mut Unprotected,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Vec<u8>, Error> {
+        if !pw.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        super::pbes2::wrap(alg, pw, &self.key, head, rng)
+    }
+
+    /// Decrypts the CEK for a recipient from the JWE Encrypted Key.
+    ///
+    /// The `head` parameter contains the header parameters for the recipient
+    /// (i.e. the union of the protected, shared and per-recipient headers).
+    ///
+    /// This uses the default [`Limits`].
+    pub fn unwrap(
+        key: &Key,
+        alg: KeyManagement,
+        enc: Encryption,
+        ekey: &[u8],
+        head: &Unprotected,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Self, Error> {
+        Self::unwrap_with(key, alg, enc, ekey, head, &Limits::default(), rng)
+    }
+
+    /// Decrypts the CEK for a recipient from the JWE Encrypted Key, applying
+    /// the specified limits.
+    ///
+    /// This fails with [`Error::Unsupported`] if the header requests more
+    /// work than the limits allow.
+    #[allow(unused_variables)]
+    pub fn unwrap_with(
+        key: &Key,
+        alg: KeyManagement,
+        enc: Encryption,
+        ekey: &[u8],
+        head: &Unprotected,
+        limits: &Limits,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Self, Error> {
+        if !key.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        let len = key_len(enc)?;
+        let key = match key {
+            Key::Oct(kek) => match alg {
+                // The JWE Encrypted Key must be empty in Direct Encryption mode.
+                KeyManagement::Direct if ekey.is_empty() => Ok(kek.clone()),
+                KeyManagement::Direct => Err(Error::Invalid),
+
+                #[cfg(feature = "rcrypto-aes-kw")]
+                KeyManagement::A128Kw | KeyManagement::A192Kw | KeyManagement::A256Kw => {
+                    super::kw::unwrap(kek, ekey)
+                }
+
+                #[cfg(feature = "rcrypto-aes-gcm")]
+                KeyManagement::A128GcmKw | KeyManagement::A192GcmKw | KeyManagement::A256GcmKw => {
+                    super::gcm::unwrap(alg, kek, ekey, head)
+                }
+
+                _ => Err(Error::Unsupported),
+            },
+
+            #[cfg(feature = "rcrypto-rsa")]
+            Key::Rsa(key) => super::rsa::unwrap(alg, key, ekey, len, rng),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(key) => super::ecdh::unwrap(alg, enc, key, ekey, head),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(key) => super::ecdh::unwrap(alg, enc, key, ekey, head),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Unsupported),
+        }?;
+
+        Self::new(enc, key)
+    }
+
+    /// Decrypts the CEK with a password (i.e. PBES2) from the JWE Encrypted
+    /// Key, applying the specified limits.
+    ///
+    /// This fails with [`Error::Unsupported`] if the iteration count (i.e.
+    /// `p2c`) exceeds [`Limits::p2c`].
+    #[cfg(feature = "rcrypto-pbes2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rcrypto-pbes2")))]
+    pub fn unwrap_password(
+        pw: &Password,
+        alg: KeyManagement,
+        enc: Encryption,
+        ekey: &[u8],
+        head: &Unprotected,
+        limits: &Limits,
+    ) -> Result<Self, Error> {
+        if !pw.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        let key = super::pbes2::unwrap(alg, pw, ekey, head, limits)?;
+        Self::new(enc, key)
+    }
+
+    /// Encrypts `buf` in place, returning the authentication tag.
+    ///
+    /// The `aad` parameter should be the Additional Authenticated Data
+    /// computed from the JWE (see [`crate::Flattened::additional_data()`]).
+    ///
+    /// The buffer is a `Vec` since some algorithms change the length of the
+    /// content (e.g. when padding is applied).
+    #[allow(unused_variables, clippy::ptr_arg)]
+    pub fn encrypt(&self, iv: &[u8], aad: &[u8], buf: &mut Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.enc {
+            #[cfg(feature = "rcrypto-aes-gcm")]
+            Encryption::A128Gcm | Encryption::A192Gcm | Encryption::A256Gcm => {
+                super::gcm::encrypt(self.enc, &self.key, iv, aad, buf)
+            }
+
+            #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+            Encryption::A128CbcHs256 | Encryption::A192CbcHs384 | Encryption::A256CbcHs512 => {
+                super::cbc::encrypt(self.enc, &self.key, iv, aad, buf)
+            }
+
+            _ => Err(Error::Unsupported),
+        }
+    }
+
+    /// Begins encrypting a stream of plaintext.
+    ///
+    /// The `aad` parameter is as for [`Cek::encrypt()`].
+    #[allow(unused_variables)]
+    pub(super) fn seal(&self, iv: &[u8], aad: &[u8]) -> Result<Box<dyn Seal>, Error> {
+        match self.enc {
+            #[cfg(feature = "rcrypto-aes-gcm")]
+            Encryption::A128Gcm | Encryption::A192Gcm | Encryption::A256Gcm => {
+                super::gcm::seal_stream(self.enc, &self.key, iv, aad)
+            }
+
+            #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+            Encryption::A128CbcHs256 | Encryption::A192CbcHs384 | Encryption::A256CbcHs512 => {
+                super::cbc::seal_stream(self.enc, &self.key, iv, aad)
+            }
+
+            _ => Err(Error::Unsupported),
+        }
+    }
+
+    /// Verifies the authentication tag and decrypts `buf` in place.
+    ///
+    /// On failure, the contents of `buf` are unspecified and must not be used.
+    #[allow(unused_variables, clippy::ptr_arg)]
+    pub fn decrypt(
+        &self,
+        iv: &[u8],
+        aad: &[u8],
+        buf: &mut Vec<u8>,
+        tag: &[u8],
+    ) -> Result<(), Error> {
+        match self.enc {
+            #[cfg(feature = "rcrypto-aes-gcm")]
+            Encryption::A128Gcm | Encryption::A192Gcm | Encryption::A256Gcm => {
+                super::gcm::decrypt(self.enc, &self.key, iv, aad, buf, tag)
+            }
+
+            #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+            Encryption::A128CbcHs256 | Encryption::A192CbcHs384 | Encryption::A256CbcHs512 => {
+                super::cbc::decrypt(self.enc, &self.key, iv, aad, buf, tag)
+            }
+
+            _ => Err(Error::Unsupported),
+        }
+    }
+}
+
+impl AsRef<[u8]> for Cek {
+    fn as_ref(&self) -> &[u8] {
+        &self.key
+    }
+}
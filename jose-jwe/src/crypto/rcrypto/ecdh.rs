@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(any(feature = "rcrypto-p256", feature = "rcrypto-p384"))]
+
+use alloc::{boxed::Box, string::String, string::ToString, vec, vec::Vec};
+
+use elliptic_curve::ecdh::diffie_hellman;
+use elliptic_curve::{CurveArithmetic, PublicKey, SecretKey};
+use jose_jwa::{Encryption, KeyManagement};
+use jose_jwk::crypto::rcrypto::Kind;
+use jose_jwk::{Ec, Jwk};
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::{cek::key_len, kw, Error};
+use crate::Unprotected;
+
+type Key<C> = Kind<PublicKey<C>, SecretKey<C>>;
+
+/// Returns the Concat KDF AlgorithmID and the length of the derived key.
+///
+/// In Direct Key Agreement mode the AlgorithmID is the `enc` value.
+/// Otherwise, it is the `alg` value (RFC 7518 Section 4.6.2).
+fn params(alg: KeyManagement, enc: Encryption) -> Result<(String, usize), Error> {
+    match alg {
+        KeyManagement::EcdhEs => Ok((enc.to_string(), key_len(enc)?)),
+        KeyManagement::EcdhEsA128Kw => Ok((alg.to_string(), 16)),
+        KeyManagement::EcdhEsA192Kw => Ok((alg.to_string(), 24)),
+        KeyManagement::EcdhEsA256Kw => Ok((alg.to_string(), 32)),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Derives a key from the shared secret (RFC 7518 Section 4.6.2).
+///
+/// This is the Concat KDF from NIST SP 800-56A using SHA-256, where the
+/// OtherInfo is the concatenation of AlgorithmID, PartyUInfo, PartyVInfo
+/// and SuppPubInfo (the key length in bits).
+fn derive(
+    z: &[u8],
+    alg: KeyManagement,
+    enc: Encryption,
+    head: &Unprotected,
+) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let (id, len) = params(alg, enc)?;
+    let apu = head.apu.as_ref().map(|x| &x[..]).unwrap_or_default();
+    let apv = head.apv.as_ref().map(|x| &x[..]).unwrap_or_default();
+
+    let mut info = Vec::new();
+    for field in [id.as_bytes(), apu, apv] {
+        let n = u32::try_from(field.len()).map_err(|_| Error::Invalid)?;
+        info.extend(n.to_be_bytes());
+        info.extend(field);
+    }
+    info.extend((len as u32 * 8).to_be_bytes());
+
+    let mut key = Zeroizing::new(vec![0u8; len].into_boxed_slice());
+    concat_kdf::derive_key_into::<Sha256>(z, &info, &mut key).map_err(|_| Error::Invalid)?;
+    Ok(key)
+}
+
+/// Performs the sender side of the key agreement.
+///
+/// A new ephemeral key is generated and stored in the `epk` header.
+fn send<C>(
+    alg: KeyManagement,
+    enc: Encryption,
+    key: &Key<C>,
+    head: &mut Unprotected,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Zeroizing<Box<[u8]>>, Error>
+where
+    C: CurveArithmetic,
+    for<'a> Ec: From<&'a PublicKey<C>>,
+{
+    let pk = match key {
+        Kind::Public(key) => *key,
+        Kind::Secret(key) => key.public_key(),
+    };
+
+    let esk = SecretKey::<C>::random(rng);
+    let z = diffie_hellman(esk.to_nonzero_scalar(), pk.as_affine());
+
+    head.epk = Some(Jwk {
+        key: jose_jwk::Key::Ec((&esk.public_key()).into()),
+        prm: Default::default(),
+    });
+
+    derive(z.raw_secret_bytes(), alg, enc, head)
+}
+
+/// Performs the recipient side of the key agreement.
+///
+/// The `epk` header is converted to a point on the recipient's curve. This
+/// fails if it is for a different curve or is not a valid point on the curve,
+/// which prevents invalid curve attacks.
+fn recv<C>(
+    alg: KeyManagement,
+    enc: Encryption,
+    key: &Key<C>,
+    head: &Unprotected,
+) -> Result<Zeroizing<Box<[u8]>>, Error>
+where
+    C: CurveArithmetic,
+    for<'a> PublicKey<C>: TryFrom<&'a Ec, Error = Error>,
+{
+    let sk = match key {
+        Kind::Public(..) => return Err(Error::NotPrivate),
+        Kind::Secret(key) => key,
+    };
+
+    let epk = match head.epk.as_ref().map(|x| &x.key) {
+        Some(jose_jwk::Key::Ec(epk)) => PublicKey::<C>::try_from(epk)?,
+        _ => return Err(Error::Invalid),
+    };
+
+    let z = diffie_hellman(sk.to_nonzero_scalar(), epk.as_affine());
+    derive(z.raw_secret_bytes(), alg, enc, head)
+}
+
+/// Determines the CEK using Direct Key Agreement (i.e. `ECDH-ES`).
+pub(super) fn agree<C>(
+    alg: KeyManagement,
+    enc: Encryption,
+    key: &Key<C>,
+    head: &mut Unprotected,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Zeroizing<Box<[u8]>>, Error>
+where
+    C: CurveArithmetic,
+    for<'a> Ec: From<&'a PublicKey<C>>,
+{
+    match alg {
+        KeyManagement::EcdhEs => send(alg, enc, key, head, rng),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Wraps the CEK using Key Agreement with Key Wrapping (i.e. `ECDH-ES+A*KW`).
+pub(super) fn wrap<C>(
+    alg: KeyManagement,
+    enc: Encryption,
+    key: &Key<C>,
+    cek: &[u8],
+    head: &mut Unprotected,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<u8>, Error>
+where
+    C: CurveArithmetic,
+    for<'a> Ec: From<&'a PublicKey<C>>,
+{
+    match alg {
+        KeyManagement::EcdhEsA128Kw | KeyManagement::EcdhEsA192Kw | KeyManagement::EcdhEsA256Kw => {
+            kw::wrap(&send(alg, enc, key, head, rng)?, cek)
+        }
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Determines the CEK for the recipient of an `ECDH-ES` JWE.
+pub(super) fn unwrap<C>(
+    alg: KeyManagement,
+    enc: Encryption,
+    key: &Key<C>,
+    ekey: &[u8],
+    head: &Unprotected,
+) -> Result<Zeroizing<Box<[u8]>>, Error>
+where
+    C: CurveArithmetic,
+    for<'a> PublicKey<C>: TryFrom<&'a Ec, Error = Error>,
+{
+    match alg {
+        // The JWE Encrypted Key must be empty in Direct Key Agreement mode.
+        KeyManagement::EcdhEs if ekey.is_empty() => recv(alg, enc, key, head),
+        KeyManagement::EcdhEs => Err(Error::Invalid),
+
+        KeyManagement::EcdhEsA128Kw | KeyManagement::EcdhEsA192Kw | KeyManagement::EcdhEsA256Kw => {
+            kw::unwrap(&recv(alg, enc, key, head)?, ekey)
+        }
+
+        _ => Err(Error::AlgMismatch),
+    }
+}
@@ -0,0 +1,258 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-aes-gcm")]
+
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use aes_gcm::aead::consts::{U12, U16};
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::aes::{Aes128, Aes192, Aes256};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, AesGcm};
+use ctr::cipher::{Block, BlockCipher, BlockEncrypt, BlockSizeUser, InnerIvInit, StreamCipher};
+use ctr::Ctr32BE;
+use ghash::universal_hash::UniversalHash;
+use ghash::GHash;
+
+use jose_jwa::{Encryption, KeyManagement};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::{cek::Seal, Error};
+use crate::Unprotected;
+
+type Aes192Gcm = AesGcm<Aes192, U12>;
+
+trait Gcm: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit {}
+impl<T: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit> Gcm for T {}
+
+fn seal<C: Gcm>(key: &[u8], iv: &[u8], aad: &[u8], buf: &mut [u8]) -> Result<Vec<u8>, Error> {
+    let iv: [u8; 12] = iv.try_into().map_err(|_| Error::Invalid)?;
+
+    let cipher = C::new_from_slice(key).map_err(|_| Error::Invalid)?;
+    let tag = cipher
+        .encrypt_in_place_detached(&iv.into(), aad, buf)
+        .map_err(|_| Error::Invalid)?;
+
+    Ok(tag.to_vec())
+}
+
+fn open<C: Gcm>(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buf: &mut [u8],
+    tag: &[u8],
+) -> Result<(), Error> {
+    let iv: [u8; 12] = iv.try_into().map_err(|_| Error::Invalid)?;
+    let tag: [u8; 16] = tag.try_into().map_err(|_| Error::Invalid)?;
+
+    let cipher = C::new_from_slice(key).map_err(|_| Error::Invalid)?;
+    cipher
+        .decrypt_in_place_detached(&iv.into(), aad, buf, &tag.into())
+        .map_err(|_| Error::Invalid)
+}
+
+/// Encrypts `buf` in place using AES GCM, returning the authentication tag.
+pub(super) fn encrypt(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buf: &mut [u8],
+) -> Result<Vec<u8>, Error> {
+    match enc {
+        Encryption::A128Gcm => seal::<Aes128Gcm>(key, iv, aad, buf),
+        Encryption::A192Gcm => seal::<Aes192Gcm>(key, iv, aad, buf),
+        Encryption::A256Gcm => seal::<Aes256Gcm>(key, iv, aad, buf),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Verifies the authentication tag and decrypts `buf` in place using AES GCM.
+pub(super) fn decrypt(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    buf: &mut [u8],
+    tag: &[u8],
+) -> Result<(), Error> {
+    match enc {
+        Encryption::A128Gcm => open::<Aes128Gcm>(key, iv, aad, buf, tag),
+        Encryption::A192Gcm => open::<Aes192Gcm>(key, iv, aad, buf, tag),
+        Encryption::A256Gcm => open::<Aes256Gcm>(key, iv, aad, buf, tag),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+trait Aes: BlockCipher + BlockEncrypt + BlockSizeUser<BlockSize = U16> + KeyInit + Send + Sync {}
+impl<T: BlockCipher + BlockEncrypt + BlockSizeUser<BlockSize = U16> + KeyInit + Send + Sync> Aes
+    for T
+{
+}
+
+/// Streaming AES GCM encryption (NIST SP 800-38D).
+///
+/// This is assembled from its parts, since the AEAD interface only supports
+/// encrypting a message in a single call: the plaintext is encrypted in
+/// counter mode as it arrives, and the ciphertext is authenticated by GHASH.
+struct Sealer<C: Aes> {
+    ctr: Ctr32BE<C>,
+    ghash: GHash,
+    mask: [u8; 16],
+    aad: u64,
+    len: u64,
+    block: [u8; 16],
+    used: usize,
+}
+
+impl<C: Aes> Sealer<C> {
+    fn new(key: &[u8], iv: &[u8], aad: &[u8]) -> Result<Self, Error> {
+        let iv: [u8; 12] = iv.try_into().map_err(|_| Error::Invalid)?;
+        let cipher = C::new_from_slice(key).map_err(|_| Error::Invalid)?;
+
+        // The hash key is the encryption of the zero block.
+        let mut h = Block::<C>::default();
+        cipher.encrypt_block(&mut h);
+        let mut ghash = GHash::new(&h);
+        ghash.update_padded(aad);
+
+        // With a 96-bit IV, the pre-counter block is `IV || 0^31 || 1`. It
+        // masks the tag, and encryption starts with the next counter.
+        let mut j0 = [0u8; 16];
+        j0[..12].copy_from_slice(&iv);
+        j0[15] = 1;
+
+        let mut mask = Block::<C>::from(j0);
+        cipher.encrypt_block(&mut mask);
+
+        j0[15] = 2;
+        Ok(Self {
+            ctr: Ctr32BE::from_core(InnerIvInit::inner_iv_init(cipher, &j0.into())),
+            ghash,
+            mask: mask.into(),
+            aad: aad.len() as u64,
+            len: 0,
+            block: [0; 16],
+            used: 0,
+        })
+    }
+}
+
+impl<C: Aes> Seal for Sealer<C> {
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        let start = out.len();
+        out.extend(chunk);
+
+        let ct = &mut out[start..];
+        self.ctr
+            .try_apply_keystream(ct)
+            .map_err(|_| Error::Invalid)?;
+        self.len += ct.len() as u64;
+
+        // Only whole blocks are authenticated until the end of the stream.
+        for byte in ct.iter() {
+            self.block[self.used] = *byte;
+            self.used += 1;
+
+            if self.used == self.block.len() {
+                self.ghash.update(&[self.block.into()]);
+                self.used = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>, _out: &mut Vec<u8>) -> Vec<u8> {
+        let Self {
+            mut ghash,
+            mask,
+            aad,
+            len,
+            block,
+            used,
+            ..
+        } = *self;
+
+        ghash.update_padded(&block[..used]);
+
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&(aad * 8).to_be_bytes());
+        lengths[8..].copy_from_slice(&(len * 8).to_be_bytes());
+        ghash.update(&[lengths.into()]);
+
+        let mut tag = ghash.finalize().to_vec();
+        for (t, m) in tag.iter_mut().zip(mask) {
+            *t ^= m;
+        }
+
+        tag
+    }
+}
+
+/// Begins encrypting a stream using AES GCM.
+pub(super) fn seal_stream(
+    enc: Encryption,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+) -> Result<Box<dyn Seal>, Error> {
+    Ok(match enc {
+        Encryption::A128Gcm => Box::new(Sealer::<Aes128>::new(key, iv, aad)?),
+        Encryption::A192Gcm => Box::new(Sealer::<Aes192>::new(key, iv, aad)?),
+        Encryption::A256Gcm => Box::new(Sealer::<Aes256>::new(key, iv, aad)?),
+        _ => return Err(Error::AlgMismatch),
+    })
+}
+
+/// Returns the AES GCM variant used for key wrapping.
+fn kw(alg: KeyManagement) -> Result<Encryption, Error> {
+    match alg {
+        KeyManagement::A128GcmKw => Ok(Encryption::A128Gcm),
+        KeyManagement::A192GcmKw => Ok(Encryption::A192Gcm),
+        KeyManagement::A256GcmKw => Ok(Encryption::A256Gcm),
+        _ => Err(Error::AlgMismatch),
+    }
+}
+
+/// Wraps the CEK using AES GCM (RFC 7518 Section 4.7).
+///
+/// A random initialization vector is generated. It and the resulting
+/// authentication tag are stored in the `iv` and `tag` headers.
+pub(super) fn wrap(
+    alg: KeyManagement,
+    kek: &[u8],
+    cek: &[u8],
+    head: &mut Unprotected,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<u8>, Error> {
+    let enc = kw(alg)?;
+
+    let mut iv = vec![0u8; 12];
+    rng.fill_bytes(&mut iv);
+
+    let mut ekey = cek.to_vec();
+    let tag = encrypt(enc, kek, &iv, &[], &mut ekey)?;
+
+    head.iv = Some(iv.into_boxed_slice().into());
+    head.tag = Some(tag.into_boxed_slice().into());
+    Ok(ekey)
+}
+
+/// Unwraps the CEK using AES GCM (RFC 7518 Section 4.7).
+pub(super) fn unwrap(
+    alg: KeyManagement,
+    kek: &[u8],
+    ekey: &[u8],
+    head: &Unprotected,
+) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let enc = kw(alg)?;
+    let iv = head.iv.as_ref().ok_or(Error::Invalid)?;
+    let tag = head.tag.as_ref().ok_or(Error::Invalid)?;
+
+    let mut cek = Zeroizing::new(ekey.to_vec().into_boxed_slice());
+    decrypt(enc, kek, iv, &[], &mut cek, tag)?;
+    Ok(cek)
+}
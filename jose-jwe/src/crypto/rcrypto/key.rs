@@ -0,0 +1,452 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::{boxed::Box, vec::Vec};
+use core::convert::Infallible;
+use core::mem::take;
+
+use jose_b64::serde::{Bytes, Json};
+use jose_b64::stream::Update;
+use jose_jwa::KeyManagement;
+use jose_jwk::crypto::rcrypto::Key;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::{cek::Seal, Cek, Error, Limits};
+use crate::crypto::{DecryptingKey, EncryptingKey, Envelope};
+use crate::{Flattened, Protected, Recipient, Unprotected};
+
+#[cfg(feature = "deflate")]
+use super::zip::{inflate, Deflater};
+#[cfg(feature = "deflate")]
+use crate::Zip;
+
+#[cfg(feature = "rcrypto-pbes2")]
+use super::Password;
+
+/// An error that occurs while streaming a JWE.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamError<E> {
+    /// An error from the output stream.
+    Inner(E),
+
+    /// A cryptographic error.
+    Crypto(Error),
+}
+
+impl From<StreamError<Infallible>> for Error {
+    fn from(value: StreamError<Infallible>) -> Self {
+        match value {
+            StreamError::Inner(x) => match x {},
+            StreamError::Crypto(e) => e,
+        }
+    }
+}
+
+/// A JWE encryptor
+///
+/// The plaintext is encrypted as it is received and the ciphertext is written
+/// to the output stream, which is a `Vec<u8>` by default. So the ciphertext
+/// does not need to be held in memory, use [`Encryptor::stream()`] to write it
+/// elsewhere. For example, wrapping the output in a
+/// [`jose_b64::stream::Encoder`] writes base64url ciphertext, ready for the
+/// compact or JSON serializations.
+///
+/// If the protected header specifies compression (i.e. `zip`), the plaintext
+/// is compressed as it is received.
+pub struct Encryptor<T = Vec<u8>> {
+    seal: Box<dyn Seal>,
+    #[cfg(feature = "deflate")]
+    zip: Option<Deflater>,
+    iv: Vec<u8>,
+    ekey: Vec<u8>,
+    prot: Option<Json<Protected>>,
+    head: Option<Unprotected>,
+    buf: Vec<u8>,
+    next: T,
+}
+
+impl<T: Update> Update for Encryptor<T> {
+    type Error = StreamError<T::Error>;
+
+    fn update(&mut self, chunk: impl AsRef<[u8]>) -> Result<(), Self::Error> {
+        self.process(chunk.as_ref(), false)
+            .map_err(StreamError::Crypto)?;
+        self.next.update(&self.buf).map_err(StreamError::Inner)
+    }
+}
+
+impl Encryptor {
+    /// Writes the ciphertext to another output stream.
+    ///
+    /// Any ciphertext already produced is written to `next` first.
+    pub fn stream<T: Update>(self, mut next: T) -> Result<Encryptor<T>, T::Error> {
+        next.update(&self.next)?;
+
+        Ok(Encryptor {
+            seal: self.seal,
+            #[cfg(feature = "deflate")]
+            zip: self.zip,
+            iv: self.iv,
+            ekey: self.ekey,
+            prot: self.prot,
+            head: self.head,
+            buf: self.buf,
+            next,
+        })
+    }
+}
+
+impl<T: Update> Encryptor<T> {
+    /// Compresses, if required, and encrypts the next chunk of plaintext,
+    /// replacing the contents of the buffer with the ciphertext.
+    #[allow(unused_variables)]
+    fn process(&mut self, chunk: &[u8], last: bool) -> Result<(), Error> {
+        self.buf.clear();
+
+        #[cfg(feature = "deflate")]
+        if let Some(zip) = self.zip.as_mut() {
+            let mut compressed = Zeroizing::new(Vec::new());
+            zip.update(chunk, &mut compressed, last)?;
+            return self.seal.update(&compressed, &mut self.buf);
+        }
+
+        self.seal.update(chunk, &mut self.buf)
+    }
+
+    /// Finish processing the plaintext, returning the JWE and the output
+    /// stream.
+    ///
+    /// The returned JWE has an empty ciphertext, since it has been written
+    /// to the output stream instead.
+    pub fn finish_detached(mut self) -> Result<(Flattened, T), StreamError<T::Error>> {
+        self.process(&[], true).map_err(StreamError::Crypto)?;
+        let tag = self.seal.finish(&mut self.buf);
+        self.next.update(&self.buf).map_err(StreamError::Inner)?;
+
+        let ekey: Bytes = self.ekey.into();
+        let jwe = Flattened {
+            protected: self.prot,
+            unprotected: None,
+            recipient: Recipient {
+                header: self.head,
+                encrypted_key: Some(ekey).filter(|x| !x.is_empty()),
+            },
+            aad: None,
+            iv: Some(self.iv.into()),
+            ciphertext: Vec::new().into(),
+            tag: Some(tag.into()),
+        };
+
+        Ok((jwe, self.next))
+    }
+}
+
+impl crate::crypto::Encryptor for Encryptor {
+    type FinishError = Error;
+
+    fn finish(self) -> Result<Flattened, Self::FinishError> {
+        let (mut jwe, ciphertext) = self.finish_detached()?;
+        jwe.ciphertext = ciphertext.into();
+        Ok(jwe)
+    }
+}
+
+/// A key which determines the CEK
+#[derive(Copy, Clone)]
+enum Kek<'k> {
+    Key(&'k Key),
+
+    #[cfg(feature = "rcrypto-pbes2")]
+    Password(&'k Password),
+}
+
+/// Stores a header parameter generated by key management, if any.
+fn generated<T>(param: &mut Option<T>, value: Option<T>) {
+    if value.is_some() {
+        *param = value;
+    }
+}
+
+/// Begins encrypting a JWE for a single recipient.
+fn encrypt(
+    kek: Kek<'_>,
+    rng: &mut (impl CryptoRng + RngCore),
+    mut prot: Option<Protected>,
+    mut head: Option<Unprotected>,
+) -> Result<Encryptor, Error> {
+    #[cfg(feature = "deflate")]
+    let zip = prot.as_ref().and_then(|x| x.zip).map(|zip| match zip {
+        Zip::Deflate => Deflater::new(),
+    });
+
+    #[cfg(not(feature = "deflate"))]
+    if prot.as_ref().and_then(|x| x.zip).is_some() {
+        return Err(Error::Unsupported);
+    }
+
+    let mut both = match (&prot, &head) {
+        (Some(prot), Some(head)) => prot.oth.clone().union(head),
+        (Some(prot), None) => prot.oth.clone(),
+        (None, Some(head)) => head.clone(),
+        (None, None) => Unprotected::default(),
+    };
+
+    let alg = both.alg.ok_or(Error::Invalid)?;
+    let enc = both.enc.ok_or(Error::Invalid)?;
+    let p2c = both.p2c;
+
+    // Key management reads its parameters (e.g. `apu`) from either header.
+    // Those it generates are always replaced.
+    both.epk = None;
+    both.iv = None;
+    both.tag = None;
+    both.p2s = None;
+
+    let (cek, ekey) = match (kek, alg) {
+        (Kek::Key(key), KeyManagement::Direct | KeyManagement::EcdhEs) => {
+            (Cek::agree(key, alg, enc, &mut both, rng)?, Vec::new())
+        }
+
+        (Kek::Key(key), _) => {
+            let cek = Cek::generate(enc, rng)?;
+            let ekey = cek.wrap(key, alg, &mut both, rng)?;
+            (cek, ekey)
+        }
+
+        #[cfg(feature = "rcrypto-pbes2")]
+        (Kek::Password(pw), _) => {
+            let cek = Cek::generate(enc, rng)?;
+            let ekey = cek.wrap_password(pw, alg, &mut both, rng)?;
+            (cek, ekey)
+        }
+    };
+
+    // The generated parameters are written to the protected header, if any.
+    // A default (i.e. `p2c`) is only written if the caller set it in neither.
+    let params = match prot.as_mut() {
+        Some(prot) => &mut prot.oth,
+        None => head.get_or_insert_with(Default::default),
+    };
+
+    generated(&mut params.epk, both.epk);
+    generated(&mut params.iv, both.iv);
+    generated(&mut params.tag, both.tag);
+    generated(&mut params.p2s, both.p2s);
+    if p2c.is_none() {
+        params.p2c = both.p2c;
+    }
+
+    let prot = match prot {
+        Some(prot) => Some(Json::new(prot).map_err(|_| Error::Invalid)?),
+        None => None,
+    };
+
+    let iv = cek.iv(rng);
+    let aad = crate::additional_data(prot.as_ref(), None);
+
+    Ok(Encryptor {
+        seal: cek.seal(&iv, &aad)?,
+        #[cfg(feature = "deflate")]
+        zip,
+        iv,
+        ekey,
+        prot,
+        head,
+        buf: Vec::new(),
+        next: Vec::new(),
+    })
+}
+
+impl<'a> EncryptingKey<'a> for Key {
+    type StartError = Error;
+    type Encryptor = Encryptor;
+
+    fn encrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        prot: Option<Protected>,
+        head: Option<Unprotected>,
+    ) -> Result<Self::Encryptor, Self::StartError> {
+        encrypt(Kek::Key(self), rng, prot, head)
+    }
+}
+
+#[cfg(feature = "rcrypto-pbes2")]
+impl<'a> EncryptingKey<'a> for Password {
+    type StartError = Error;
+    type Encryptor = Encryptor;
+
+    fn encrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        prot: Option<Protected>,
+        head: Option<Unprotected>,
+    ) -> Result<Self::Encryptor, Self::StartError> {
+        encrypt(Kek::Password(self), rng, prot, head)
+    }
+}
+
+/// A JWE decryptor
+///
+/// No plaintext is released until the authentication tag has been verified,
+/// which is only possible once all of the ciphertext has been received. The
+/// ciphertext is therefore buffered in memory and decrypted when the
+/// decryptor is finished, so decryption requires memory proportional to the
+/// size of the JWE.
+///
+/// To decrypt base64url ciphertext, wrap the decryptor in a
+/// [`jose_b64::stream::Decoder`].
+///
+/// If the protected header specifies compression (i.e. `zip`), the plaintext
+/// is decompressed after decryption, subject to [`Limits::zip`].
+pub struct Decryptor {
+    cek: Cek,
+    iv: Vec<u8>,
+    aad: Vec<u8>,
+    tag: Vec<u8>,
+    buf: Zeroizing<Vec<u8>>,
+    #[cfg(feature = "deflate")]
+    zip: Option<Zip>,
+    #[cfg(feature = "deflate")]
+    max: usize,
+}
+
+impl Update for Decryptor {
+    type Error = Infallible;
+
+    fn update(&mut self, chunk: impl AsRef<[u8]>) -> Result<(), Self::Error> {
+        self.buf.update(chunk)
+    }
+}
+
+impl<'a> crate::crypto::Decryptor<'a> for Decryptor {
+    type FinishError = Error;
+
+    fn finish(mut self) -> Result<Vec<u8>, Self::FinishError> {
+        let mut buf = take(&mut *self.buf);
+        self.cek.decrypt(&self.iv, &self.aad, &mut buf, &self.tag)?;
+
+        #[cfg(feature = "deflate")]
+        if let Some(Zip::Deflate) = self.zip {
+            return inflate(&Zeroizing::new(buf), self.max);
+        }
+
+        Ok(buf)
+    }
+}
+
+/// Begins decrypting a JWE for a recipient.
+fn decrypt(
+    kek: Kek<'_>,
+    limits: &Limits,
+    rng: &mut (impl CryptoRng + RngCore),
+    envelope: Envelope<'_>,
+) -> Result<Decryptor, Error> {
+    // No extensions are understood (RFC 7516 Section 4.1.13).
+    let crit = envelope.protected.and_then(|x| x.crit.as_ref());
+    if crit.map_or(false, |x| !x.is_empty()) {
+        return Err(Error::Unsupported);
+    }
+
+    #[cfg(not(feature = "deflate"))]
+    if envelope.zip().is_some() {
+        return Err(Error::Unsupported);
+    }
+
+    let head = envelope.header();
+    let alg = head.alg.ok_or(Error::Invalid)?;
+    let enc = head.enc.ok_or(Error::Invalid)?;
+
+    let ekey = envelope.recipient.encrypted_key.as_ref();
+    let ekey = ekey.map(|x| &x[..]).unwrap_or_default();
+
+    let cek = match kek {
+        Kek::Key(key) => Cek::unwrap_with(key, alg, enc, ekey, &head, limits, rng)?,
+
+        #[cfg(feature = "rcrypto-pbes2")]
+        Kek::Password(pw) => Cek::unwrap_password(pw, alg, enc, ekey, &head, limits)?,
+    };
+
+    Ok(Decryptor {
+        cek,
+        iv: envelope.iv.map(|x| x.to_vec()).unwrap_or_default(),
+        aad: envelope.additional_data(),
+        tag: envelope.tag.map(|x| x.to_vec()).unwrap_or_default(),
+        buf: Default::default(),
+        #[cfg(feature = "deflate")]
+        zip: envelope.zip(),
+        #[cfg(feature = "deflate")]
+        max: limits.zip,
+    })
+}
+
+impl<'a> DecryptingKey<'a, Envelope<'a>> for Key {
+    type StartError = Error;
+    type Decryptor = Decryptor;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        envelope: Envelope<'a>,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        decrypt(Kek::Key(self), &Limits::default(), rng, envelope)
+    }
+}
+
+#[cfg(feature = "rcrypto-pbes2")]
+impl<'a> DecryptingKey<'a, Envelope<'a>> for Password {
+    type StartError = Error;
+    type Decryptor = Decryptor;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        envelope: Envelope<'a>,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        decrypt(Kek::Password(self), &Limits::default(), rng, envelope)
+    }
+}
+
+/// A decryption key with custom [`Limits`]
+///
+/// Decrypting with a [`Key`] (or a [`Password`]) applies the default limits.
+/// This type allows other limits to be applied instead.
+pub struct Limited<'k, K: ?Sized = Key> {
+    key: &'k K,
+    limits: Limits,
+}
+
+impl<'k, K: ?Sized> Limited<'k, K> {
+    /// Applies the limits to decryption with the key.
+    pub fn new(key: &'k K, limits: Limits) -> Self {
+        Self { key, limits }
+    }
+}
+
+impl<'a> DecryptingKey<'a, Envelope<'a>> for Limited<'_> {
+    type StartError = Error;
+    type Decryptor = Decryptor;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        envelope: Envelope<'a>,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        decrypt(Kek::Key(self.key), &self.limits, rng, envelope)
+    }
+}
+
+#[cfg(feature = "rcrypto-pbes2")]
+impl<'a> DecryptingKey<'a, Envelope<'a>> for Limited<'_, Password> {
+    type StartError = Error;
+    type Decryptor = Decryptor;
+
+    fn decrypt(
+        &'a self,
+        rng: &mut (impl CryptoRng + RngCore),
+        envelope: Envelope<'a>,
+    ) -> Result<Self::Decryptor, Self::StartError> {
+        decrypt(Kek::Password(self.key), &self.limits, rng, envelope)
+    }
+}
@@ -0,0 +1,48 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-aes-kw")]
+
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use aes_kw::{KekAes128, KekAes192, KekAes256};
+use zeroize::Zeroizing;
+
+use super::Error;
+
+/// Wraps the CEK using AES Key Wrap (RFC 3394).
+///
+/// The length of the key encryption key selects the AES variant.
+pub(super) fn wrap(kek: &[u8], cek: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = vec![0u8; cek.len() + 8];
+
+    match kek.len() {
+        16 => KekAes128::try_from(kek).map(|k| k.wrap(cek, &mut out)),
+        24 => KekAes192::try_from(kek).map(|k| k.wrap(cek, &mut out)),
+        32 => KekAes256::try_from(kek).map(|k| k.wrap(cek, &mut out)),
+        _ => return Err(Error::Invalid),
+    }
+    .map_err(|_| Error::Invalid)?
+    .map_err(|_| Error::Invalid)?;
+
+    Ok(out)
+}
+
+/// Unwraps the CEK using AES Key Wrap (RFC 3394).
+///
+/// This fails if the integrity check of the wrapped key fails.
+pub(super) fn unwrap(kek: &[u8], ekey: &[u8]) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let len = ekey.len().checked_sub(8).ok_or(Error::Invalid)?;
+    let mut out = Zeroizing::new(vec![0u8; len].into_boxed_slice());
+
+    match kek.len() {
+        16 => KekAes128::try_from(kek).map(|k| k.unwrap(ekey, &mut out)),
+        24 => KekAes192::try_from(kek).map(|k| k.unwrap(ekey, &mut out)),
+        32 => KekAes256::try_from(kek).map(|k| k.unwrap(ekey, &mut out)),
+        _ => return Err(Error::Invalid),
+    }
+    .map_err(|_| Error::Invalid)?
+    .map_err(|_| Error::Invalid)?;
+
+    Ok(out)
+}
@@ -0,0 +1,25 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Integration with RustCrypto types
+
+#![cfg(feature = "rcrypto")]
+#![cfg_attr(docsrs, doc(cfg(feature = "rcrypto")))]
+
+mod cbc;
+mod cek;
+mod ecdh;
+mod gcm;
+mod key;
+mod kw;
+mod pbes2;
+mod rsa;
+mod zip;
+
+pub use cek::{Cek, Limits};
+pub use jose_jwk::crypto::rcrypto::Error;
+pub use key::{Decryptor, Encryptor, Limited, StreamError};
+
+#[cfg(feature = "rcrypto-pbes2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rcrypto-pbes2")))]
+pub use pbes2::Password;
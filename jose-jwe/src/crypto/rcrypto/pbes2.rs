@@ -0,0 +1,144 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-pbes2")]
+
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
+use jose_jwa::{Algorithm, KeyManagement};
+use jose_jwk::crypto::KeyInfo;
+use pbkdf2::pbkdf2_hmac;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Sha256, Sha384, Sha512};
+use zeroize::Zeroizing;
+
+use super::{kw, Error, Limits};
+use crate::Unprotected;
+
+/// The iteration count used when none is specified.
+///
+/// This is also the most accepted by the default [`Limits`].
+const P2C: u32 = 100_000;
+
+/// The length of the generated salt.
+const P2S: usize = 16;
+
+/// A password for password-based encryption (i.e. PBES2)
+///
+/// A symmetric [`Key`](jose_jwk::crypto::rcrypto::Key) is never used as a
+/// password, so PBES2 always requires this type.
+#[derive(Clone)]
+pub struct Password(Zeroizing<Box<[u8]>>);
+
+impl From<Zeroizing<Box<[u8]>>> for Password {
+    fn from(value: Zeroizing<Box<[u8]>>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[u8]> for Password {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec().into_boxed_slice().into())
+    }
+}
+
+impl KeyInfo for Password {
+    fn strength(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_supported(&self, algo: &Algorithm) -> bool {
+        match algo {
+            Algorithm::KeyManagement(
+                KeyManagement::Pbes2Hs256A128Kw
+                | KeyManagement::Pbes2Hs384A192Kw
+                | KeyManagement::Pbes2Hs512A256Kw,
+            ) => !self.0.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// PBKDF2 with a particular pseudorandom function.
+type Prf = fn(&[u8], &[u8], u32, &mut [u8]);
+
+/// Derives the key encryption key from the password (RFC 7518 Section 4.8).
+///
+/// The PBKDF2 salt is `UTF8(alg) || 0x00 || p2s`.
+fn derive(
+    alg: KeyManagement,
+    pw: &[u8],
+    p2s: &[u8],
+    p2c: u32,
+) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let name = alg.to_string();
+
+    let mut salt = Vec::with_capacity(name.len() + 1 + p2s.len());
+    salt.extend(name.as_bytes());
+    salt.push(0);
+    salt.extend(p2s);
+
+    let (len, prf): (usize, Prf) = match alg {
+        KeyManagement::Pbes2Hs256A128Kw => (16, pbkdf2_hmac::<Sha256>),
+        KeyManagement::Pbes2Hs384A192Kw => (24, pbkdf2_hmac::<Sha384>),
+        KeyManagement::Pbes2Hs512A256Kw => (32, pbkdf2_hmac::<Sha512>),
+        _ => return Err(Error::AlgMismatch),
+    };
+
+    let mut kek = Zeroizing::new(vec![0u8; len].into_boxed_slice());
+    prf(pw, &salt, p2c, &mut kek);
+    Ok(kek)
+}
+
+/// Wraps the CEK using a key derived from the password.
+///
+/// A random salt is generated and stored in the `p2s` header. The iteration
+/// count is taken from the `p2c` header if present; otherwise, a default is
+/// used and stored in the header.
+pub(super) fn wrap(
+    alg: KeyManagement,
+    pw: &Password,
+    cek: &[u8],
+    head: &mut Unprotected,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<u8>, Error> {
+    let p2c = *head.p2c.get_or_insert(P2C);
+    if p2c == 0 {
+        return Err(Error::Invalid);
+    }
+
+    let mut p2s = vec![0u8; P2S];
+    rng.fill_bytes(&mut p2s);
+
+    let kek = derive(alg, &pw.0, &p2s, p2c)?;
+    head.p2s = Some(p2s.into_boxed_slice().into());
+    kw::wrap(&kek, cek)
+}
+
+/// Unwraps the CEK using a key derived from the password.
+///
+/// The `p2c` header is checked against the limits before any key derivation
+/// is performed, since an attacker could otherwise force an arbitrarily large
+/// amount of work.
+pub(super) fn unwrap(
+    alg: KeyManagement,
+    pw: &Password,
+    ekey: &[u8],
+    head: &Unprotected,
+    limits: &Limits,
+) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let p2s = head.p2s.as_ref().ok_or(Error::Invalid)?;
+    let p2c = head.p2c.ok_or(Error::Invalid)?;
+
+    // RFC 7518 Section 4.8.1.1 requires at least 8 octets of salt.
+    if p2s.len() < 8 || p2c == 0 {
+        return Err(Error::Invalid);
+    }
+
+    if p2c > limits.p2c {
+        return Err(Error::Unsupported);
+    }
+
+    let kek = derive(alg, &pw.0, p2s, p2c)?;
+    kw::unwrap(&kek, ekey)
+}
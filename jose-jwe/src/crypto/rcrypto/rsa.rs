@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-rsa")]
+
+use alloc::{boxed::Box, vec::Vec};
+
+use jose_jwa::KeyManagement;
+use jose_jwk::crypto::rcrypto::Kind;
+use rand_core::{CryptoRng, RngCore};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::Error;
+
+type Key = Kind<RsaPublicKey, RsaPrivateKey>;
+
+/// Encrypts the CEK to the RSA public key (RFC 7518 Sections 4.2 and 4.3).
+pub(super) fn wrap(
+    alg: KeyManagement,
+    key: &Key,
+    cek: &[u8],
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<u8>, Error> {
+    let key = match key {
+        Kind::Public(key) => key,
+        Kind::Secret(key) => key.as_ref(),
+    };
+
+    let ekey = match alg {
+        KeyManagement::RsaOaep => key.encrypt(rng, Oaep::new::<Sha1>(), cek),
+        KeyManagement::RsaOaep256 => key.encrypt(rng, Oaep::new::<Sha256>(), cek),
+
+        #[cfg(feature = "rcrypto-rsa1_5")]
+        KeyManagement::Rsa1_5 => key.encrypt(rng, rsa::Pkcs1v15Encrypt, cek),
+
+        #[cfg(not(feature = "rcrypto-rsa1_5"))]
+        KeyManagement::Rsa1_5 => return Err(Error::Unsupported),
+
+        _ => return Err(Error::AlgMismatch),
+    };
+
+    ekey.map_err(|_| Error::Invalid)
+}
+
+/// Decrypts a CEK of `len` bytes using the RSA private key.
+pub(super) fn unwrap(
+    alg: KeyManagement,
+    key: &Key,
+    ekey: &[u8],
+    len: usize,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Zeroizing<Box<[u8]>>, Error> {
+    let key = match key {
+        Kind::Public(..) => return Err(Error::NotPrivate),
+        Kind::Secret(key) => key,
+    };
+
+    let cek = match alg {
+        KeyManagement::RsaOaep => key.decrypt_blinded(rng, Oaep::new::<Sha1>(), ekey),
+        KeyManagement::RsaOaep256 => key.decrypt_blinded(rng, Oaep::new::<Sha256>(), ekey),
+
+        #[cfg(feature = "rcrypto-rsa1_5")]
+        KeyManagement::Rsa1_5 => return Ok(rsa1_5(key, ekey, len, rng)),
+
+        #[cfg(not(feature = "rcrypto-rsa1_5"))]
+        KeyManagement::Rsa1_5 => return Err(Error::Unsupported),
+
+        _ => return Err(Error::AlgMismatch),
+    };
+
+    let cek = Zeroizing::new(cek.map_err(|_| Error::Invalid)?.into_boxed_slice());
+    if cek.len() != len {
+        return Err(Error::Invalid);
+    }
+
+    Ok(cek)
+}
+
+/// Decrypts a CEK using RSAES-PKCS1-v1_5.
+///
+/// In order to mitigate padding oracle attacks, this function never fails.
+/// Instead, when decryption fails or produces a CEK of the wrong length, a
+/// random CEK is substituted (RFC 7516 Section 11.5). The failure then only
+/// becomes observable when the authentication tag fails to verify, which is
+/// indistinguishable from any other tampering with the JWE.
+#[cfg(feature = "rcrypto-rsa1_5")]
+fn rsa1_5(
+    key: &RsaPrivateKey,
+    ekey: &[u8],
+    len: usize,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Zeroizing<Box<[u8]>> {
+    use alloc::vec;
+    use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+    let mut cek = Zeroizing::new(vec![0u8; len].into_boxed_slice());
+    rng.fill_bytes(&mut cek);
+
+    let dec = Zeroizing::new(
+        key.decrypt_blinded(rng, rsa::Pkcs1v15Encrypt, ekey)
+            .unwrap_or_default(),
+    );
+
+    let valid = (dec.len() as u64).ct_eq(&(len as u64));
+    for (i, byte) in cek.iter_mut().enumerate() {
+        byte.conditional_assign(dec.get(i).unwrap_or(&0), valid);
+    }
+
+    cek
+}
@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "deflate")]
+
+use alloc::{boxed::Box, vec::Vec};
+
+use miniz_oxide::deflate::core::CompressorOxide;
+use miniz_oxide::deflate::stream::deflate;
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, TINFLStatus};
+use miniz_oxide::{DataFormat, MZFlush, MZStatus};
+
+use super::Error;
+
+/// The size by which the output grows while compressing.
+const CHUNK: usize = 4096;
+
+/// Streaming DEFLATE compression (RFC 1951).
+pub(super) struct Deflater(Box<CompressorOxide>);
+
+impl Deflater {
+    pub(super) fn new() -> Self {
+        let mut compressor = Box::<CompressorOxide>::default();
+        compressor.set_format_and_level(DataFormat::Raw, 6);
+        Self(compressor)
+    }
+
+    /// Compresses the input, appending the output produced so far to `out`.
+    ///
+    /// When `finish` is set, the stream is completed.
+    pub(super) fn update(
+        &mut self,
+        mut input: &[u8],
+        out: &mut Vec<u8>,
+        finish: bool,
+    ) -> Result<(), Error> {
+        let flush = match finish {
+            true => MZFlush::Finish,
+            false => MZFlush::None,
+        };
+
+        loop {
+            if input.is_empty() && !finish {
+                return Ok(());
+            }
+
+            let len = out.len();
+            out.resize(len + CHUNK, 0);
+
+            let res = deflate(&mut self.0, input, &mut out[len..], flush);
+            out.truncate(len + res.bytes_written);
+            input = &input[res.bytes_consumed..];
+
+            match res.status {
+                Ok(MZStatus::StreamEnd) => return Ok(()),
+                Ok(..) => continue,
+                Err(..) => return Err(Error::Invalid),
+            }
+        }
+    }
+}
+
+/// Decompresses DEFLATE data (RFC 1951).
+///
+/// This fails with [`Error::Unsupported`] if the output would be larger than
+/// `max` bytes, which prevents decompression bombs.
+pub(super) fn inflate(input: &[u8], max: usize) -> Result<Vec<u8>, Error> {
+    decompress_to_vec_with_limit(input, max).map_err(|e| match e.status {
+        TINFLStatus::HasMoreOutput => Error::Unsupported,
+        _ => Error::Invalid,
+    })
+}
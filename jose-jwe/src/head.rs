@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::vec::Vec;
+use alloc::{boxed::Box, string::String};
+
+use jose_b64::base64ct::Base64;
+use jose_b64::serde::Bytes;
+use jose_jwa::{Encryption, KeyManagement};
+use jose_jwk::{Jwk, Thumbprint};
+use serde::{Deserialize, Serialize};
+
+/// The JWE Protected Header
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Protected {
+    /// RFC 7516 Section 4.1.13
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub crit: Option<Vec<String>>,
+
+    /// RFC 7516 Section 4.1.3
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub zip: Option<Zip>,
+
+    /// Other values that may appear in the protected header.
+    #[serde(flatten)]
+    pub oth: Unprotected,
+}
+
+/// The JWE Unprotected Header
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Unprotected {
+    /// RFC 7516 Section 4.1.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub alg: Option<KeyManagement>,
+
+    /// RFC 7516 Section 4.1.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub enc: Option<Encryption>,
+
+    /// RFC 7516 Section 4.1.4
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+    #[cfg(feature = "url")]
+    pub jku: Option<url::Url>,
+
+    /// RFC 7516 Section 4.1.5
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jwk: Option<Jwk>,
+
+    /// RFC 7516 Section 4.1.6
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub kid: Option<String>,
+
+    /// RFC 7516 Section 4.1.7
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+    #[cfg(feature = "url")]
+    pub x5u: Option<url::Url>,
+
+    /// RFC 7516 Section 4.1.8
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub x5c: Option<Vec<Bytes<Box<[u8]>, Base64>>>, // base64, not base64url
+
+    /// RFC 7516 Section 4.1.9-10
+    #[serde(flatten)]
+    pub x5t: Thumbprint,
+
+    /// RFC 7516 Section 4.1.11
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub typ: Option<String>,
+
+    /// RFC 7516 Section 4.1.12
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cty: Option<String>,
+
+    /// RFC 7518 Section 4.6.1.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub epk: Option<Jwk>,
+
+    /// RFC 7518 Section 4.6.1.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub apu: Option<Bytes>,
+
+    /// RFC 7518 Section 4.6.1.3
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub apv: Option<Bytes>,
+
+    /// RFC 7518 Section 4.7.1.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub iv: Option<Bytes>,
+
+    /// RFC 7518 Section 4.7.1.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tag: Option<Bytes>,
+
+    /// RFC 7518 Section 4.8.1.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub p2s: Option<Bytes>,
+
+    /// RFC 7518 Section 4.8.1.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub p2c: Option<u32>,
+}
+
+impl Unprotected {
+    /// Returns the union of two headers.
+    ///
+    /// Where a parameter appears in both headers, the value in `self` is used.
+    /// Since a parameter may not appear in more than one header of a JWE, this
+    /// is only relevant for headers that were not deserialized.
+    pub(crate) fn union(mut self, other: &Self) -> Self {
+        fn or<T: Clone>(x: &mut Option<T>, y: &Option<T>) {
+            if x.is_none() {
+                x.clone_from(y);
+            }
+        }
+
+        or(&mut self.alg, &other.alg);
+        or(&mut self.enc, &other.enc);
+        #[cfg(feature = "url")]
+        or(&mut self.jku, &other.jku);
+        or(&mut self.jwk, &other.jwk);
+        or(&mut self.kid, &other.kid);
+        #[cfg(feature = "url")]
+        or(&mut self.x5u, &other.x5u);
+        or(&mut self.x5c, &other.x5c);
+        or(&mut self.x5t.s1, &other.x5t.s1);
+        or(&mut self.x5t.s256, &other.x5t.s256);
+        or(&mut self.typ, &other.typ);
+        or(&mut self.cty, &other.cty);
+        or(&mut self.epk, &other.epk);
+        or(&mut self.apu, &other.apu);
+        or(&mut self.apv, &other.apv);
+        or(&mut self.iv, &other.iv);
+        or(&mut self.tag, &other.tag);
+        or(&mut self.p2s, &other.p2s);
+        or(&mut self.p2c, &other.p2c);
+        self
+    }
+}
+
+/// Compression algorithms, as defined in RFC 7516 Section 4.1.3.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Zip {
+    /// DEFLATE, as defined in RFC 1951
+    #[serde(rename = "DEF")]
+    Deflate,
+}
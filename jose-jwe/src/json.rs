@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use jose_b64::serde::{Bytes, Json};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::{Flattened, General, Protected, Recipient, Unprotected};
+
+type Header = Map<String, Value>;
+
+/// The wire form of a recipient, retaining the raw header parameter names.
+#[derive(Deserialize)]
+struct RawRecipient {
+    header: Option<Header>,
+    encrypted_key: Option<Bytes>,
+}
+
+#[derive(Deserialize)]
+struct RawGeneral {
+    protected: Option<Json<Protected>>,
+    unprotected: Option<Header>,
+    recipients: Vec<RawRecipient>,
+    aad: Option<Bytes>,
+    iv: Option<Bytes>,
+    ciphertext: Bytes,
+    tag: Option<Bytes>,
+}
+
+#[derive(Deserialize)]
+struct RawFlattened {
+    protected: Option<Json<Protected>>,
+    unprotected: Option<Header>,
+    #[serde(flatten)]
+    recipient: RawRecipient,
+    aad: Option<Bytes>,
+    iv: Option<Bytes>,
+    ciphertext: Bytes,
+    tag: Option<Bytes>,
+}
+
+/// Parses the raw names of the protected header parameters.
+fn names<E: Error>(prot: Option<&Json<Protected>>) -> Result<Header, E> {
+    match prot {
+        Some(prot) => serde_json::from_slice(prot.as_ref()).map_err(E::custom),
+        None => Ok(Header::new()),
+    }
+}
+
+/// Ensures the header parameter names are disjoint (RFC 7516 Section 7.2.1).
+fn disjoint<E: Error>(
+    prot: &Header,
+    shared: Option<&Header>,
+    recip: Option<&Header>,
+) -> Result<(), E> {
+    let mut seen = BTreeSet::new();
+
+    let shared = shared.into_iter().flat_map(|x| x.keys());
+    let recip = recip.into_iter().flat_map(|x| x.keys());
+    for name in prot.keys().chain(shared).chain(recip) {
+        if !seen.insert(name) {
+            return Err(E::custom(alloc::format!(
+                "duplicate header parameter: {name}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn typed<E: Error>(header: Option<Header>) -> Result<Option<Unprotected>, E> {
+    header
+        .map(|x| serde_json::from_value(Value::Object(x)).map_err(E::custom))
+        .transpose()
+}
+
+impl<'de> Deserialize<'de> for Recipient {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawRecipient::deserialize(deserializer)?;
+
+        Ok(Self {
+            header: typed(raw.header)?,
+            encrypted_key: raw.encrypted_key,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for General {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawGeneral::deserialize(deserializer)?;
+
+        let prot = names(raw.protected.as_ref())?;
+        let mut recipients = Vec::with_capacity(raw.recipients.len());
+        for recipient in raw.recipients {
+            disjoint(&prot, raw.unprotected.as_ref(), recipient.header.as_ref())?;
+            recipients.push(Recipient {
+                header: typed(recipient.header)?,
+                encrypted_key: recipient.encrypted_key,
+            });
+        }
+
+        // Check the shared headers even when there are no recipients.
+        disjoint(&prot, raw.unprotected.as_ref(), None)?;
+
+        Ok(Self {
+            protected: raw.protected,
+            unprotected: typed(raw.unprotected)?,
+            recipients,
+            aad: raw.aad,
+            iv: raw.iv,
+            ciphertext: raw.ciphertext,
+            tag: raw.tag,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Flattened {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawFlattened::deserialize(deserializer)?;
+
+        let prot = names(raw.protected.as_ref())?;
+        disjoint(
+            &prot,
+            raw.unprotected.as_ref(),
+            raw.recipient.header.as_ref(),
+        )?;
+
+        Ok(Self {
+            protected: raw.protected,
+            unprotected: typed(raw.unprotected)?,
+            recipient: Recipient {
+                header: typed(raw.recipient.header)?,
+                encrypted_key: raw.recipient.encrypted_key,
+            },
+            aad: raw.aad,
+            iv: raw.iv,
+            ciphertext: raw.ciphertext,
+            tag: raw.tag,
+        })
+    }
+}
@@ -18,66 +18,351 @@
 
 extern crate alloc;
 
-use alloc::string::String;
+pub mod crypto;
 
-/// JSON Web Encryption: a data structure representing an encrypted and
+mod compact;
+mod head;
+mod json;
+
+pub use head::{Protected, Unprotected, Zip};
+
+use alloc::{vec, vec::Vec};
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_b64::serde::{Bytes, Json};
+use serde::Serialize;
+
+/// Computes the Additional Authenticated Data encryption parameter.
+///
+/// This is the ASCII form of the encoded protected header, followed by a
+/// period and the encoded JWE AAD when present (RFC 7516 Section 5.1).
+fn additional_data(protected: Option<&Json<Protected>>, aad: Option<&Bytes>) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    if let Some(x) = protected {
+        data.extend(Base64UrlUnpadded::encode_string(x.as_ref()).as_bytes());
+    }
+
+    if let Some(x) = aad {
+        data.push(b'.');
+        data.extend(Base64UrlUnpadded::encode_string(x).as_bytes());
+    }
+
+    data
+}
+
+/// A JSON Web Encryption representation
+///
+/// A JWE is a data structure representing an encrypted and
 /// integrity-protected message.
-pub struct Jwe {
-    /// One of the JOSE JWE header types; see [`Header`]
-    pub header: Header,
+#[derive(Clone, Debug, Serialize, serde::Deserialize)]
+#[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
+#[serde(untagged)]
+pub enum Jwe {
+    /// General Serialization
+    General(General),
+
+    /// Flattened Serialization
+    Flattened(Flattened),
+}
+
+impl From<General> for Jwe {
+    fn from(value: General) -> Self {
+        Jwe::General(value)
+    }
+}
+
+impl From<Flattened> for Jwe {
+    fn from(value: Flattened) -> Self {
+        Jwe::Flattened(value)
+    }
+}
+
+impl From<Compact> for Jwe {
+    fn from(value: Compact) -> Self {
+        Jwe::Flattened(value.into())
+    }
+}
+
+/// General Serialization
+///
+/// This is the usual JWE JSON form, which allows the content to be encrypted
+/// to multiple recipients.
+///
+/// ```json
+/// {
+///     "protected":"<integrity-protected shared header contents>",
+///     "unprotected":<non-integrity-protected shared header contents>,
+///     "recipients":[
+///      {"header":<per-recipient unprotected header 1 contents>,
+///       "encrypted_key":"<encrypted key 1 contents>"},
+///      ...
+///      {"header":<per-recipient unprotected header N contents>,
+///       "encrypted_key":"<encrypted key N contents>"}],
+///     "aad":"<additional authenticated data contents>",
+///     "iv":"<initialization vector contents>",
+///     "ciphertext":"<ciphertext contents>",
+///     "tag":"<authentication tag contents>"
+/// }
+/// ```
+///
+/// Deserialization fails if a header parameter name appears in more than one
+/// of the protected, shared unprotected and per-recipient headers.
+#[derive(Clone, Debug, Serialize)]
+pub struct General {
+    /// The JWE Protected Header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protected: Option<Json<Protected>>,
+
+    /// The JWE Shared Unprotected Header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unprotected: Option<Unprotected>,
+
+    /// The recipients of the encrypted content.
+    pub recipients: Vec<Recipient>,
+
+    /// The JWE Additional Authenticated Data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aad: Option<Bytes>,
+
+    /// The JWE Initialization Vector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iv: Option<Bytes>,
+
+    /// The JWE Ciphertext
+    pub ciphertext: Bytes,
+
+    /// The JWE Authentication Tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Bytes>,
+}
+
+impl General {
+    /// Returns the Additional Authenticated Data used during encryption.
+    pub fn additional_data(&self) -> Vec<u8> {
+        additional_data(self.protected.as_ref(), self.aad.as_ref())
+    }
+}
+
+impl From<Flattened> for General {
+    fn from(value: Flattened) -> Self {
+        Self {
+            protected: value.protected,
+            unprotected: value.unprotected,
+            recipients: vec![value.recipient],
+            aad: value.aad,
+            iv: value.iv,
+            ciphertext: value.ciphertext,
+            tag: value.tag,
+        }
+    }
+}
+
+impl From<Compact> for General {
+    fn from(value: Compact) -> Self {
+        Flattened::from(value).into()
+    }
+}
+
+/// Flattened Serialization
+///
+/// This is similar to the general serialization but is more compact, only
+/// supporting one recipient.
+///
+/// ```json
+/// {
+///     "protected":"<integrity-protected header contents>",
+///     "unprotected":<non-integrity-protected header contents>,
+///     "header":<more non-integrity-protected header contents>,
+///     "encrypted_key":"<encrypted key contents>",
+///     "aad":"<additional authenticated data contents>",
+///     "iv":"<initialization vector contents>",
+///     "ciphertext":"<ciphertext contents>",
+///     "tag":"<authentication tag contents>"
+/// }
+/// ```
+///
+/// Deserialization fails if a header parameter name appears in more than one
+/// of the protected, shared unprotected and per-recipient headers.
+#[derive(Clone, Debug, Serialize)]
+pub struct Flattened {
+    /// The JWE Protected Header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protected: Option<Json<Protected>>,
+
+    /// The JWE Shared Unprotected Header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unprotected: Option<Unprotected>,
+
+    /// The single recipient of the encrypted content.
+    #[serde(flatten)]
+    pub recipient: Recipient,
+
+    /// The JWE Additional Authenticated Data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aad: Option<Bytes>,
+
+    /// The JWE Initialization Vector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iv: Option<Bytes>,
+
+    /// The JWE Ciphertext
+    pub ciphertext: Bytes,
+
+    /// The JWE Authentication Tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Bytes>,
+}
+
+impl Flattened {
+    /// Returns the Additional Authenticated Data used during encryption.
+    pub fn additional_data(&self) -> Vec<u8> {
+        additional_data(self.protected.as_ref(), self.aad.as_ref())
+    }
+}
+
+/// Converts to the flattened serialization.
+///
+/// This fails, returning the input, unless there is exactly one recipient.
+impl TryFrom<General> for Flattened {
+    type Error = General;
+
+    fn try_from(mut value: General) -> Result<Self, Self::Error> {
+        if value.recipients.len() != 1 {
+            return Err(value);
+        }
+
+        Ok(Self {
+            protected: value.protected,
+            unprotected: value.unprotected,
+            recipient: value.recipients.remove(0),
+            aad: value.aad,
+            iv: value.iv,
+            ciphertext: value.ciphertext,
+            tag: value.tag,
+        })
+    }
+}
+
+impl From<Compact> for Flattened {
+    fn from(value: Compact) -> Self {
+        fn some(bytes: Bytes) -> Option<Bytes> {
+            Some(bytes).filter(|x| !x.is_empty())
+        }
+
+        Self {
+            protected: Some(value.protected),
+            unprotected: None,
+            recipient: Recipient {
+                header: None,
+                encrypted_key: some(value.encrypted_key),
+            },
+            aad: None,
+            iv: some(value.iv),
+            ciphertext: value.ciphertext,
+            tag: some(value.tag),
+        }
+    }
+}
+
+/// A Recipient
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Recipient {
+    /// The JWE Per-Recipient Unprotected Header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<Unprotected>,
+
+    /// The JWE Encrypted Key
+    ///
+    /// Note that for some algorithms, the JWE Encrypted Key value is
+    /// specified as being the empty octet sequence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_key: Option<Bytes>,
+}
+
+/// Compact Serialization
+///
+/// This is the most compact JWE form, which can only represent a single
+/// recipient whose header is entirely integrity protected. It is parsed
+/// from and formatted to its five dot-separated parts using [`FromStr`] and
+/// [`Display`].
+///
+/// [`FromStr`]: core::str::FromStr
+/// [`Display`]: core::fmt::Display
+#[derive(Clone, Debug)]
+pub struct Compact {
+    /// The JWE Protected Header
+    ///
+    /// The originally encoded bytes are retained since the encoded form of
+    /// this header is used as the Additional Authenticated Data.
+    pub protected: Json<Protected>,
 
     /// Encrypted Content Encryption Key value.  Note that for some algorithms,
     /// the JWE Encrypted Key value is specified as being the empty octet
     /// sequence.
-    pub encrypted_key: String,
+    pub encrypted_key: Bytes,
 
     /// Initialization Vector value used when encrypting the plaintext. Note
     /// that some algorithms may not use an Initialization Vector, in which case
     /// this value is the empty octet sequence.
-    pub init_vector: String,
-
-    /// Additional value to be integrity protected by the authenticated
-    /// encryption operation.  This can only be present when using the JWE JSON
-    /// Serialization.  (Note that this can also be achieved when using either
-    /// the JWE Compact Serialization or the JWE JSON Serialization by including
-    /// the AAD value as an integrity-protected Header Parameter value, but at
-    /// the cost of the value being double base64url encoded.)
-    pub aad: Option<String>,
-    
+    pub iv: Bytes,
+
     /// Ciphertext value resulting from authenticated encryption of the
     /// plaintext with Additional Authenticated Data.
-    pub cyphertext: String,
+    pub ciphertext: Bytes,
 
     /// Authentication Tag value resulting from authenticated encryption of the
     /// plaintext with Additional Authenticated Data.
-    pub auth_tag: String,
+    pub tag: Bytes,
 }
 
+impl Compact {
+    /// Returns the Additional Authenticated Data used during encryption.
+    pub fn additional_data(&self) -> Vec<u8> {
+        additional_data(Some(&self.protected), None)
+    }
+}
 
-/// One of the JWE header types
-#[non_exhaustive]
-pub enum Header {
-    /// JWE Protected Header
-    /// 
-    /// JSON object that contains the Header Parameters that are integrity
-    /// protected by the authenticated encryption operation.  These parameters
-    /// apply to all recipients of the JWE.  For the JWE Compact Serialization,
-    /// this comprises the entire JOSE Header.  For the JWE JSON Serialization,
-    /// this is one component of the JOSE Header.
-    Protected(String),
-
-    /// JWE Shared Unprotected Header
-    /// 
-    /// JSON object that contains the Header Parameters that apply to all
-    /// recipients of the JWE that are not integrity protected.  This can only
-    /// be present when using the JWE JSON Serialization.
-    Unprotected(String),
-
-    /// JWE Per-Recipient Unprotected Header
-    /// 
-    /// JSON object that contains Header Parameters that apply to a single
-    /// recipient of the JWE.  These Header Parameter values are not integrity
-    /// protected.  This can only be present when using the JWE JSON
-    /// Serialization.
-    PerRecipientUnprotected(String),
+/// Converts to the compact serialization.
+///
+/// This fails, returning the input, if there is no protected header or if
+/// there are any unprotected headers or additional authenticated data, since
+/// the compact serialization cannot represent them.
+impl TryFrom<Flattened> for Compact {
+    type Error = Flattened;
+
+    fn try_from(value: Flattened) -> Result<Self, Self::Error> {
+        let compatible =
+            value.unprotected.is_none() && value.recipient.header.is_none() && value.aad.is_none();
+
+        let protected = match value.protected {
+            Some(protected) if compatible => protected,
+            protected => return Err(Flattened { protected, ..value }),
+        };
+
+        let empty = || Bytes::from(Vec::new());
+
+        Ok(Self {
+            protected,
+            encrypted_key: value.recipient.encrypted_key.unwrap_or_else(empty),
+            iv: value.iv.unwrap_or_else(empty),
+            ciphertext: value.ciphertext,
+            tag: value.tag.unwrap_or_else(empty),
+        })
+    }
+}
+
+/// Converts to the compact serialization.
+///
+/// This fails, returning the input, unless the value can be converted to the
+/// flattened serialization and from there to the compact serialization.
+impl TryFrom<General> for Compact {
+    type Error = General;
+
+    fn try_from(value: General) -> Result<Self, Self::Error> {
+        Flattened::try_from(value)?
+            .try_into()
+            .map_err(General::from)
+    }
 }
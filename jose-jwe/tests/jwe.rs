@@ -0,0 +1,1499 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+mod rfc7516 {
+    use jose_jwe::{Compact, Flattened, General, Jwe};
+    use serde_json::json;
+
+    const A1: &str = concat!(
+        "eyJhbGciOiJSU0EtT0FFUCIsImVuYyI6IkEyNTZHQ00ifQ.",
+        "OKOawDo13gRp2ojaHV7LFpZcgV7T6DVZKTyKOMTYUmKoTCVJRgckCL9kiMT03JGe",
+        "ipsEdY3mx_etLbbWSrFr05kLzcSr4qKAq7YN7e9jwQRb23nfa6c9d-StnImGyFDb",
+        "Sv04uVuxIp5Zms1gNxKKK2Da14B8S4rzVRltdYwam_lDp5XnZAYpQdb76FdIKLaV",
+        "mqgfwX7XWRxv2322i-vDxRfqNzo_tETKzpVLzfiwQyeyPGLBIO56YJ7eObdv0je8",
+        "1860ppamavo35UgoRdbYaBcoh9QcfylQr66oc6vFWXRcZ_ZT2LawVCWTIy3brGPi",
+        "6UklfCpIMfIjf7iGdXKHzg.",
+        "48V1_ALb6US04U3b.",
+        "5eym8TW_c8SuK0ltJ3rpYIzOeDQz7TALvtu6UG9oMo4vpzs9tX_EFShS8iB7j6ji",
+        "SdiwkIr3ajwQzaBtQD_A.",
+        "XFBoMYUZodetZdvTiFvSkQ",
+    );
+
+    #[test]
+    fn a1_compact() {
+        let jwe: Compact = A1.parse().unwrap();
+
+        assert_eq!(
+            jwe.protected.as_ref(),
+            br#"{"alg":"RSA-OAEP","enc":"A256GCM"}"#
+        );
+        assert_eq!(jwe.encrypted_key.len(), 256);
+        assert_eq!(
+            &**jwe.iv,
+            &[227, 197, 117, 252, 2, 219, 233, 68, 180, 225, 77, 219]
+        );
+        assert_eq!(
+            &**jwe.tag,
+            &[92, 80, 104, 49, 133, 25, 161, 215, 173, 101, 219, 211, 136, 91, 210, 145]
+        );
+
+        assert_eq!(jwe.to_string(), A1);
+    }
+
+    #[test]
+    fn compact_length() {
+        assert!("a.b.c.d".parse::<Compact>().is_err());
+        assert!(format!("{A1}.").parse::<Compact>().is_err());
+    }
+
+    fn a4() -> serde_json::Value {
+        json!({
+            "protected": "eyJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+            "unprotected": { "jku": "https://server.example.com/keys.jwks" },
+            "recipients": [
+                {
+                    "header": { "alg": "RSA1_5", "kid": "2011-04-29" },
+                    "encrypted_key": concat!(
+                        "UGhIOguC7IuEvf_NPVaXsGMoLOmwvc1GyqlIKOK1nN94nHPoltGRhWhw7Zx0-",
+                        "kFm1NJn8LE9XShH59_i8J0PH5ZZyNfGy2xGdULU7sHNF6Gp2vPLgNZ__deLKx",
+                        "GHZ7PcHALUzoOegEI-8E66jX2E4zyJKx-YxzZIItRzC5hlRirb6Y5Cl_p-ko3",
+                        "YvkkysZIFNPccxRU7qve1WYPxqbb2Yw8kZqa2rMWI5ng8OtvzlV7elprCbuPh",
+                        "cCdZ6XDP0_F8rkXds2vE4X-ncOIM8hAYHHi29NX0mcKiRaD0-D-ljQTP-cFPg",
+                        "wCp6X-nZZd9OHBv-B3oWh2TbqmScqXMR4gp_A",
+                    ),
+                },
+                {
+                    "header": { "alg": "A128KW", "kid": "7" },
+                    "encrypted_key": "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ",
+                }
+            ],
+            "iv": "AxY8DCtDaGlsbGljb3RoZQ",
+            "ciphertext": "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY",
+            "tag": "Mz-VPPyU4RlcuYv1IwIvzw",
+        })
+    }
+
+    #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+    const A2_CEK: [u8; 32] = [
+        4, 211, 31, 197, 84, 157, 252, 254, 11, 100, 157, 250, 63, 170, 106, 206, 107, 124, 212,
+        45, 111, 107, 9, 219, 200, 177, 0, 240, 143, 156, 44, 207,
+    ];
+
+    #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+    fn cbc_decrypt(cek: &[u8], aad: &[u8], iv: &[u8], ct: &[u8], tag: &[u8]) {
+        use jose_jwa::Encryption;
+        use jose_jwe::crypto::rcrypto::Cek;
+
+        let cek = Cek::new(
+            Encryption::A128CbcHs256,
+            cek.to_vec().into_boxed_slice().into(),
+        );
+        let cek = cek.unwrap();
+
+        let mut buf = ct.to_vec();
+        cek.decrypt(iv, aad, &mut buf, tag).unwrap();
+        assert_eq!(buf, b"Live long and prosper.");
+
+        let tagged = cek.encrypt(iv, aad, &mut buf).unwrap();
+        assert_eq!(buf, ct);
+        assert_eq!(tagged, tag);
+
+        let mut bad = tag.to_vec();
+        bad[15] ^= 1;
+        assert!(cek.decrypt(iv, aad, &mut buf, &bad).is_err());
+    }
+
+    #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+    #[test]
+    fn a2_decrypt() {
+        let jwe: Flattened = serde_json::from_value(json!({
+            "protected": "eyJhbGciOiJSU0ExXzUiLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+            "iv": "AxY8DCtDaGlsbGljb3RoZQ",
+            "ciphertext": "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY",
+            "tag": "9hH0vgRfYgPnAHOd8stkvw",
+        }))
+        .unwrap();
+
+        let aad = jwe.additional_data();
+        let iv = jwe.iv.unwrap();
+        let tag = jwe.tag.unwrap();
+        cbc_decrypt(&A2_CEK, &aad, &iv, &jwe.ciphertext, &tag);
+    }
+
+    #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+    #[test]
+    fn a3_decrypt() {
+        let jwe: Compact = concat!(
+            "eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.",
+            "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.",
+            "AxY8DCtDaGlsbGljb3RoZQ.",
+            "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.",
+            "U0m_YmjN04DJvceFICbCVQ",
+        )
+        .parse()
+        .unwrap();
+
+        let aad = jwe.additional_data();
+        cbc_decrypt(&A2_CEK, &aad, &jwe.iv, &jwe.ciphertext, &jwe.tag);
+    }
+
+    #[cfg(all(feature = "rcrypto-aes-kw", feature = "rcrypto-aes-cbc-hmac-sha2"))]
+    #[test]
+    fn a3_unwrap() {
+        use jose_jwa::{Encryption, KeyManagement};
+        use jose_jwe::crypto::rcrypto::Cek;
+        use jose_jwk::Jwk;
+
+        let kek: Jwk = serde_json::from_value(json!({
+            "kty": "oct",
+            "k": "GawgguFyGrWKav7AX4VKUg"
+        }))
+        .unwrap();
+        let kek = jose_jwk::crypto::rcrypto::Key::try_from(&kek.key).unwrap();
+
+        let jwe: Compact = concat!(
+            "eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.",
+            "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.",
+            "AxY8DCtDaGlsbGljb3RoZQ.",
+            "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.",
+            "U0m_YmjN04DJvceFICbCVQ",
+        )
+        .parse()
+        .unwrap();
+
+        let alg = jwe.protected.oth.alg.unwrap();
+        let enc = jwe.protected.oth.enc.unwrap();
+        assert_eq!(alg, KeyManagement::A128Kw);
+        assert_eq!(enc, Encryption::A128CbcHs256);
+
+        let mut rng = rand::thread_rng();
+        let head = &jwe.protected.oth;
+        let cek = Cek::unwrap(&kek, alg, enc, &jwe.encrypted_key, head, &mut rng).unwrap();
+        assert_eq!(cek.as_ref(), &A2_CEK);
+
+        let mut buf = jwe.ciphertext.to_vec();
+        let aad = jwe.additional_data();
+        cek.decrypt(&jwe.iv, &aad, &mut buf, &jwe.tag).unwrap();
+        assert_eq!(buf, b"Live long and prosper.");
+
+        // Wrapping the CEK again is deterministic.
+        let mut head = head.clone();
+        let ekey = cek.wrap(&kek, alg, &mut head, &mut rng).unwrap();
+        assert_eq!(ekey, &**jwe.encrypted_key);
+    }
+
+    #[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+    #[test]
+    fn a4_decrypt() {
+        let jwe: General = serde_json::from_value(a4()).unwrap();
+
+        let aad = jwe.additional_data();
+        let iv = jwe.iv.unwrap();
+        let tag = jwe.tag.unwrap();
+        cbc_decrypt(&A2_CEK, &aad, &iv, &jwe.ciphertext, &tag);
+    }
+
+    #[test]
+    fn a4_general() {
+        let jwe: General = serde_json::from_value(a4()).unwrap();
+
+        assert_eq!(
+            jwe.protected.unwrap().as_ref(),
+            br#"{"enc":"A128CBC-HS256"}"#
+        );
+        assert_eq!(jwe.recipients.len(), 2);
+        assert_eq!(
+            jwe.recipients[0].header.as_ref().unwrap().kid.as_deref(),
+            Some("2011-04-29")
+        );
+        assert_eq!(
+            jwe.recipients[1].header.as_ref().unwrap().kid.as_deref(),
+            Some("7")
+        );
+        assert_eq!(jwe.recipients[1].encrypted_key.as_ref().unwrap().len(), 40);
+        assert!(jwe.aad.is_none());
+
+        let jwe: Jwe = serde_json::from_value(a4()).unwrap();
+        assert!(matches!(jwe, Jwe::General(..)));
+    }
+
+    #[test]
+    fn a5_flattened() {
+        let val = json!({
+            "protected": "eyJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+            "unprotected": { "jku": "https://server.example.com/keys.jwks" },
+            "header": { "alg": "A128KW", "kid": "7" },
+            "encrypted_key": "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ",
+            "iv": "AxY8DCtDaGlsbGljb3RoZQ",
+            "ciphertext": "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY",
+            "tag": "Mz-VPPyU4RlcuYv1IwIvzw",
+        });
+
+        let jwe: Jwe = serde_json::from_value(val.clone()).unwrap();
+        let Jwe::Flattened(jwe) = jwe else {
+            panic!("expected the flattened serialization");
+        };
+
+        assert_eq!(
+            jwe.recipient.header.as_ref().unwrap().kid.as_deref(),
+            Some("7")
+        );
+        assert_eq!(
+            jwe.recipient.header.as_ref().unwrap().alg,
+            Some(jose_jwa::KeyManagement::A128Kw)
+        );
+
+        #[cfg(feature = "url")]
+        assert_eq!(serde_json::to_value(&jwe).unwrap(), val);
+
+        // The unprotected headers cannot be represented in compact form.
+        let jwe = Compact::try_from(jwe).unwrap_err();
+        let general = General::from(jwe);
+        assert_eq!(general.recipients.len(), 1);
+        Flattened::try_from(general).unwrap();
+    }
+
+    #[test]
+    fn a4_to_flattened() {
+        let jwe: General = serde_json::from_value(a4()).unwrap();
+        let jwe = Flattened::try_from(jwe).unwrap_err();
+        assert!(Compact::try_from(jwe).is_err());
+    }
+
+    #[test]
+    fn compact_conversions() {
+        let jwe: Flattened = A1.parse().unwrap();
+        assert!(jwe.unprotected.is_none());
+        assert!(jwe.recipient.header.is_none());
+        assert_eq!(jwe.recipient.encrypted_key.as_ref().unwrap().len(), 256);
+
+        let val = serde_json::to_value(&jwe).unwrap();
+        assert_eq!(
+            val["protected"],
+            "eyJhbGciOiJSU0EtT0FFUCIsImVuYyI6IkEyNTZHQ00ifQ"
+        );
+        assert_eq!(val["iv"], "48V1_ALb6US04U3b");
+        assert!(val.get("unprotected").is_none());
+
+        let jwe: Flattened = serde_json::from_value(val).unwrap();
+        let jwe = Compact::try_from(General::from(jwe)).unwrap();
+        assert_eq!(jwe.to_string(), A1);
+
+        let jwe: Jwe = A1.parse().unwrap();
+        assert!(matches!(jwe, Jwe::Flattened(..)));
+    }
+
+    #[test]
+    fn duplicate_headers() {
+        // "enc" is in both the protected and per-recipient headers.
+        let val = json!({
+            "protected": "eyJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+            "header": { "alg": "A128KW", "enc": "A128CBC-HS256" },
+            "encrypted_key": "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ",
+            "iv": "AxY8DCtDaGlsbGljb3RoZQ",
+            "ciphertext": "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY",
+            "tag": "Mz-VPPyU4RlcuYv1IwIvzw",
+        });
+        assert!(serde_json::from_value::<Flattened>(val).is_err());
+
+        // "kid" is in both the shared and per-recipient headers.
+        let mut val = a4();
+        val["unprotected"] = json!({ "kid": "7" });
+        assert!(serde_json::from_value::<General>(val).is_err());
+
+        // Unrelated recipients may share header parameter names.
+        let mut val = a4();
+        val["unprotected"] = json!({ "cty": "JWT" });
+        serde_json::from_value::<General>(val).unwrap();
+    }
+}
+
+mod rfc7518 {
+    use jose_jwa::Encryption;
+    use jose_jwe::{Protected, Zip};
+    use jose_jwk::{EcCurves, Key};
+    use serde_json::json;
+
+    #[test]
+    fn c_ecdh_es() {
+        let val = json!({
+            "alg": "ECDH-ES",
+            "enc": "A128GCM",
+            "apu": "QWxpY2U",
+            "apv": "Qm9i",
+            "epk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0",
+                "y": "SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"
+            }
+        });
+
+        let prot: Protected = serde_json::from_value(val).unwrap();
+        assert_eq!(prot.oth.enc, Some(Encryption::A128Gcm));
+        assert_eq!(&**prot.oth.apu.unwrap(), b"Alice");
+        assert_eq!(&**prot.oth.apv.unwrap(), b"Bob");
+
+        let Key::Ec(epk) = prot.oth.epk.unwrap().key else {
+            panic!("expected an elliptic-curve key");
+        };
+        assert_eq!(epk.crv, EcCurves::P256);
+        assert!(epk.d.is_none());
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn c_ecdh_es_derive() {
+        use jose_jwa::KeyManagement;
+        use jose_jwe::crypto::rcrypto::Cek;
+        use jose_jwk::Jwk;
+
+        let bob: Jwk = serde_json::from_value(json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ",
+            "y": "e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck",
+            "d": "VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw"
+        }))
+        .unwrap();
+        let bob = jose_jwk::crypto::rcrypto::Key::try_from(&bob.key).unwrap();
+
+        let prot: Protected = serde_json::from_value(json!({
+            "alg": "ECDH-ES",
+            "enc": "A128GCM",
+            "apu": "QWxpY2U",
+            "apv": "Qm9i",
+            "epk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0",
+                "y": "SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"
+            }
+        }))
+        .unwrap();
+
+        let mut rng = rand::thread_rng();
+        let alg = KeyManagement::EcdhEs;
+        let cek = Cek::unwrap(&bob, alg, Encryption::A128Gcm, &[], &prot.oth, &mut rng).unwrap();
+        assert_eq!(
+            cek.as_ref(),
+            &[86, 170, 141, 234, 248, 35, 109, 32, 92, 34, 40, 205, 113, 167, 16, 26]
+        );
+    }
+
+    #[test]
+    fn pbes2_and_zip() {
+        let val = json!({
+            "enc": "A128CBC-HS256",
+            "zip": "DEF",
+            "cty": "jwk+json",
+            "p2s": "2WCTcJZ1Rvd_CJuJripQ1w",
+            "p2c": 4096,
+        });
+
+        let prot: Protected = serde_json::from_value(val.clone()).unwrap();
+        assert_eq!(prot.zip, Some(Zip::Deflate));
+        assert_eq!(prot.oth.enc, Some(Encryption::A128CbcHs256));
+        assert_eq!(prot.oth.p2s.as_ref().unwrap().len(), 16);
+        assert_eq!(prot.oth.p2c, Some(4096));
+        assert_eq!(serde_json::to_value(&prot).unwrap(), val);
+    }
+}
+
+#[cfg(feature = "rcrypto-aes-gcm")]
+mod gcm {
+    use jose_jwa::Encryption;
+    use jose_jwe::crypto::rcrypto::Cek;
+    use jose_jwe::Flattened;
+    use serde_json::json;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+
+        for enc in [
+            Encryption::A128Gcm,
+            Encryption::A192Gcm,
+            Encryption::A256Gcm,
+        ] {
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+            let iv = cek.iv(&mut rng);
+            assert_eq!(iv.len(), 12);
+
+            let jwe: Flattened = serde_json::from_value(json!({
+                "protected": "eyJlbmMiOiJBMTI4R0NNIn0",
+                "aad": "aGVsbG8",
+                "ciphertext": "",
+            }))
+            .unwrap();
+            let aad = jwe.additional_data();
+            assert_eq!(aad, b"eyJlbmMiOiJBMTI4R0NNIn0.aGVsbG8");
+
+            let mut buf = b"Live long and prosper.".to_vec();
+            let tag = cek.encrypt(&iv, &aad, &mut buf).unwrap();
+            assert_ne!(buf, b"Live long and prosper.");
+
+            let mut bad = tag.clone();
+            bad[0] ^= 1;
+            assert!(cek.decrypt(&iv, &aad, &mut buf.clone(), &bad).is_err());
+            assert!(cek.decrypt(&iv[1..], &aad, &mut buf.clone(), &tag).is_err());
+
+            cek.decrypt(&iv, &aad, &mut buf, &tag).unwrap();
+            assert_eq!(buf, b"Live long and prosper.");
+        }
+    }
+
+    #[test]
+    fn key_length() {
+        assert!(Cek::new(Encryption::A128Gcm, vec![0u8; 16].into_boxed_slice().into()).is_ok());
+        assert!(Cek::new(Encryption::A128Gcm, vec![0u8; 32].into_boxed_slice().into()).is_err());
+        assert!(Cek::new(Encryption::A256Gcm, vec![0u8; 16].into_boxed_slice().into()).is_err());
+    }
+}
+
+#[cfg(feature = "rcrypto-aes-cbc-hmac-sha2")]
+mod cbc {
+    use jose_jwa::Encryption;
+    use jose_jwe::crypto::rcrypto::Cek;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+
+        for enc in [
+            Encryption::A128CbcHs256,
+            Encryption::A192CbcHs384,
+            Encryption::A256CbcHs512,
+        ] {
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+            let iv = cek.iv(&mut rng);
+            assert_eq!(iv.len(), 16);
+
+            // Exercise the empty, partial and full block padding cases.
+            for len in [0, 1, 15, 16, 17, 32] {
+                let plaintext = vec![0x5a; len];
+                let mut buf = plaintext.clone();
+                let tag = cek.encrypt(&iv, b"aad", &mut buf).unwrap();
+                assert_eq!(buf.len(), (len / 16 + 1) * 16);
+                assert_eq!(tag.len(), cek.as_ref().len() / 2);
+
+                assert!(cek.decrypt(&iv, b"aae", &mut buf.clone(), &tag).is_err());
+                assert!(cek
+                    .decrypt(&iv, b"aad", &mut buf.clone(), &tag[1..])
+                    .is_err());
+
+                cek.decrypt(&iv, b"aad", &mut buf, &tag).unwrap();
+                assert_eq!(buf, plaintext);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rcrypto-rsa")]
+mod rsa {
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::{Cek, Error};
+    use jose_jwe::Unprotected;
+    use jose_jwk::crypto::rcrypto::Key;
+    use rsa::RsaPrivateKey;
+
+    fn roundtrip(alg: KeyManagement) {
+        let mut rng = rand::thread_rng();
+
+        let sk = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let pk: Key = sk.to_public_key().into();
+        let sk: Key = sk.into();
+
+        for enc in [Encryption::A128Gcm, Encryption::A256CbcHs512] {
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+
+            let mut head = Unprotected::default();
+            let ekey = cek.wrap(&pk, alg, &mut head, &mut rng).unwrap();
+            assert_eq!(ekey.len(), 256);
+
+            let out = Cek::unwrap(&sk, alg, enc, &ekey, &head, &mut rng).unwrap();
+            assert_eq!(out.as_ref(), cek.as_ref());
+
+            // Wrapping only requires the public part of a private key.
+            let ekey = cek.wrap(&sk, alg, &mut head, &mut rng).unwrap();
+            let out = Cek::unwrap(&sk, alg, enc, &ekey, &head, &mut rng).unwrap();
+            assert_eq!(out.as_ref(), cek.as_ref());
+
+            // Unwrapping requires the private key.
+            let err = Cek::unwrap(&pk, alg, enc, &ekey, &head, &mut rng).err();
+            assert_eq!(err, Some(Error::NotPrivate));
+        }
+    }
+
+    #[test]
+    fn rsa_oaep() {
+        roundtrip(KeyManagement::RsaOaep);
+    }
+
+    #[test]
+    fn rsa_oaep_256() {
+        roundtrip(KeyManagement::RsaOaep256);
+    }
+
+    #[test]
+    fn rsa_oaep_tampered() {
+        let mut rng = rand::thread_rng();
+        let sk: Key = RsaPrivateKey::new(&mut rng, 2048).unwrap().into();
+        let head = Unprotected::default();
+
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let mut head2 = Unprotected::default();
+        let mut ekey = cek
+            .wrap(&sk, KeyManagement::RsaOaep, &mut head2, &mut rng)
+            .unwrap();
+        ekey[0] ^= 1;
+
+        let alg = KeyManagement::RsaOaep;
+        let err = Cek::unwrap(&sk, alg, Encryption::A128Gcm, &ekey, &head, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+
+    #[test]
+    fn small_key() {
+        let mut rng = rand::thread_rng();
+        let sk: Key = RsaPrivateKey::new(&mut rng, 1024).unwrap().into();
+
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let mut head = Unprotected::default();
+        let err = cek
+            .wrap(&sk, KeyManagement::RsaOaep, &mut head, &mut rng)
+            .err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[cfg(not(feature = "rcrypto-rsa1_5"))]
+    #[test]
+    fn rsa1_5_disabled() {
+        let mut rng = rand::thread_rng();
+        let sk: Key = RsaPrivateKey::new(&mut rng, 2048).unwrap().into();
+
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let mut head = Unprotected::default();
+        let err = cek
+            .wrap(&sk, KeyManagement::Rsa1_5, &mut head, &mut rng)
+            .err();
+        assert_eq!(err, Some(Error::Unsupported));
+    }
+
+    #[cfg(feature = "rcrypto-rsa1_5")]
+    #[test]
+    fn rsa1_5() {
+        roundtrip(KeyManagement::Rsa1_5);
+    }
+
+    #[cfg(feature = "rcrypto-rsa1_5")]
+    #[test]
+    fn rsa1_5_countermeasure() {
+        let mut rng = rand::thread_rng();
+        let sk: Key = RsaPrivateKey::new(&mut rng, 2048).unwrap().into();
+        let alg = KeyManagement::Rsa1_5;
+        let head = Unprotected::default();
+
+        // A CEK of the wrong length must not produce a distinct error.
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let mut head2 = Unprotected::default();
+        let ekey = cek.wrap(&sk, alg, &mut head2, &mut rng).unwrap();
+        let out = Cek::unwrap(&sk, alg, Encryption::A256Gcm, &ekey, &head, &mut rng).unwrap();
+        assert_eq!(out.as_ref().len(), 32);
+
+        // Neither may a padding failure.
+        let mut ekey = ekey;
+        ekey[0] ^= 0xff;
+        let out = Cek::unwrap(&sk, alg, Encryption::A128Gcm, &ekey, &head, &mut rng).unwrap();
+        assert_eq!(out.as_ref().len(), 16);
+        assert_ne!(out.as_ref(), cek.as_ref());
+    }
+}
+
+#[cfg(any(feature = "rcrypto-p256", feature = "rcrypto-p384"))]
+mod ecdh {
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::{Cek, Error};
+    use jose_jwe::Unprotected;
+    use jose_jwk::crypto::rcrypto::Key;
+
+    const KW: [KeyManagement; 3] = [
+        KeyManagement::EcdhEsA128Kw,
+        KeyManagement::EcdhEsA192Kw,
+        KeyManagement::EcdhEsA256Kw,
+    ];
+
+    fn roundtrip(sk: Key, pk: Key) {
+        let mut rng = rand::thread_rng();
+        let enc = Encryption::A256Gcm;
+
+        // Direct Key Agreement
+        let mut head = Unprotected {
+            apu: Some(b"Alice".to_vec().into_boxed_slice().into()),
+            apv: Some(b"Bob".to_vec().into_boxed_slice().into()),
+            ..Default::default()
+        };
+        let alg = KeyManagement::EcdhEs;
+        let cek = Cek::agree(&pk, alg, enc, &mut head, &mut rng).unwrap();
+        assert!(head.epk.is_some());
+
+        let out = Cek::unwrap(&sk, alg, enc, &[], &head, &mut rng).unwrap();
+        assert_eq!(out.as_ref(), cek.as_ref());
+
+        // The party info is bound into the derived key.
+        let mut other = head.clone();
+        other.apv = None;
+        let out = Cek::unwrap(&sk, alg, enc, &[], &other, &mut rng).unwrap();
+        assert_ne!(out.as_ref(), cek.as_ref());
+
+        // The JWE Encrypted Key must be empty.
+        let err = Cek::unwrap(&sk, alg, enc, &[0], &head, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        // Key Agreement with Key Wrapping
+        for alg in KW {
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+            let mut head = Unprotected::default();
+            let ekey = cek.wrap(&pk, alg, &mut head, &mut rng).unwrap();
+            assert_eq!(ekey.len(), 40);
+
+            let out = Cek::unwrap(&sk, alg, enc, &ekey, &head, &mut rng).unwrap();
+            assert_eq!(out.as_ref(), cek.as_ref());
+
+            let mut bad = ekey.clone();
+            bad[0] ^= 1;
+            let err = Cek::unwrap(&sk, alg, enc, &bad, &head, &mut rng).err();
+            assert_eq!(err, Some(Error::Invalid));
+
+            // Direct modes can't wrap a CEK and vice versa.
+            let alg = KeyManagement::EcdhEs;
+            assert!(cek.wrap(&pk, alg, &mut head, &mut rng).is_err());
+        }
+
+        // A missing ephemeral key is rejected.
+        let head = Unprotected::default();
+        let err = Cek::unwrap(&sk, KeyManagement::EcdhEs, enc, &[], &head, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn p256() {
+        let sk = p256::SecretKey::random(&mut rand::thread_rng());
+        roundtrip(sk.clone().into(), sk.public_key().into());
+    }
+
+    #[cfg(feature = "rcrypto-p384")]
+    #[test]
+    fn p384() {
+        let sk = p384::SecretKey::random(&mut rand::thread_rng());
+        roundtrip(sk.clone().into(), sk.public_key().into());
+    }
+
+    #[cfg(all(feature = "rcrypto-p256", feature = "rcrypto-p384"))]
+    #[test]
+    fn invalid_curve() {
+        let mut rng = rand::thread_rng();
+        let enc = Encryption::A128Gcm;
+        let alg = KeyManagement::EcdhEs;
+
+        let p256 = p256::SecretKey::random(&mut rng);
+        let p384 = p384::SecretKey::random(&mut rng);
+
+        // An ephemeral key on another curve is rejected.
+        let mut head = Unprotected::default();
+        let pk: Key = p384.public_key().into();
+        Cek::agree(&pk, alg, enc, &mut head, &mut rng).unwrap();
+        let sk: Key = p256.clone().into();
+        let err = Cek::unwrap(&sk, alg, enc, &[], &head, &mut rng).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+
+        // So is an ephemeral key which is not on the curve.
+        let mut head = Unprotected::default();
+        let pk: Key = p256.public_key().into();
+        Cek::agree(&pk, alg, enc, &mut head, &mut rng).unwrap();
+        let jose_jwk::Key::Ec(epk) = &mut head.epk.as_mut().unwrap().key else {
+            panic!("expected an elliptic-curve key");
+        };
+        let mut y = epk.y.to_vec();
+        y[31] ^= 1;
+        epk.y = y.into_boxed_slice().into();
+        let err = Cek::unwrap(&sk, alg, enc, &[], &head, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+}
+
+#[cfg(any(feature = "rcrypto-aes-kw", feature = "rcrypto-aes-gcm"))]
+mod oct {
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::{Cek, Error};
+    use jose_jwe::Unprotected;
+    use jose_jwk::crypto::rcrypto::Key;
+    use rand::RngCore;
+
+    fn roundtrip(alg: KeyManagement, len: usize) {
+        let mut rng = rand::thread_rng();
+
+        let mut kek = vec![0u8; len];
+        rng.fill_bytes(&mut kek);
+        let kek = Key::Oct(kek.into_boxed_slice().into());
+
+        for enc in [Encryption::A128Gcm, Encryption::A256CbcHs512] {
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+
+            let mut head = Unprotected::default();
+            let ekey = cek.wrap(&kek, alg, &mut head, &mut rng).unwrap();
+
+            let out = Cek::unwrap(&kek, alg, enc, &ekey, &head, &mut rng).unwrap();
+            assert_eq!(out.as_ref(), cek.as_ref());
+
+            let mut bad = ekey.clone();
+            bad[0] ^= 1;
+            let err = Cek::unwrap(&kek, alg, enc, &bad, &head, &mut rng).err();
+            assert_eq!(err, Some(Error::Invalid));
+        }
+
+        // The key encryption key must have exactly the right size.
+        let kek = Key::Oct(vec![0u8; len + 8].into_boxed_slice().into());
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let mut head = Unprotected::default();
+        let err = cek.wrap(&kek, alg, &mut head, &mut rng).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[cfg(feature = "rcrypto-aes-kw")]
+    #[test]
+    fn aes_kw() {
+        roundtrip(KeyManagement::A128Kw, 16);
+        roundtrip(KeyManagement::A192Kw, 24);
+        roundtrip(KeyManagement::A256Kw, 32);
+    }
+
+    #[cfg(feature = "rcrypto-aes-kw")]
+    #[test]
+    fn rfc3394_4_1() {
+        let kek = Key::Oct((0u8..16).collect::<Vec<_>>().into_boxed_slice().into());
+        let cek = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+            0xEE, 0xFF,
+        ];
+        let cek = Cek::new(Encryption::A128Gcm, cek.to_vec().into_boxed_slice().into()).unwrap();
+
+        let mut rng = rand::thread_rng();
+        let mut head = Unprotected::default();
+        let ekey = cek
+            .wrap(&kek, KeyManagement::A128Kw, &mut head, &mut rng)
+            .unwrap();
+        assert_eq!(
+            ekey,
+            [
+                0x1F, 0xA6, 0x8B, 0x0A, 0x81, 0x12, 0xB4, 0x47, 0xAE, 0xF3, 0x4B, 0xD8, 0xFB, 0x5A,
+                0x7B, 0x82, 0x9D, 0x3E, 0x86, 0x23, 0x71, 0xD2, 0xCF, 0xE5,
+            ]
+        );
+    }
+
+    #[cfg(feature = "rcrypto-aes-gcm")]
+    #[test]
+    fn aes_gcm_kw() {
+        roundtrip(KeyManagement::A128GcmKw, 16);
+        roundtrip(KeyManagement::A192GcmKw, 24);
+        roundtrip(KeyManagement::A256GcmKw, 32);
+    }
+
+    #[cfg(feature = "rcrypto-aes-gcm")]
+    #[test]
+    fn aes_gcm_kw_headers() {
+        let mut rng = rand::thread_rng();
+        let kek = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let alg = KeyManagement::A128GcmKw;
+        let enc = Encryption::A128Gcm;
+
+        let cek = Cek::generate(enc, &mut rng).unwrap();
+        let mut head = Unprotected::default();
+        let ekey = cek.wrap(&kek, alg, &mut head, &mut rng).unwrap();
+        assert_eq!(ekey.len(), 16);
+        assert_eq!(head.iv.as_ref().unwrap().len(), 12);
+        assert_eq!(head.tag.as_ref().unwrap().len(), 16);
+
+        let json = serde_json::to_value(&head).unwrap();
+        assert!(json.get("iv").is_some());
+        assert!(json.get("tag").is_some());
+
+        // Both headers are required and authenticate the wrapped key.
+        let mut other = head.clone();
+        other.tag = None;
+        let err = Cek::unwrap(&kek, alg, enc, &ekey, &other, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        let mut other = head.clone();
+        other.iv = Some(vec![0u8; 12].into_boxed_slice().into());
+        let err = Cek::unwrap(&kek, alg, enc, &ekey, &other, &mut rng).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+}
+
+#[cfg(feature = "rcrypto-pbes2")]
+mod pbes2 {
+    use jose_b64::serde::Bytes;
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::{Cek, Error, Limits, Password};
+    use jose_jwe::Unprotected;
+    use jose_jwk::crypto::rcrypto::Key;
+    use serde_json::json;
+
+    fn password() -> Password {
+        Password::from(&b"Thus from my lips, by yours, my sin is purged."[..])
+    }
+
+    #[test]
+    fn rfc7517_c() {
+        let head: Unprotected = serde_json::from_value(json!({
+            "alg": "PBES2-HS256+A128KW",
+            "p2s": "2WCTcJZ1Rvd_CJuJripQ1w",
+            "p2c": 4096,
+            "enc": "A128CBC-HS256",
+        }))
+        .unwrap();
+
+        let ekey: Bytes = serde_json::from_value(json!(
+            "TrqXOwuNUfDV9VPTNbyGvEJ9JMjefAVn-TR1uIxR9p6hsRQh9Tk7BA"
+        ))
+        .unwrap();
+
+        let alg = head.alg.unwrap();
+        let enc = head.enc.unwrap();
+
+        let limits = Limits::default();
+        let cek = Cek::unwrap_password(&password(), alg, enc, &ekey, &head, &limits).unwrap();
+        assert_eq!(
+            cek.as_ref(),
+            &[
+                111, 27, 25, 52, 66, 29, 20, 78, 92, 176, 56, 240, 65, 208, 82, 112, 161, 131, 36,
+                55, 202, 236, 185, 172, 129, 23, 153, 194, 195, 48, 253, 182
+            ]
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+        let limits = Limits::default();
+        let key = password();
+
+        for alg in [
+            KeyManagement::Pbes2Hs256A128Kw,
+            KeyManagement::Pbes2Hs384A192Kw,
+            KeyManagement::Pbes2Hs512A256Kw,
+        ] {
+            let enc = Encryption::A128Gcm;
+            let cek = Cek::generate(enc, &mut rng).unwrap();
+
+            let mut head = Unprotected {
+                p2c: Some(1000),
+                ..Default::default()
+            };
+            let ekey = cek.wrap_password(&key, alg, &mut head, &mut rng).unwrap();
+            assert_eq!(head.p2c, Some(1000));
+            assert_eq!(head.p2s.as_ref().unwrap().len(), 16);
+
+            let out = Cek::unwrap_password(&key, alg, enc, &ekey, &head, &limits).unwrap();
+            assert_eq!(out.as_ref(), cek.as_ref());
+
+            // A different iteration count derives a different key.
+            let mut other = head.clone();
+            other.p2c = Some(999);
+            let err = Cek::unwrap_password(&key, alg, enc, &ekey, &other, &limits).err();
+            assert_eq!(err, Some(Error::Invalid));
+        }
+    }
+
+    #[test]
+    fn jwe() {
+        use jose_b64::stream::Update;
+        use jose_jwe::crypto::rcrypto::Limited;
+        use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+        use jose_jwe::Protected;
+
+        let mut rng = rand::thread_rng();
+        let key = password();
+
+        let prot = Protected {
+            oth: Unprotected {
+                alg: Some(KeyManagement::Pbes2Hs256A128Kw),
+                enc: Some(Encryption::A128Gcm),
+                p2c: Some(1000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut enc = key.encrypt(&mut rng, Some(prot), None).unwrap();
+        enc.update(b"Live long and prosper.").unwrap();
+        let jwe = enc.finish().unwrap();
+
+        let dec = key.decrypt(&mut rng, &jwe).unwrap();
+        let out = dec.chain(&jwe.ciphertext).unwrap().finish().unwrap();
+        assert_eq!(out, b"Live long and prosper.");
+
+        let mut limits = Limits::default();
+        limits.p2c = 999;
+        let limited = Limited::new(&key, limits);
+        let err = limited.decrypt(&mut rng, &jwe).err();
+        assert_eq!(err, Some(Error::Unsupported));
+
+        let other = Password::from(&b"Out, damned spot!"[..]);
+        let dec = other.decrypt(&mut rng, &jwe).err();
+        assert_eq!(dec, Some(Error::Invalid));
+    }
+
+    #[test]
+    fn unprotected_p2c() {
+        use jose_b64::stream::Update;
+        use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+        use jose_jwe::Protected;
+
+        let mut rng = rand::thread_rng();
+        let key = password();
+
+        let prot = Protected {
+            oth: Unprotected {
+                alg: Some(KeyManagement::Pbes2Hs256A128Kw),
+                enc: Some(Encryption::A128Gcm),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let head = Unprotected {
+            p2c: Some(1000),
+            ..Default::default()
+        };
+
+        let mut enc = key.encrypt(&mut rng, Some(prot), Some(head)).unwrap();
+        enc.update(b"Live long and prosper.").unwrap();
+        let jwe = enc.finish().unwrap();
+
+        // The iteration count is used and is not duplicated.
+        let prot = jwe.protected.as_ref().unwrap();
+        let head = jwe.recipient.header.as_ref().unwrap();
+        assert_eq!(prot.oth.p2c, None);
+        assert_eq!(head.p2c, Some(1000));
+        assert!(prot.oth.p2s.is_some());
+        assert!(head.p2s.is_none());
+
+        let dec = key.decrypt(&mut rng, &jwe).unwrap();
+        let out = dec.chain(&jwe.ciphertext).unwrap().finish().unwrap();
+        assert_eq!(out, b"Live long and prosper.");
+    }
+
+    #[test]
+    fn oct() {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let alg = KeyManagement::Pbes2Hs256A128Kw;
+        let enc = Encryption::A128Gcm;
+
+        // A symmetric key is not used as a password.
+        let cek = Cek::generate(enc, &mut rng).unwrap();
+        let mut head = Unprotected::default();
+        let err = cek.wrap(&key, alg, &mut head, &mut rng).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+
+        let pw = password();
+        let ekey = cek.wrap_password(&pw, alg, &mut head, &mut rng).unwrap();
+        let err = Cek::unwrap(&key, alg, enc, &ekey, &head, &mut rng).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[test]
+    fn default_p2c() {
+        let mut rng = rand::thread_rng();
+        let cek = Cek::generate(Encryption::A128Gcm, &mut rng).unwrap();
+        let alg = KeyManagement::Pbes2Hs256A128Kw;
+
+        let mut head = Unprotected::default();
+        let pw = password();
+        cek.wrap_password(&pw, alg, &mut head, &mut rng).unwrap();
+        assert!(head.p2c.unwrap() >= 1000);
+        assert!(head.p2c.unwrap() <= Limits::default().p2c);
+    }
+
+    #[test]
+    fn p2c_limit() {
+        let mut rng = rand::thread_rng();
+        let key = password();
+        let alg = KeyManagement::Pbes2Hs256A128Kw;
+        let enc = Encryption::A128Gcm;
+
+        let cek = Cek::generate(enc, &mut rng).unwrap();
+        let mut head = Unprotected {
+            p2c: Some(2000),
+            ..Default::default()
+        };
+        let ekey = cek.wrap_password(&key, alg, &mut head, &mut rng).unwrap();
+
+        let mut limits = Limits::default();
+        limits.p2c = 1999;
+        let err = Cek::unwrap_password(&key, alg, enc, &ekey, &head, &limits).err();
+        assert_eq!(err, Some(Error::Unsupported));
+
+        limits.p2c = 2000;
+        let out = Cek::unwrap_password(&key, alg, enc, &ekey, &head, &limits).unwrap();
+        assert_eq!(out.as_ref(), cek.as_ref());
+
+        // An absurd iteration count is rejected by default without deriving.
+        head.p2c = Some(u32::MAX);
+        let limits = Limits::default();
+        let err = Cek::unwrap_password(&key, alg, enc, &ekey, &head, &limits).err();
+        assert_eq!(err, Some(Error::Unsupported));
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let mut rng = rand::thread_rng();
+        let limits = Limits::default();
+        let key = password();
+        let alg = KeyManagement::Pbes2Hs256A128Kw;
+        let enc = Encryption::A128Gcm;
+
+        let cek = Cek::generate(enc, &mut rng).unwrap();
+        let mut head = Unprotected {
+            p2c: Some(1000),
+            ..Default::default()
+        };
+        let ekey = cek.wrap_password(&key, alg, &mut head, &mut rng).unwrap();
+
+        let mut other = head.clone();
+        other.p2s = Some(vec![0u8; 7].into_boxed_slice().into());
+        let err = Cek::unwrap_password(&key, alg, enc, &ekey, &other, &limits).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        let mut other = head.clone();
+        other.p2s = None;
+        let err = Cek::unwrap_password(&key, alg, enc, &ekey, &other, &limits).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        let mut other = head;
+        other.p2c = Some(0);
+        let err = Cek::unwrap_password(&key, alg, enc, &ekey, &other, &limits).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+}
+
+#[cfg(all(feature = "rcrypto-aes-gcm", feature = "rcrypto-aes-kw"))]
+mod api {
+    use jose_b64::stream::Update;
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::Error;
+    use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+    use jose_jwe::{Compact, Flattened, General, Jwe, Protected, Recipient, Unprotected};
+    use jose_jwk::crypto::rcrypto::Key;
+
+    fn protected(alg: KeyManagement, enc: Encryption) -> Protected {
+        Protected {
+            oth: Unprotected {
+                alg: Some(alg),
+                enc: Some(enc),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn encrypt(key: &Key, alg: KeyManagement, enc: Encryption) -> Flattened {
+        let mut rng = rand::thread_rng();
+        let prot = protected(alg, enc);
+        let mut enc = key.encrypt(&mut rng, Some(prot), None).unwrap();
+        enc.update(b"Live long ").unwrap();
+        enc.update(b"and prosper.").unwrap();
+        enc.finish().unwrap()
+    }
+
+    fn decrypt(key: &Key, jwe: &Flattened) -> Result<Vec<u8>, Error> {
+        let mut rng = rand::thread_rng();
+        key.decrypt(&mut rng, jwe)?.chain(&jwe.ciphertext)?.finish()
+    }
+
+    #[test]
+    fn direct() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let jwe = encrypt(&key, KeyManagement::Direct, Encryption::A128Gcm);
+        assert!(jwe.recipient.encrypted_key.is_none());
+        assert_eq!(decrypt(&key, &jwe).unwrap(), b"Live long and prosper.");
+
+        // The compact serialization round trips.
+        let compact: Compact = jwe.try_into().unwrap();
+        let compact: Compact = compact.to_string().parse().unwrap();
+        let jwe = Flattened::from(compact);
+        assert_eq!(decrypt(&key, &jwe).unwrap(), b"Live long and prosper.");
+
+        // The key must have the length of the CEK.
+        let mut rng = rand::thread_rng();
+        let prot = protected(KeyManagement::Direct, Encryption::A256Gcm);
+        let err = key.encrypt(&mut rng, Some(prot), None).err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+
+    #[test]
+    fn key_wrap() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let jwe = encrypt(&key, KeyManagement::A128Kw, Encryption::A128Gcm);
+        assert_eq!(jwe.recipient.encrypted_key.as_ref().unwrap().len(), 24);
+        assert_eq!(decrypt(&key, &jwe).unwrap(), b"Live long and prosper.");
+
+        let other = Key::Oct(vec![8u8; 16].into_boxed_slice().into());
+        assert_eq!(decrypt(&other, &jwe).err(), Some(Error::Invalid));
+    }
+
+    #[test]
+    fn key_management_params() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let mut rng = rand::thread_rng();
+
+        // With a protected header, the parameters are integrity protected.
+        let jwe = encrypt(&key, KeyManagement::A128GcmKw, Encryption::A128Gcm);
+        assert!(jwe.protected.as_ref().unwrap().oth.tag.is_some());
+        assert!(jwe.recipient.header.is_none());
+
+        // Otherwise, they are added to the unprotected header.
+        let head = protected(KeyManagement::A128GcmKw, Encryption::A128Gcm).oth;
+        let jwe = key
+            .encrypt(&mut rng, None, Some(head))
+            .unwrap()
+            .chain(b"Live long and prosper.")
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert!(jwe.protected.is_none());
+        assert!(jwe.recipient.header.as_ref().unwrap().tag.is_some());
+        assert_eq!(decrypt(&key, &jwe).unwrap(), b"Live long and prosper.");
+    }
+
+    #[test]
+    fn tampered() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let mut jwe = encrypt(&key, KeyManagement::A128Kw, Encryption::A128Gcm);
+        let mut ciphertext = jwe.ciphertext.to_vec();
+        ciphertext[0] ^= 1;
+        jwe.ciphertext = ciphertext.into();
+        assert_eq!(decrypt(&key, &jwe).err(), Some(Error::Invalid));
+    }
+
+    #[test]
+    fn unsupported_crit() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let mut rng = rand::thread_rng();
+
+        let mut prot = protected(KeyManagement::Direct, Encryption::A128Gcm);
+        prot.crit = Some(vec!["exp".into()]);
+        let jwe = key
+            .encrypt(&mut rng, Some(prot), None)
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        assert_eq!(decrypt(&key, &jwe).err(), Some(Error::Unsupported));
+    }
+
+    #[test]
+    fn general() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let jwe = encrypt(&key, KeyManagement::A128Kw, Encryption::A128Gcm);
+
+        // Add a recipient for a different key before the intended one.
+        let mut general = General::from(jwe);
+        let other = Recipient {
+            header: None,
+            encrypted_key: Some(vec![0u8; 24].into()),
+        };
+        general.recipients.insert(0, other);
+        let jwe = Jwe::from(general);
+
+        let mut rng = rand::thread_rng();
+        let plaintext = match &jwe {
+            Jwe::General(general) => key
+                .decrypt(&mut rng, &jwe)
+                .unwrap()
+                .chain(&general.ciphertext)
+                .unwrap()
+                .finish()
+                .unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(plaintext, b"Live long and prosper.");
+
+        // No recipient can be decrypted with an unrelated key.
+        let other = Key::Oct(vec![8u8; 24].into_boxed_slice().into());
+        assert_eq!(
+            other.decrypt(&mut rng, &jwe).err(),
+            Some(Error::AlgMismatch)
+        );
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn ecdh_es() {
+        let sk = p256::SecretKey::random(&mut rand::thread_rng());
+        let pk = Key::from(sk.public_key());
+        let sk = Key::from(sk);
+
+        for alg in [KeyManagement::EcdhEs, KeyManagement::EcdhEsA128Kw] {
+            let jwe = encrypt(&pk, alg, Encryption::A128Gcm);
+            assert!(jwe.protected.as_ref().unwrap().oth.epk.is_some());
+            assert_eq!(decrypt(&sk, &jwe).unwrap(), b"Live long and prosper.");
+            assert_eq!(decrypt(&pk, &jwe).err(), Some(Error::NotPrivate));
+        }
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn ecdh_es_unprotected_party_info() {
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let pk = Key::from(sk.public_key());
+        let sk = Key::from(sk);
+
+        for alg in [KeyManagement::EcdhEs, KeyManagement::EcdhEsA128Kw] {
+            let prot = protected(alg, Encryption::A128Gcm);
+            let head = Unprotected {
+                apu: Some(b"Alice".to_vec().into_boxed_slice().into()),
+                apv: Some(b"Bob".to_vec().into_boxed_slice().into()),
+                ..Default::default()
+            };
+
+            let mut enc = pk.encrypt(&mut rng, Some(prot), Some(head)).unwrap();
+            enc.update(b"Live long and prosper.").unwrap();
+            let mut jwe = enc.finish().unwrap();
+            assert_eq!(decrypt(&sk, &jwe).unwrap(), b"Live long and prosper.");
+
+            // The party info is part of the key derivation.
+            jwe.recipient.header.as_mut().unwrap().apv = None;
+            assert!(decrypt(&sk, &jwe).is_err());
+        }
+    }
+}
+
+#[cfg(all(feature = "rcrypto-aes-gcm", feature = "rcrypto-aes-cbc-hmac-sha2"))]
+mod stream {
+    use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+    use jose_b64::stream::{Decoder, Encoder, Update};
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::{Cek, Error};
+    use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+    use jose_jwe::{Protected, Unprotected};
+    use jose_jwk::crypto::rcrypto::Key;
+    use rand::RngCore;
+
+    const ENCS: [Encryption; 6] = [
+        Encryption::A128Gcm,
+        Encryption::A192Gcm,
+        Encryption::A256Gcm,
+        Encryption::A128CbcHs256,
+        Encryption::A192CbcHs384,
+        Encryption::A256CbcHs512,
+    ];
+
+    fn setup(enc: Encryption) -> (Cek, Key, Protected) {
+        let mut rng = rand::thread_rng();
+        let cek = Cek::generate(enc, &mut rng).unwrap();
+        let key = Key::Oct(cek.as_ref().to_vec().into_boxed_slice().into());
+
+        let prot = Protected {
+            oth: Unprotected {
+                alg: Some(KeyManagement::Direct),
+                enc: Some(enc),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        (cek, key, prot)
+    }
+
+    #[test]
+    fn chunks() {
+        let mut rng = rand::thread_rng();
+        let mut plaintext = vec![0u8; 1000];
+        rng.fill_bytes(&mut plaintext);
+
+        for enc in ENCS {
+            let (cek, key, prot) = setup(enc);
+
+            for len in [0, 1, 15, 16, 17, 1000] {
+                for size in [1, 7, 16, 64] {
+                    let mut e = key.encrypt(&mut rng, Some(prot.clone()), None).unwrap();
+                    for chunk in plaintext[..len].chunks(size) {
+                        e.update(chunk).unwrap();
+                    }
+                    let jwe = e.finish().unwrap();
+
+                    // The result is checked with the one-shot implementation.
+                    let iv = jwe.iv.as_ref().unwrap();
+                    let tag = jwe.tag.as_ref().unwrap();
+                    let mut buf = jwe.ciphertext.to_vec();
+                    cek.decrypt(iv, &jwe.additional_data(), &mut buf, tag)
+                        .unwrap();
+                    assert_eq!(buf, &plaintext[..len], "{enc} {len} {size}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn base64url() {
+        let mut rng = rand::thread_rng();
+        let plaintext = b"Live long and prosper.".repeat(100);
+
+        for enc in ENCS {
+            let (_, key, prot) = setup(enc);
+
+            let mut e = key
+                .encrypt(&mut rng, Some(prot), None)
+                .unwrap()
+                .stream(Encoder::<String>::default())
+                .unwrap();
+            for chunk in plaintext.chunks(10) {
+                e.update(chunk).unwrap();
+            }
+
+            let (mut jwe, encoder) = e.finish_detached().unwrap();
+            let encoded = encoder.finish().unwrap();
+            assert!(jwe.ciphertext.is_empty());
+
+            let ciphertext = Base64UrlUnpadded::decode_vec(&encoded).unwrap();
+            jwe.ciphertext = ciphertext.into();
+
+            let mut d = Decoder::<_, Base64UrlUnpadded>::from(key.decrypt(&mut rng, &jwe).unwrap());
+            for chunk in encoded.as_bytes().chunks(10) {
+                d.update(chunk).unwrap();
+            }
+            assert_eq!(d.finish().unwrap().finish().unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn redirected() {
+        let mut rng = rand::thread_rng();
+        let (_, key, prot) = setup(Encryption::A128CbcHs256);
+
+        // Ciphertext produced before redirecting the output is not lost.
+        let mut e = key.encrypt(&mut rng, Some(prot), None).unwrap();
+        e.update(b"Live long ").unwrap();
+        let mut e = e.stream(Vec::new()).unwrap();
+        e.update(b"and prosper.").unwrap();
+        let (mut jwe, ciphertext) = e.finish_detached().unwrap();
+        jwe.ciphertext = ciphertext.into();
+
+        let plaintext = key
+            .decrypt(&mut rng, &jwe)
+            .unwrap()
+            .chain(&jwe.ciphertext)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!(plaintext, b"Live long and prosper.");
+
+        // Nothing is released if the tag is invalid.
+        let mut tag = jwe.tag.as_ref().unwrap().to_vec();
+        tag[0] ^= 1;
+        jwe.tag = Some(tag.into());
+        let err = key
+            .decrypt(&mut rng, &jwe)
+            .unwrap()
+            .chain(&jwe.ciphertext)
+            .unwrap()
+            .finish()
+            .err();
+        assert_eq!(err, Some(Error::Invalid));
+    }
+}
+
+#[cfg(feature = "rcrypto-aes-gcm")]
+mod zip {
+    use jose_b64::stream::Update;
+    use jose_jwa::{Encryption, KeyManagement};
+    use jose_jwe::crypto::rcrypto::Error;
+    use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+    use jose_jwe::{Flattened, Protected, Unprotected, Zip};
+    use jose_jwk::crypto::rcrypto::Key;
+
+    fn protected(zip: Option<Zip>) -> Protected {
+        Protected {
+            oth: Unprotected {
+                alg: Some(KeyManagement::Direct),
+                enc: Some(Encryption::A128Gcm),
+                ..Default::default()
+            },
+            zip,
+            ..Default::default()
+        }
+    }
+
+    fn encrypt(key: &Key, zip: Option<Zip>, plaintext: &[u8]) -> Result<Flattened, Error> {
+        let mut rng = rand::thread_rng();
+        let mut enc = key.encrypt(&mut rng, Some(protected(zip)), None)?;
+        for chunk in plaintext.chunks(1000) {
+            enc.update(chunk)?;
+        }
+        enc.finish()
+    }
+
+    fn decrypt<'a, K>(key: &'a K, jwe: &'a Flattened) -> Result<Vec<u8>, Error>
+    where
+        K: DecryptingKey<'a, &'a Flattened, StartError = Error>,
+        K::Decryptor: Decryptor<'a, FinishError = Error>,
+        Error: From<<K::Decryptor as Update>::Error>,
+    {
+        let mut rng = rand::thread_rng();
+        key.decrypt(&mut rng, jwe)?.chain(&jwe.ciphertext)?.finish()
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn roundtrip() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let plaintext = b"Live long and prosper. ".repeat(1000);
+
+        let plain = encrypt(&key, None, &plaintext).unwrap();
+        let jwe = encrypt(&key, Some(Zip::Deflate), &plaintext).unwrap();
+        assert!(jwe.ciphertext.len() * 10 < plain.ciphertext.len());
+        assert_eq!(decrypt(&key, &jwe).unwrap(), plaintext);
+
+        // Empty plaintext still produces a valid DEFLATE stream.
+        let jwe = encrypt(&key, Some(Zip::Deflate), b"").unwrap();
+        assert_eq!(decrypt(&key, &jwe).unwrap(), b"");
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn bomb() {
+        use jose_jwe::crypto::rcrypto::{Limited, Limits};
+
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let jwe = encrypt(&key, Some(Zip::Deflate), &[0u8; 1 << 20]).unwrap();
+        assert!(jwe.ciphertext.len() < 2048);
+
+        let mut limits = Limits::default();
+        limits.zip = 1 << 20;
+        let limited = Limited::new(&key, limits);
+        assert_eq!(decrypt(&limited, &jwe).unwrap().len(), 1 << 20);
+
+        limits.zip -= 1;
+        let limited = Limited::new(&key, limits);
+        assert_eq!(decrypt(&limited, &jwe).err(), Some(Error::Unsupported));
+    }
+
+    #[cfg(not(feature = "deflate"))]
+    #[test]
+    fn unsupported() {
+        let key = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let err = encrypt(&key, Some(Zip::Deflate), b"Live long and prosper.").err();
+        assert_eq!(err, Some(Error::Unsupported));
+
+        let mut jwe = encrypt(&key, None, b"Live long and prosper.").unwrap();
+        let prot = protected(Some(Zip::Deflate));
+        jwe.protected = Some(jose_b64::serde::Json::new(prot).unwrap());
+        assert_eq!(decrypt(&key, &jwe).err(), Some(Error::Unsupported));
+    }
+}
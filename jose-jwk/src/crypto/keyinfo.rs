@@ -4,7 +4,9 @@
 use core::ops::Deref;
 
 use alloc::{boxed::Box, vec::Vec};
-use jose_jwa::{Algorithm, Algorithm::Signing, Signing::*};
+use jose_jwa::{
+    Algorithm, Algorithm::KeyManagement, Algorithm::Signing, KeyManagement::*, Signing::*,
+};
 
 use crate::{Ec, EcCurves, Jwk, Key, Oct, Okp, OkpCurves, Rsa};
 
@@ -72,6 +74,13 @@ impl KeyInfo for [u8] {
             (Signing(Hs256), 16..) => true,
             (Signing(Hs384), 24..) => true,
             (Signing(Hs512), 32..) => true,
+            (KeyManagement(Direct), 16..) => true,
+            (KeyManagement(A128Kw), 16) => true,
+            (KeyManagement(A192Kw), 24) => true,
+            (KeyManagement(A256Kw), 32) => true,
+            (KeyManagement(A128GcmKw), 16) => true,
+            (KeyManagement(A192GcmKw), 24) => true,
+            (KeyManagement(A256GcmKw), 32) => true,
             _ => false,
         }
     }
@@ -124,6 +133,9 @@ impl KeyInfo for Ec {
             (EcCurves::P256K, Signing(Es256K)) => true,
             (EcCurves::P384, Signing(Es384)) => true,
             (EcCurves::P521, Signing(Es512)) => true,
+            (EcCurves::P256 | EcCurves::P384 | EcCurves::P521, KeyManagement(alg)) => {
+                matches!(alg, EcdhEs | EcdhEsA128Kw | EcdhEsA192Kw | EcdhEsA256Kw)
+            }
             _ => false,
         }
     }
@@ -140,6 +152,13 @@ impl KeyInfo for Oct {
             (Signing(Hs256), 16..) => true,
             (Signing(Hs384), 24..) => true,
             (Signing(Hs512), 32..) => true,
+            (KeyManagement(Direct), 16..) => true,
+            (KeyManagement(A128Kw), 16) => true,
+            (KeyManagement(A192Kw), 24) => true,
+            (KeyManagement(A256Kw), 32) => true,
+            (KeyManagement(A128GcmKw), 16) => true,
+            (KeyManagement(A192GcmKw), 24) => true,
+            (KeyManagement(A256GcmKw), 32) => true,
             _ => false,
         }
     }
@@ -174,6 +193,9 @@ impl KeyInfo for Rsa {
             (Signing(Ps256), 16..) => true,
             (Signing(Ps384), 24..) => true,
             (Signing(Ps512), 32..) => true,
+            (KeyManagement(Rsa1_5), 16..) => true,
+            (KeyManagement(RsaOaep), 16..) => true,
+            (KeyManagement(RsaOaep256), 16..) => true,
             _ => false,
         }
     }
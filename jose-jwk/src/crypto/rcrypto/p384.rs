@@ -6,7 +6,9 @@
 use p384::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
 use p384::{EncodedPoint, FieldBytes, PublicKey, SecretKey};
 
-use jose_jwa::{Algorithm, Algorithm::Signing, Signing::*};
+use jose_jwa::{
+    Algorithm, Algorithm::KeyManagement, Algorithm::Signing, KeyManagement::*, Signing::*,
+};
 
 use super::super::KeyInfo;
 use super::Error;
@@ -19,7 +21,14 @@ impl KeyInfo for PublicKey {
     }
 
     fn is_supported(&self, algo: &Algorithm) -> bool {
-        matches!(algo, Signing(Es384))
+        matches!(
+            algo,
+            Signing(Es384)
+                | KeyManagement(EcdhEs)
+                | KeyManagement(EcdhEsA128Kw)
+                | KeyManagement(EcdhEsA192Kw)
+                | KeyManagement(EcdhEsA256Kw)
+        )
     }
 }
 
@@ -30,7 +39,14 @@ impl KeyInfo for SecretKey {
     }
 
     fn is_supported(&self, algo: &Algorithm) -> bool {
-        matches!(algo, Signing(Es384))
+        matches!(
+            algo,
+            Signing(Es384)
+                | KeyManagement(EcdhEs)
+                | KeyManagement(EcdhEsA128Kw)
+                | KeyManagement(EcdhEsA192Kw)
+                | KeyManagement(EcdhEsA256Kw)
+        )
     }
 }
 
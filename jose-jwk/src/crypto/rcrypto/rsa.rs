@@ -3,9 +3,12 @@
 
 #![cfg(feature = "rcrypto-rsa")]
 
-use rsa::{BigUint, PublicKeyParts, RsaPrivateKey, RsaPublicKey};
+use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
 
-use jose_jwa::{Algorithm, Algorithm::Signing, Signing::*};
+use jose_jwa::{
+    Algorithm, Algorithm::KeyManagement, Algorithm::Signing, KeyManagement::*, Signing::*,
+};
 
 use super::super::KeyInfo;
 use super::Error;
@@ -35,6 +38,9 @@ impl KeyInfo for RsaPublicKey {
             Signing(Ps256) => true,
             Signing(Ps384) => true,
             Signing(Ps512) => true,
+            KeyManagement(Rsa1_5) => true,
+            KeyManagement(RsaOaep) => true,
+            KeyManagement(RsaOaep256) => true,
             _ => false,
         }
     }
@@ -55,6 +61,9 @@ impl KeyInfo for RsaPrivateKey {
             (Signing(Ps256), 16..) => true,
             (Signing(Ps384), 24..) => true,
             (Signing(Ps512), 32..) => true,
+            (KeyManagement(Rsa1_5), 16..) => true,
+            (KeyManagement(RsaOaep), 16..) => true,
+            (KeyManagement(RsaOaep256), 16..) => true,
             _ => false,
         }
     }
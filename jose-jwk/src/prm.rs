@@ -54,6 +54,7 @@ impl<T: Into<Algorithm>> From<T> for Parameters {
 
         let cls = match alg {
             Some(Algorithm::Signing(..)) => Some(Class::Signing),
+            Some(Algorithm::KeyManagement(..)) => Some(Class::Encryption),
             _ => None,
         };
 
@@ -541,3 +541,29 @@ mod rfc8037 {
         assert_eq!(val, serde_json::to_value(jwk).unwrap());
     }
 }
+
+#[cfg(test)]
+mod keyinfo {
+    use jose_jwa::{Algorithm, KeyManagement};
+    use jose_jwk::crypto::KeyInfo;
+    use jose_jwk::*;
+
+    #[test]
+    fn pbes2() {
+        let pbes2 = Algorithm::KeyManagement(KeyManagement::Pbes2Hs256A128Kw);
+        let kw = Algorithm::KeyManagement(KeyManagement::A128Kw);
+
+        // A symmetric key is never used as a password, even if the JWK says so.
+        let mut jwk: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "oct",
+            "k": "BwcHBwcHBwcHBwcHBwcHBw",
+        }))
+        .unwrap();
+        assert!(!jwk.is_supported(&pbes2));
+        assert!(jwk.is_supported(&kw));
+
+        jwk.prm.alg = Some(pbes2.clone());
+        assert!(!jwk.is_supported(&pbes2));
+        assert!(!jwk.is_supported(&kw));
+    }
+}
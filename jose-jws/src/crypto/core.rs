@@ -5,14 +5,16 @@ use alloc::vec::Vec;
 
 use jose_b64::stream::Update;
 use jose_jwa::Signing;
-use rand_core::RngCore;
+use rand_core::{CryptoRng, RngCore};
 
+/// Signature creation over a raw JWS Signing Input.
 pub trait CoreSigner: Update {
     type FinishError: From<Self::Error>;
 
-    fn finish(self, rng: impl 'static + RngCore) -> Result<Vec<u8>, Self::FinishError>;
+    fn finish(self, rng: &mut (impl CryptoRng + RngCore)) -> Result<Vec<u8>, Self::FinishError>;
 }
 
+/// A key that creates signatures with a particular algorithm.
 pub trait CoreSigningKey<'a> {
     type StartError: From<<Self::Finish as Update>::Error>;
     type Finish: CoreSigner;
@@ -20,12 +22,14 @@ pub trait CoreSigningKey<'a> {
     fn sign(&'a self, alg: Signing) -> Result<Self::Finish, Self::StartError>;
 }
 
+/// Signature verification over a raw JWS Signing Input.
 pub trait CoreVerifier: Update {
     type FinishError: From<Self::Error>;
 
     fn finish(self, signature: &[u8]) -> Result<(), Self::FinishError>;
 }
 
+/// A key that verifies signatures with a particular algorithm.
 pub trait CoreVerifyingKey<'a> {
     type StartError: From<<Self::Finish as Update>::Error>;
     type Finish: CoreVerifier;
@@ -3,12 +3,15 @@
 
 //! JWS Cryptographic Implementation
 
+pub mod rcrypto;
+
+#[cfg_attr(not(feature = "rcrypto"), allow(dead_code))]
 mod core;
 
 use alloc::{vec, vec::Vec};
 
 use jose_b64::stream::Update;
-use rand_core::RngCore;
+use rand_core::{CryptoRng, RngCore};
 
 use crate::{Flattened, General, Jws, Protected, Signature, Unprotected};
 
@@ -18,7 +21,7 @@ pub trait Signer: Update {
     type FinishError: From<Self::Error>;
 
     /// Finish processing payload and create the signature.
-    fn finish(self, rng: impl 'static + RngCore) -> Result<Signature, Self::FinishError>;
+    fn finish(self, rng: &mut (impl CryptoRng + RngCore)) -> Result<Signature, Self::FinishError>;
 }
 
 /// A signature creation key
@@ -100,6 +103,78 @@ where
     }
 }
 
+/// A set of signature verification keys (e.g. from a JWK Set)
+///
+/// Unlike a slice of keys, which fails if any key cannot begin verification,
+/// this begins verification with every key that supports the signature and
+/// skips the others. So the set may contain keys for other algorithms. It
+/// fails, returning the last error, only if no key supports the signature.
+#[derive(Debug)]
+pub struct KeySet<'k, T>(pub &'k [T]);
+
+impl<'k, T> Clone for KeySet<'k, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'k, T> Copy for KeySet<'k, T> {}
+
+impl<'k, T> KeySet<'k, T> {
+    fn start<'a, A, V>(&'a self, val: A) -> Result<Vec<V>, T::StartError>
+    where
+        T: VerifyingKey<'a, A, Verifier = Vec<V>>,
+        T::StartError: Default,
+        A: Copy,
+    {
+        let mut all = Vec::new();
+        let mut last = T::StartError::default();
+
+        for key in self.0 {
+            match key.verify(val) {
+                Ok(x) => all.extend(x),
+                Err(e) => last = e,
+            }
+        }
+
+        if all.is_empty() {
+            return Err(last);
+        }
+
+        Ok(all)
+    }
+}
+
+impl<'a, 'k, T, V> VerifyingKey<'a, &'a Flattened> for KeySet<'k, T>
+where
+    T: VerifyingKey<'a, &'a Flattened, Verifier = Vec<V>>,
+    T::StartError: Default,
+    V::FinishError: Default,
+    V: Verifier<'a>,
+{
+    type StartError = T::StartError;
+    type Verifier = Vec<V>;
+
+    fn verify(&'a self, val: &'a Flattened) -> Result<Self::Verifier, Self::StartError> {
+        self.start(val)
+    }
+}
+
+impl<'a, 'k, T, V> VerifyingKey<'a, &'a General> for KeySet<'k, T>
+where
+    T: VerifyingKey<'a, &'a General, Verifier = Vec<V>>,
+    T::StartError: Default,
+    V::FinishError: Default,
+    V: Verifier<'a>,
+{
+    type StartError = T::StartError;
+    type Verifier = Vec<V>;
+
+    fn verify(&'a self, val: &'a General) -> Result<Self::Verifier, Self::StartError> {
+        self.start(val)
+    }
+}
+
 impl<'a, T: VerifyingKey<'a, &'a Signature>> VerifyingKey<'a, &'a Flattened> for T
 where
     <T::Verifier as Verifier<'a>>::FinishError: Default,
@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use core::convert::Infallible;
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_b64::serde::Json;
+use jose_b64::stream::{Optional, Update};
+use jose_jwa::Signing;
+use jose_jwk::crypto::rcrypto::Key;
+use rand_core::{CryptoRng, RngCore};
+
+use super::{state::State, Error};
+use crate::crypto::core::{CoreSigner, CoreSigningKey, CoreVerifier, CoreVerifyingKey};
+use crate::crypto::{SigningKey, VerifyingKey};
+use crate::{Protected, Signature, Unprotected};
+
+/// Returns the signature algorithm from the headers.
+///
+/// Header parameter names must be disjoint (RFC 7515 Section 7.2.1), so the
+/// algorithm may not appear in both headers.
+fn algorithm(prot: Option<&Protected>, head: Option<&Unprotected>) -> Result<Signing, Error> {
+    match (prot.and_then(|x| x.oth.alg), head.and_then(|x| x.alg)) {
+        (Some(alg), None) | (None, Some(alg)) => Ok(alg),
+        _ => Err(Error::Invalid),
+    }
+}
+
+/// Begins the JWS Signing Input, returning the state used to process the
+/// payload.
+///
+/// The only extension understood is the unencoded payload option (RFC 7797),
+/// which must be marked as critical when used.
+fn start<T: Update>(mut core: T, prot: Option<&Json<Protected>>) -> Result<Optional<T>, Error>
+where
+    Error: From<T::Error>,
+{
+    let mut b64 = true;
+
+    if let Some(prot) = prot {
+        let crit = prot.crit.as_deref().unwrap_or_default();
+        if crit.iter().any(|x| x != "b64") {
+            return Err(Error::Unsupported);
+        }
+
+        if !prot.b64 && crit.is_empty() {
+            return Err(Error::Invalid);
+        }
+
+        b64 = prot.b64;
+        core.update(Base64UrlUnpadded::encode_string(prot.as_ref()))?;
+    }
+
+    core.update(b".")?;
+    Ok(Optional::new(core, b64))
+}
+
+/// A JWS signer
+///
+/// The payload is signed as it is received. Unless the unencoded payload
+/// option is used, the payload must be the original (i.e. decoded) bytes.
+pub struct Signer<'a> {
+    core: Optional<State<'a>>,
+    prot: Option<Json<Protected>>,
+    head: Option<Unprotected>,
+}
+
+impl Update for Signer<'_> {
+    type Error = Infallible;
+
+    fn update(&mut self, chunk: impl AsRef<[u8]>) -> Result<(), Self::Error> {
+        self.core.update(chunk)
+    }
+}
+
+impl crate::crypto::Signer for Signer<'_> {
+    type FinishError = Error;
+
+    fn finish(self, rng: &mut (impl CryptoRng + RngCore)) -> Result<Signature, Self::FinishError> {
+        let signature = CoreSigner::finish(self.core.finish()?, rng)?;
+
+        Ok(Signature {
+            header: self.head,
+            protected: self.prot,
+            signature: signature.into(),
+        })
+    }
+}
+
+impl<'a> SigningKey<'a> for Key {
+    type StartError = Error;
+    type Signer = Signer<'a>;
+
+    fn sign(
+        &'a self,
+        prot: Option<Protected>,
+        head: Option<Unprotected>,
+    ) -> Result<Self::Signer, Self::StartError> {
+        let alg = algorithm(prot.as_ref(), head.as_ref())?;
+
+        let prot = match prot {
+            Some(prot) => Some(Json::new(prot).map_err(|_| Error::Invalid)?),
+            None => None,
+        };
+
+        Ok(Signer {
+            core: start(CoreSigningKey::sign(self, alg)?, prot.as_ref())?,
+            prot,
+            head,
+        })
+    }
+}
+
+/// A JWS verifier
+///
+/// The payload is verified as it is received. Unless the unencoded payload
+/// option is used, the payload must be the original (i.e. decoded) bytes.
+pub struct Verifier<'a> {
+    core: Optional<State<'a>>,
+    signature: &'a [u8],
+}
+
+impl Update for Verifier<'_> {
+    type Error = Infallible;
+
+    fn update(&mut self, chunk: impl AsRef<[u8]>) -> Result<(), Self::Error> {
+        self.core.update(chunk)
+    }
+}
+
+impl<'a> crate::crypto::Verifier<'a> for Verifier<'a> {
+    type FinishError = Error;
+
+    fn finish(self) -> Result<(), Self::FinishError> {
+        CoreVerifier::finish(self.core.finish()?, self.signature)
+    }
+}
+
+impl<'a> VerifyingKey<'a, &'a Signature> for Key {
+    type StartError = Error;
+    type Verifier = Verifier<'a>;
+
+    fn verify(&'a self, sig: &'a Signature) -> Result<Self::Verifier, Self::StartError> {
+        let prot = sig.protected.as_ref();
+        let alg = algorithm(prot.map(|x| &**x), sig.header.as_ref())?;
+
+        Ok(Verifier {
+            core: start(CoreVerifyingKey::verify(self, alg)?, prot)?,
+            signature: &sig.signature,
+        })
+    }
+}
@@ -0,0 +1,16 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Integration with RustCrypto types
+
+#![cfg(feature = "rcrypto")]
+#![cfg_attr(docsrs, doc(cfg(feature = "rcrypto")))]
+
+mod key;
+mod p256;
+mod p384;
+mod rsa;
+mod state;
+
+pub use jose_jwk::crypto::rcrypto::Error;
+pub use key::{Signer, Verifier};
@@ -0,0 +1,39 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-p384")]
+
+use alloc::vec::Vec;
+
+use jose_jwk::crypto::rcrypto::Kind;
+use p384::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use p384::ecdsa::{Signature, SigningKey, VerifyingKey};
+use p384::{PublicKey, SecretKey};
+
+use super::Error;
+
+type Key = Kind<PublicKey, SecretKey>;
+
+/// Signs the hash of the JWS Signing Input (RFC 7518 Section 3.4).
+///
+/// The signature is the concatenation of `R` and `S`.
+pub(super) fn sign(key: &Key, hash: &[u8]) -> Result<Vec<u8>, Error> {
+    let key = match key {
+        Kind::Public(..) => return Err(Error::NotPrivate),
+        Kind::Secret(key) => SigningKey::from(key),
+    };
+
+    let sig: Signature = key.sign_prehash(hash).map_err(|_| Error::Invalid)?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Verifies a signature over the hash of the JWS Signing Input.
+pub(super) fn verify(key: &Key, hash: &[u8], sig: &[u8]) -> Result<(), Error> {
+    let key = match key {
+        Kind::Public(key) => VerifyingKey::from(key),
+        Kind::Secret(key) => VerifyingKey::from(key.public_key()),
+    };
+
+    let sig = Signature::from_slice(sig).map_err(|_| Error::Invalid)?;
+    key.verify_prehash(hash, &sig).map_err(|_| Error::Invalid)
+}
@@ -0,0 +1,61 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#![cfg(feature = "rcrypto-rsa")]
+
+use alloc::vec::Vec;
+
+use jose_jwa::Signing;
+use jose_jwk::crypto::rcrypto::Kind;
+use rand_core::{CryptoRng, RngCore};
+use rsa::{Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey};
+use sha2::{Sha256, Sha384, Sha512};
+
+use super::Error;
+
+type Key = Kind<RsaPublicKey, RsaPrivateKey>;
+
+/// Signs the hash of the JWS Signing Input (RFC 7518 Sections 3.3 and 3.5).
+pub(super) fn sign(
+    alg: Signing,
+    key: &Key,
+    hash: &[u8],
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<u8>, Error> {
+    let key = match key {
+        Kind::Public(..) => return Err(Error::NotPrivate),
+        Kind::Secret(key) => key,
+    };
+
+    let sig = match alg {
+        Signing::Rs256 => key.sign_with_rng(rng, Pkcs1v15Sign::new::<Sha256>(), hash),
+        Signing::Rs384 => key.sign_with_rng(rng, Pkcs1v15Sign::new::<Sha384>(), hash),
+        Signing::Rs512 => key.sign_with_rng(rng, Pkcs1v15Sign::new::<Sha512>(), hash),
+        Signing::Ps256 => key.sign_with_rng(rng, Pss::new::<Sha256>(), hash),
+        Signing::Ps384 => key.sign_with_rng(rng, Pss::new::<Sha384>(), hash),
+        Signing::Ps512 => key.sign_with_rng(rng, Pss::new::<Sha512>(), hash),
+        _ => return Err(Error::AlgMismatch),
+    };
+
+    sig.map_err(|_| Error::Invalid)
+}
+
+/// Verifies a signature over the hash of the JWS Signing Input.
+pub(super) fn verify(alg: Signing, key: &Key, hash: &[u8], sig: &[u8]) -> Result<(), Error> {
+    let key = match key {
+        Kind::Public(key) => key,
+        Kind::Secret(key) => key.as_ref(),
+    };
+
+    let res = match alg {
+        Signing::Rs256 => key.verify(Pkcs1v15Sign::new::<Sha256>(), hash, sig),
+        Signing::Rs384 => key.verify(Pkcs1v15Sign::new::<Sha384>(), hash, sig),
+        Signing::Rs512 => key.verify(Pkcs1v15Sign::new::<Sha512>(), hash, sig),
+        Signing::Ps256 => key.verify(Pss::new::<Sha256>(), hash, sig),
+        Signing::Ps384 => key.verify(Pss::new::<Sha384>(), hash, sig),
+        Signing::Ps512 => key.verify(Pss::new::<Sha512>(), hash, sig),
+        _ => return Err(Error::AlgMismatch),
+    };
+
+    res.map_err(|_| Error::Invalid)
+}
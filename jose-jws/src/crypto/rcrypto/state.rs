@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+use hmac::{Hmac, Mac};
+use jose_b64::stream::Update;
+use jose_jwa::Signing;
+#[cfg(any(
+    feature = "rcrypto-rsa",
+    feature = "rcrypto-p256",
+    feature = "rcrypto-p384"
+))]
+use jose_jwk::crypto::rcrypto::Kind;
+use jose_jwk::crypto::{rcrypto::Key, KeyInfo};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use super::Error;
+use crate::crypto::core::{CoreSigner, CoreSigningKey, CoreVerifier, CoreVerifyingKey};
+
+/// The hash (or MAC) being computed over the JWS Signing Input.
+enum Hash {
+    Hs256(Hmac<Sha256>),
+    Hs384(Hmac<Sha384>),
+    Hs512(Hmac<Sha512>),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+/// The state of signature creation or verification with a key.
+pub struct State<'a> {
+    key: &'a Key,
+    #[cfg_attr(not(feature = "rcrypto-rsa"), allow(dead_code))]
+    alg: Signing,
+    hash: Hash,
+}
+
+impl<'a> State<'a> {
+    fn new(key: &'a Key, alg: Signing) -> Result<Self, Error> {
+        if !key.is_supported(&alg.into()) {
+            return Err(Error::AlgMismatch);
+        }
+
+        let hash = match (key, alg) {
+            (Key::Oct(k), Signing::Hs256) => {
+                Hash::Hs256(Mac::new_from_slice(k).map_err(|_| Error::Invalid)?)
+            }
+            (Key::Oct(k), Signing::Hs384) => {
+                Hash::Hs384(Mac::new_from_slice(k).map_err(|_| Error::Invalid)?)
+            }
+            (Key::Oct(k), Signing::Hs512) => {
+                Hash::Hs512(Mac::new_from_slice(k).map_err(|_| Error::Invalid)?)
+            }
+            (_, Signing::Rs256 | Signing::Ps256 | Signing::Es256) => Hash::Sha256(Sha256::new()),
+            (_, Signing::Rs384 | Signing::Ps384 | Signing::Es384) => Hash::Sha384(Sha384::new()),
+            (_, Signing::Rs512 | Signing::Ps512) => Hash::Sha512(Sha512::new()),
+            _ => return Err(Error::Unsupported),
+        };
+
+        Ok(Self { key, alg, hash })
+    }
+}
+
+impl Update for State<'_> {
+    type Error = Infallible;
+
+    fn update(&mut self, chunk: impl AsRef<[u8]>) -> Result<(), Self::Error> {
+        match &mut self.hash {
+            Hash::Hs256(x) => x.update(chunk.as_ref()),
+            Hash::Hs384(x) => x.update(chunk.as_ref()),
+            Hash::Hs512(x) => x.update(chunk.as_ref()),
+            Hash::Sha256(x) => Digest::update(x, chunk),
+            Hash::Sha384(x) => Digest::update(x, chunk),
+            Hash::Sha512(x) => Digest::update(x, chunk),
+        }
+
+        Ok(())
+    }
+}
+
+impl CoreSigner for State<'_> {
+    type FinishError = Error;
+
+    #[allow(unused_variables)]
+    fn finish(self, rng: &mut (impl CryptoRng + RngCore)) -> Result<Vec<u8>, Self::FinishError> {
+        let hash = match self.hash {
+            Hash::Hs256(x) => return Ok(x.finalize().into_bytes().to_vec()),
+            Hash::Hs384(x) => return Ok(x.finalize().into_bytes().to_vec()),
+            Hash::Hs512(x) => return Ok(x.finalize().into_bytes().to_vec()),
+            Hash::Sha256(x) => x.finalize().to_vec(),
+            Hash::Sha384(x) => x.finalize().to_vec(),
+            Hash::Sha512(x) => x.finalize().to_vec(),
+        };
+
+        match self.key {
+            #[cfg(feature = "rcrypto-rsa")]
+            Key::Rsa(key) => super::rsa::sign(self.alg, key, &hash, rng),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(key) => super::p256::sign(key, &hash),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(key) => super::p384::sign(key, &hash),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::AlgMismatch),
+        }
+    }
+}
+
+impl CoreVerifier for State<'_> {
+    type FinishError = Error;
+
+    #[allow(unused_variables)]
+    fn finish(self, signature: &[u8]) -> Result<(), Self::FinishError> {
+        let hash = match self.hash {
+            Hash::Hs256(x) => return x.verify_slice(signature).map_err(|_| Error::Invalid),
+            Hash::Hs384(x) => return x.verify_slice(signature).map_err(|_| Error::Invalid),
+            Hash::Hs512(x) => return x.verify_slice(signature).map_err(|_| Error::Invalid),
+            Hash::Sha256(x) => x.finalize().to_vec(),
+            Hash::Sha384(x) => x.finalize().to_vec(),
+            Hash::Sha512(x) => x.finalize().to_vec(),
+        };
+
+        match self.key {
+            #[cfg(feature = "rcrypto-rsa")]
+            Key::Rsa(key) => super::rsa::verify(self.alg, key, &hash, signature),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(key) => super::p256::verify(key, &hash, signature),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(key) => super::p384::verify(key, &hash, signature),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::AlgMismatch),
+        }
+    }
+}
+
+impl<'a> CoreSigningKey<'a> for Key {
+    type StartError = Error;
+    type Finish = State<'a>;
+
+    fn sign(&'a self, alg: Signing) -> Result<Self::Finish, Self::StartError> {
+        match self {
+            #[cfg(feature = "rcrypto-rsa")]
+            Key::Rsa(Kind::Public(..)) => Err(Error::NotPrivate),
+
+            #[cfg(feature = "rcrypto-p256")]
+            Key::P256(Kind::Public(..)) => Err(Error::NotPrivate),
+
+            #[cfg(feature = "rcrypto-p384")]
+            Key::P384(Kind::Public(..)) => Err(Error::NotPrivate),
+
+            _ => State::new(self, alg),
+        }
+    }
+}
+
+impl<'a> CoreVerifyingKey<'a> for Key {
+    type StartError = Error;
+    type Finish = State<'a>;
+
+    fn verify(&'a self, alg: Signing) -> Result<Self::Finish, Self::StartError> {
+        State::new(self, alg)
+    }
+}
@@ -17,7 +17,7 @@ fn b64_default() -> bool {
 
 #[inline]
 fn b64_serialize(value: &bool) -> bool {
-    !value
+    *value
 }
 
 /// The JWS Protected Header
@@ -0,0 +1,294 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+mod head {
+    use jose_jws::Protected;
+    use serde_json::json;
+
+    #[test]
+    fn b64() {
+        // The default (i.e. `true`) is omitted.
+        let prot = Protected::default();
+        assert_eq!(serde_json::to_value(&prot).unwrap(), json!({}));
+
+        // The unencoded payload option must be serialized (RFC 7797 Section 3).
+        let prot = Protected {
+            crit: Some(vec!["b64".into()]),
+            b64: false,
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&prot).unwrap();
+        assert_eq!(value, json!({ "crit": ["b64"], "b64": false }));
+
+        let prot: Protected = serde_json::from_value(value).unwrap();
+        assert!(!prot.b64);
+
+        let prot: Protected = serde_json::from_value(json!({})).unwrap();
+        assert!(prot.b64);
+    }
+}
+
+#[cfg(feature = "rcrypto")]
+mod rcrypto {
+    use jose_b64::stream::Update;
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jws::crypto::rcrypto::Error;
+    use jose_jws::crypto::{Signer, SigningKey, Verifier, VerifyingKey};
+    use jose_jws::{Flattened, Protected, Unprotected};
+    use serde_json::json;
+
+    fn key(jwk: serde_json::Value) -> Key {
+        let jwk: jose_jwk::Key = serde_json::from_value(jwk).unwrap();
+        Key::try_from(&jwk).unwrap()
+    }
+
+    fn protected(alg: Signing) -> Protected {
+        Protected {
+            oth: Unprotected {
+                alg: Some(alg),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn sign(key: &Key, prot: Protected, payload: &[u8]) -> Result<Flattened, Error> {
+        let mut rng = rand::thread_rng();
+        let signature = key
+            .sign(Some(prot), None)?
+            .chain(payload)?
+            .finish(&mut rng)?;
+
+        Ok(Flattened {
+            payload: Some(payload.to_vec().into()),
+            signature,
+        })
+    }
+
+    fn verify(key: &Key, jws: &Flattened) -> Result<(), Error> {
+        let payload = jws.payload.as_ref().unwrap();
+        key.verify(jws)?.chain(payload)?.finish()
+    }
+
+    fn hs256() -> Key {
+        key(json!({
+            "kty": "oct",
+            "k": "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+        }))
+    }
+
+    #[test]
+    fn rfc7515_a1() {
+        const JWS: &str = concat!(
+            "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.",
+            "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ.",
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+        );
+
+        let key = hs256();
+        let jws: Flattened = JWS.parse().unwrap();
+        verify(&key, &jws).unwrap();
+
+        let mut jws: Flattened = JWS.parse().unwrap();
+        jws.payload = Some(b"{}".to_vec().into());
+        assert_eq!(verify(&key, &jws), Err(Error::Invalid));
+    }
+
+    #[test]
+    fn rfc7797_unencoded() {
+        const JWS: &str = concat!(
+            "eyJhbGciOiJIUzI1NiIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19.",
+            ".",
+            "A5dxf2s96_n5FLueVuW1Z_vh161FwXZC4YLPff6dmDY",
+        );
+
+        let key = hs256();
+        let jws: Flattened = JWS.parse().unwrap();
+        assert!(jws.payload.is_none());
+        key.verify(&jws)
+            .unwrap()
+            .chain(b"$.02")
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        // The unencoded payload option must be marked as critical.
+        let mut prot = protected(Signing::Hs256);
+        prot.b64 = false;
+        let err = key.sign(Some(prot.clone()), None).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        prot.crit = Some(vec!["b64".into()]);
+        let mut jws = sign(&key, prot, b"$.02").unwrap();
+        jws.payload = None;
+        let jws: Flattened = jws.to_string().parse().unwrap();
+        key.verify(&jws)
+            .unwrap()
+            .chain(b"$.02")
+            .unwrap()
+            .finish()
+            .unwrap();
+    }
+
+    #[test]
+    fn hmac() {
+        let key = hs256();
+
+        for alg in [Signing::Hs256, Signing::Hs384, Signing::Hs512] {
+            let jws = sign(&key, protected(alg), b"Live long and prosper.").unwrap();
+            verify(&key, &jws).unwrap();
+
+            // The compact serialization round trips.
+            let jws: Flattened = jws.to_string().parse().unwrap();
+            verify(&key, &jws).unwrap();
+        }
+
+        // The key must be long enough for the algorithm.
+        let short = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let err = short.sign(Some(protected(Signing::Hs384)), None).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[test]
+    fn headers() {
+        let key = hs256();
+
+        // The algorithm may be given in the unprotected header.
+        let head = protected(Signing::Hs256).oth;
+        let mut rng = rand::thread_rng();
+        let signature = key
+            .sign(None, Some(head.clone()))
+            .unwrap()
+            .chain(b"payload")
+            .unwrap()
+            .finish(&mut rng)
+            .unwrap();
+        let jws = Flattened {
+            payload: Some(b"payload".to_vec().into()),
+            signature,
+        };
+        verify(&key, &jws).unwrap();
+
+        // But it must appear exactly once.
+        let err = key.sign(Some(protected(Signing::Hs256)), Some(head)).err();
+        assert_eq!(err, Some(Error::Invalid));
+        let err = key.sign(None, None).err();
+        assert_eq!(err, Some(Error::Invalid));
+
+        // No other extensions are understood.
+        let mut prot = protected(Signing::Hs256);
+        prot.crit = Some(vec!["exp".into()]);
+        let err = key.sign(Some(prot), None).err();
+        assert_eq!(err, Some(Error::Unsupported));
+
+        // No key supports unsecured JWSs.
+        let err = key.sign(Some(protected(Signing::Null)), None).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[cfg(feature = "rcrypto-rsa")]
+    #[test]
+    fn rsa() {
+        let mut rng = rand::thread_rng();
+        let sk = rsa::RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let pk = Key::from(sk.to_public_key());
+        let sk = Key::from(sk);
+
+        for alg in [Signing::Rs256, Signing::Ps256] {
+            let jws = sign(&sk, protected(alg), b"Live long and prosper.").unwrap();
+            assert_eq!(jws.signature.signature.len(), 256);
+            verify(&pk, &jws).unwrap();
+            verify(&sk, &jws).unwrap();
+        }
+
+        let err = pk.sign(Some(protected(Signing::Rs256)), None).err();
+        assert_eq!(err, Some(Error::NotPrivate));
+
+        // The key is too short for the stronger algorithms.
+        let err = sk.sign(Some(protected(Signing::Rs512)), None).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn ecdsa() {
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let pk = Key::from(sk.public_key());
+        let sk = Key::from(sk);
+
+        let jws = sign(&sk, protected(Signing::Es256), b"Live long and prosper.").unwrap();
+        assert_eq!(jws.signature.signature.len(), 64);
+        verify(&pk, &jws).unwrap();
+
+        let mut jws = jws;
+        jws.payload = Some(b"Live long and prosper!".to_vec().into());
+        assert_eq!(verify(&pk, &jws), Err(Error::Invalid));
+
+        let err = sk.sign(Some(protected(Signing::Es384)), None).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn key_set() {
+        use jose_jws::crypto::KeySet;
+
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let other = p256::SecretKey::random(&mut rng).public_key();
+        let pk = Key::from(sk.public_key());
+        let sk = Key::from(sk);
+
+        let jws = sign(&sk, protected(Signing::Es256), b"payload").unwrap();
+        let payload = jws.payload.as_ref().unwrap();
+
+        // Keys which do not support the algorithm are skipped.
+        let keys = [hs256(), Key::from(other), pk];
+        KeySet(&keys)
+            .verify(&jws)
+            .unwrap()
+            .chain(payload)
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        let keys = [hs256(), Key::from(other)];
+        let set = KeySet(&keys);
+        let ver = set.verify(&jws).unwrap().chain(payload).unwrap();
+        assert_eq!(ver.finish(), Err(Error::Invalid));
+
+        let keys = [hs256()];
+        assert_eq!(KeySet(&keys).verify(&jws).err(), Some(Error::AlgMismatch));
+    }
+
+    #[cfg(feature = "rcrypto-p256")]
+    #[test]
+    fn slice() {
+        use jose_jws::crypto::KeySet;
+
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let pk = sk.public_key();
+        let sk = Key::from(sk);
+
+        let jws = sign(&sk, protected(Signing::Es256), b"payload").unwrap();
+        let payload = jws.payload.as_ref().unwrap();
+
+        let keys = [Key::from(pk)];
+        keys[..]
+            .verify(&jws)
+            .unwrap()
+            .chain(payload)
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        // Every key in a slice must support the algorithm.
+        let keys = [hs256(), Key::from(pk)];
+        assert_eq!(keys[..].verify(&jws).err(), Some(Error::AlgMismatch));
+        assert!(KeySet(&keys).verify(&jws).is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! JWT Access Tokens
+//!
+//! This module implements the JWT profile for OAuth 2.0 access tokens
+//! ([RFC 9068]), as issued by authorization servers and validated by resource
+//! servers. Access tokens are identified by the `at+jwt` token type, which
+//! prevents other JWTs (e.g. ID Tokens) being used as access tokens.
+//!
+//! [RFC 9068]: https://datatracker.ietf.org/doc/html/rfc9068
+
+use alloc::{string::String, vec, vec::Vec};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::cnf::Confirmation;
+use crate::jws::decode_with;
+use crate::validation::acr;
+use crate::{Claim, Claims, Clock, Error, NumericDate, Reason, Scope, VerifyingKeys};
+
+/// The token type of a JWT access token (RFC 9068 Section 2.1).
+pub const TYP: &str = "at+jwt";
+
+/// The claims of a JWT access token
+///
+/// The claims defined in [RFC 9068 Section 2.2] (see [`Claims`]).
+///
+/// [RFC 9068 Section 2.2]: https://datatracker.ietf.org/doc/html/rfc9068#section-2.2
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccessToken<T = Map<String, Value>> {
+    /// RFC 9068 Section 2.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub client_id: Option<String>,
+
+    /// RFC 9068 Section 2.2.3
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub scope: Option<Scope>,
+
+    /// RFC 9068 Section 2.2.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub auth_time: Option<NumericDate>,
+
+    /// RFC 9068 Section 2.2.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub acr: Option<String>,
+
+    /// RFC 9068 Section 2.2.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub amr: Option<Vec<String>>,
+
+    /// RFC 9068 Section 2.2.3.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub roles: Option<Vec<String>>,
+
+    /// RFC 9068 Section 2.2.3.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub groups: Option<Vec<String>>,
+
+    /// RFC 9068 Section 2.2.3.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub entitlements: Option<Vec<String>>,
+
+    /// RFC 7800 Section 3.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cnf: Option<Confirmation>,
+
+    /// Other claims that may appear in the access token.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for AccessToken<T> {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            scope: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            roles: None,
+            groups: None,
+            entitlements: None,
+            cnf: None,
+            oth: T::default(),
+        }
+    }
+}
+
+/// A policy for validating a JWT access token
+///
+/// An access token must identify the client it was issued to (i.e.
+/// `client_id`). The resource server may also restrict the clients, require
+/// scopes and, as for an ID Token, limit how long ago and how the end-user
+/// authenticated.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The token type, issuer, audience and lifetime of the access token.
+    ///
+    /// [`Validation::new()`] requires the `at+jwt` type, so that other JWTs
+    /// from the same issuer (e.g. ID Tokens) are not accepted, and the `iss`,
+    /// `exp`, `aud`, `sub`, `iat` and `jti` claims (Section 2.2).
+    pub jwt: crate::Validation<C>,
+
+    /// The expected clients (i.e. `client_id`).
+    ///
+    /// The `client_id` claim is always required. If this is not empty, it
+    /// must also match one of these values.
+    pub clients: Vec<String>,
+
+    /// The scopes which must have been granted (i.e. `scope`).
+    pub scopes: Vec<String>,
+
+    /// The maximum time, in seconds, since the end-user authenticated.
+    ///
+    /// If set, the token must contain the `auth_time` claim.
+    pub max_age: Option<u64>,
+
+    /// The acceptable authentication context class references (i.e. `acr`).
+    pub acr: Vec<String>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for access tokens from the issuer to the resource
+    /// server, which is identified by the audience.
+    pub fn new(clock: C, issuer: impl Into<String>, audience: impl Into<String>) -> Self {
+        let mut jwt = crate::Validation::new(clock);
+        jwt.types = vec![TYP.into()];
+        jwt.issuers = vec![issuer.into()];
+        jwt.audiences = vec![audience.into()];
+        jwt.required = vec![
+            Claim::Iss,
+            Claim::Exp,
+            Claim::Aud,
+            Claim::Sub,
+            Claim::Iat,
+            Claim::Jti,
+        ];
+
+        Self {
+            jwt,
+            clients: Vec::new(),
+            scopes: Vec::new(),
+            max_age: None,
+            acr: Vec::new(),
+        }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of an access token.
+    ///
+    /// This performs the checks of Section 4, other than the signature
+    /// verification.
+    pub fn validate<T>(
+        &self,
+        typ: Option<&str>,
+        claims: &Claims<AccessToken<T>>,
+    ) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+        let at = &claims.oth;
+
+        let client_id = at.client_id.as_ref().ok_or(Reason::ClientId)?;
+        if !self.clients.is_empty() && !self.clients.contains(client_id) {
+            return Err(Reason::ClientId);
+        }
+
+        if !self.scopes.is_empty() {
+            let scope = at.scope.as_ref().ok_or(Reason::Scope)?;
+            if self.scopes.iter().any(|x| !scope.contains(x)) {
+                return Err(Reason::Scope);
+            }
+        }
+
+        acr(&self.acr, at.acr.as_ref())?;
+        self.jwt.authenticated(self.max_age, at.auth_time)?;
+
+        Ok(())
+    }
+}
+
+/// Verifies an access token in the compact serialization and validates its
+/// claims.
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<AccessToken<T>>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })
+}
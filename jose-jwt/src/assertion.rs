@@ -0,0 +1,227 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! JWT Client Assertions
+//!
+//! A client may authenticate to the token endpoint of an authorization server
+//! with a JWT signed by its own key ([RFC 7523 Section 2.2]), which is sent as
+//! the `client_assertion` parameter. This is also known as the
+//! `private_key_jwt` (or, with an HMAC, `client_secret_jwt`) client
+//! authentication method.
+//!
+//! The client creates assertions with [`Assertion`]. The authorization server
+//! verifies them with [`decode()`], using a [`ReplayCache`] to ensure that
+//! each assertion is only used once.
+//!
+//! [RFC 7523 Section 2.2]: https://datatracker.ietf.org/doc/html/rfc7523#section-2.2
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_jws::crypto::{Signer, SigningKey};
+use jose_jws::Protected;
+use rand_core::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
+
+use crate::jws::decode_with;
+use crate::{Claim, Claims, Clock, Error, NumericDate, Reason, VerifyingKeys};
+
+/// The `client_assertion_type` of a JWT client assertion.
+pub const ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// A client assertion builder
+///
+/// Each assertion identifies the client as both issuer and subject, names the
+/// token endpoint as its audience and has a random JWT ID.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Assertion {
+    /// The client identifier (i.e. `iss` and `sub`).
+    pub client_id: String,
+
+    /// The token endpoint of the authorization server (i.e. `aud`).
+    pub audience: String,
+
+    /// The lifetime of each assertion, in seconds.
+    pub lifetime: u64,
+}
+
+impl Assertion {
+    /// Creates a builder for assertions by the client to the token endpoint.
+    ///
+    /// Assertions are valid for 60 seconds by default.
+    pub fn new(client_id: impl Into<String>, audience: impl Into<String>) -> Self {
+        Self {
+            client_id: client_id.into(),
+            audience: audience.into(),
+            lifetime: 60,
+        }
+    }
+
+    /// Creates the claims of an assertion issued at the given time.
+    ///
+    /// The expiration time saturates at the latest representable date.
+    pub fn claims(&self, rng: &mut (impl CryptoRng + RngCore), now: NumericDate) -> Claims {
+        let mut jti = [0u8; 16];
+        rng.fill_bytes(&mut jti);
+
+        Claims {
+            iss: Some(self.client_id.clone()),
+            sub: Some(self.client_id.clone()),
+            aud: Some(self.audience.as_str().into()),
+            exp: Some(now.saturating_add(self.lifetime)),
+            iat: Some(now),
+            jti: Some(Base64UrlUnpadded::encode_string(&jti)),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a signed assertion, issued at the given time.
+    ///
+    /// The algorithm (i.e. `alg`) is taken from the protected header.
+    pub fn sign<'a, K>(
+        &self,
+        key: &'a K,
+        rng: &mut (impl CryptoRng + RngCore),
+        header: Protected,
+        now: NumericDate,
+    ) -> Result<String, Error<K::StartError>>
+    where
+        K: SigningKey<'a> + ?Sized,
+        K::Signer: Signer<FinishError = K::StartError>,
+    {
+        let claims = self.claims(rng, now);
+        crate::encode(&claims, key, rng, header)
+    }
+}
+
+/// A record of the assertions that have been used
+///
+/// An assertion must only be used once (RFC 7523 Section 3). The record need
+/// only be kept until each assertion is no longer accepted, after which it is
+/// rejected anyway. [`decode()`] passes the expiration time extended by the
+/// validation leeway, since an assertion is still accepted within the leeway.
+pub trait ReplayCache {
+    /// Records the use of an assertion, unless it has already been used.
+    ///
+    /// Returns `false` if the assertion with this issuer and JWT ID has been
+    /// used before. Otherwise, returns `true` once the record is kept until
+    /// `exp`.
+    fn insert(&mut self, iss: &str, jti: &str, exp: NumericDate, now: NumericDate) -> bool;
+}
+
+/// An in-memory [`ReplayCache`]
+///
+/// Expired records are removed as new assertions are recorded.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCache {
+    seen: BTreeMap<(String, String), NumericDate>,
+}
+
+impl MemoryCache {
+    /// Returns the number of records held.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Returns whether no records are held.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+impl ReplayCache for MemoryCache {
+    fn insert(&mut self, iss: &str, jti: &str, exp: NumericDate, now: NumericDate) -> bool {
+        self.seen.retain(|_, exp| *exp > now);
+
+        let key = (iss.to_string(), jti.to_string());
+        if self.seen.contains_key(&key) {
+            return false;
+        }
+
+        self.seen.insert(key, exp);
+        true
+    }
+}
+
+/// A policy for validating a client assertion
+///
+/// The client must be the subject of the assertion (i.e. `sub`), so that an
+/// assertion issued by the client about someone else cannot authenticate it.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The issuer, audience and lifetime of the assertion.
+    ///
+    /// [`Validation::new()`] trusts only the client as the issuer, requires
+    /// the token endpoint as an audience and requires the `iss`, `sub`,
+    /// `aud`, `exp` and `jti` claims (Section 3). The JWT ID is what the
+    /// [`ReplayCache`] remembers, for as long as the leeway lets the
+    /// assertion be accepted.
+    pub jwt: crate::Validation<C>,
+
+    /// The client identifier.
+    pub client_id: String,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for assertions by the client to the token endpoint.
+    pub fn new(clock: C, client_id: impl Into<String>, audience: impl Into<String>) -> Self {
+        let client_id = client_id.into();
+
+        let mut jwt = crate::Validation::new(clock);
+        jwt.issuers = vec![client_id.clone()];
+        jwt.audiences = vec![audience.into()];
+        jwt.required = vec![Claim::Iss, Claim::Sub, Claim::Aud, Claim::Exp, Claim::Jti];
+
+        Self { jwt, client_id }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of an assertion.
+    ///
+    /// This does not check for replay, which requires a [`ReplayCache`].
+    pub fn validate<T>(&self, typ: Option<&str>, claims: &Claims<T>) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+
+        if claims.sub.as_ref() != Some(&self.client_id) {
+            return Err(Reason::ClientId);
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies a client assertion in the compact serialization, validates its
+/// claims and records its use.
+///
+/// The assertion is rejected with [`Reason::Replay`] if it has been used
+/// before. Its use is only recorded if it is otherwise valid.
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+    cache: &mut impl ReplayCache,
+) -> Result<Claims<T>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    let claims: Claims<T> = decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })?;
+
+    let iss = claims.iss.as_deref().ok_or(Reason::Missing(Claim::Iss))?;
+    let jti = claims.jti.as_deref().ok_or(Reason::Missing(Claim::Jti))?;
+    let exp = claims.exp.ok_or(Reason::Missing(Claim::Exp))?;
+
+    // The assertion is accepted until its expiration time plus the leeway, so
+    // the record must be kept at least as long.
+    let exp = exp.saturating_add(validation.jwt.leeway);
+    if !cache.insert(iss, jti, exp, validation.jwt.clock.now()) {
+        return Err(Error::Rejected(Reason::Replay));
+    }
+
+    Ok(claims)
+}
@@ -0,0 +1,300 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// A JSON numeric value representing a number of seconds since the epoch
+///
+/// This type is defined in [RFC7519 Section 2]. Fractional values are
+/// accepted and rounded down to the whole second. Values which cannot be
+/// represented as a signed 64-bit number of seconds are rejected, as are
+/// values that are not finite.
+///
+/// [RFC7519 Section 2]: https://datatracker.ietf.org/doc/html/rfc7519#section-2
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NumericDate(i64);
+
+impl NumericDate {
+    /// The epoch (i.e. 1970-01-01T00:00:00Z)
+    pub const EPOCH: Self = Self(0);
+
+    /// Creates a date from a number of seconds since the epoch.
+    pub const fn new(secs: i64) -> Self {
+        Self(secs)
+    }
+
+    /// Returns the number of seconds since the epoch.
+    pub const fn secs(self) -> i64 {
+        self.0
+    }
+
+    /// Adds a number of seconds, returning `None` on overflow.
+    pub fn checked_add(self, secs: u64) -> Option<Self> {
+        let secs = i128::from(self.0) + i128::from(secs);
+        i64::try_from(secs).ok().map(Self)
+    }
+
+    /// Adds a number of seconds, saturating at the latest representable date.
+    pub fn saturating_add(self, secs: u64) -> Self {
+        self.checked_add(secs).unwrap_or(Self(i64::MAX))
+    }
+
+    /// Subtracts a number of seconds, returning `None` on overflow.
+    pub fn checked_sub(self, secs: u64) -> Option<Self> {
+        let secs = i128::from(self.0) - i128::from(secs);
+        i64::try_from(secs).ok().map(Self)
+    }
+}
+
+impl From<i64> for NumericDate {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NumericDate> for i64 {
+    fn from(value: NumericDate) -> Self {
+        value.0
+    }
+}
+
+impl Serialize for NumericDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for NumericDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NumericDateVisitor;
+
+        impl Visitor<'_> for NumericDateVisitor {
+            type Value = NumericDate;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of seconds since the epoch")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(NumericDate(v))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                let v = i64::try_from(v).map_err(|_| E::custom("date out of range"))?;
+                Ok(NumericDate(v))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                // The bounds are exactly representable, so values within them
+                // may be converted without saturation.
+                const MIN: f64 = i64::MIN as f64;
+                const MAX: f64 = -MIN;
+
+                if !(MIN..MAX).contains(&v) {
+                    return Err(E::custom("date out of range"));
+                }
+
+                // Round down, so that a fractional expiry never extends
+                // the lifetime of a token.
+                let secs = v as i64;
+                Ok(NumericDate(match secs as f64 > v {
+                    true => secs - 1,
+                    false => secs,
+                }))
+            }
+        }
+
+        deserializer.deserialize_any(NumericDateVisitor)
+    }
+}
+
+/// The intended recipients of a JWT
+///
+/// This type is defined in [RFC7519 Section 4.1.3]. The audience may be a
+/// single string or an array of strings; both forms are preserved.
+///
+/// [RFC7519 Section 4.1.3]: https://datatracker.ietf.org/doc/html/rfc7519#section-4.1.3
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    /// A single recipient
+    One(String),
+
+    /// Many recipients
+    Many(Vec<String>),
+}
+
+impl Audience {
+    /// Returns the recipients as a slice.
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            Self::One(x) => core::slice::from_ref(x),
+            Self::Many(x) => x,
+        }
+    }
+
+    /// Returns whether the value is one of the recipients.
+    pub fn contains(&self, value: &str) -> bool {
+        self.as_slice().iter().any(|x| x == value)
+    }
+}
+
+impl From<String> for Audience {
+    fn from(value: String) -> Self {
+        Self::One(value)
+    }
+}
+
+impl From<&str> for Audience {
+    fn from(value: &str) -> Self {
+        Self::One(value.into())
+    }
+}
+
+impl From<Vec<String>> for Audience {
+    fn from(value: Vec<String>) -> Self {
+        Self::Many(value)
+    }
+}
+
+impl From<Audience> for Vec<String> {
+    fn from(value: Audience) -> Self {
+        match value {
+            Audience::One(x) => vec![x],
+            Audience::Many(x) => x,
+        }
+    }
+}
+
+/// A space-delimited list of scopes
+///
+/// This type is defined in [RFC6749 Section 3.3] and is used by claims such
+/// as `scope`. The order of the scopes does not matter.
+///
+/// [RFC6749 Section 3.3]: https://datatracker.ietf.org/doc/html/rfc6749#section-3.3
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scope(Vec<String>);
+
+impl Scope {
+    /// Returns the scopes as a slice.
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Returns whether the scope is included.
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.iter().any(|x| x == scope)
+    }
+}
+
+impl From<Vec<String>> for Scope {
+    fn from(value: Vec<String>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Scope {
+    fn from(value: &str) -> Self {
+        Self(
+            value
+                .split(' ')
+                .filter(|x| !x.is_empty())
+                .map(Into::into)
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, scope) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            f.write_str(scope)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for Scope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Self::from(value.as_str()))
+    }
+}
+
+/// A JWT Claims Set
+///
+/// This type contains the registered claims defined in [RFC7519 Section 4.1].
+/// Any other claims are collected in `oth`, which by default is a JSON object
+/// but may be any type that can be flattened by serde.
+///
+/// The profiles of JWT in this crate define their claims as such a type (e.g.
+/// `Claims<IdToken>`, where [`IdToken`](crate::oidc::IdToken) contains the ID
+/// Token claims). Each such type likewise collects any claims that it does not
+/// define in its own `oth`.
+///
+/// [RFC7519 Section 4.1]: https://datatracker.ietf.org/doc/html/rfc7519#section-4.1
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Claims<T = Map<String, Value>> {
+    /// RFC 7519 Section 4.1.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub iss: Option<String>,
+
+    /// RFC 7519 Section 4.1.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sub: Option<String>,
+
+    /// RFC 7519 Section 4.1.3
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub aud: Option<Audience>,
+
+    /// RFC 7519 Section 4.1.4
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub exp: Option<NumericDate>,
+
+    /// RFC 7519 Section 4.1.5
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nbf: Option<NumericDate>,
+
+    /// RFC 7519 Section 4.1.6
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub iat: Option<NumericDate>,
+
+    /// RFC 7519 Section 4.1.7
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jti: Option<String>,
+
+    /// Other claims that may appear in the claims set.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for Claims<T> {
+    fn default() -> Self {
+        Self {
+            iss: None,
+            sub: None,
+            aud: None,
+            exp: None,
+            nbf: None,
+            iat: None,
+            jti: None,
+            oth: T::default(),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Proof-of-Possession Confirmation
+//!
+//! A proof-of-possession token is bound to a key held by its presenter, which
+//! is identified by the `cnf` (confirmation) claim ([RFC 7800]). The
+//! recipient accepts the token only from a presenter which demonstrates
+//! possession of that key, for example by signing a DPoP proof (see
+//! [`crate::dpop`]) or by authenticating with a client certificate.
+//!
+//! Once possession of the key has been demonstrated, the recipient checks
+//! that the key matches the confirmation method with
+//! [`Confirmation::confirm()`], or with [`Confirmation::confirm_resolved()`]
+//! if the key was resolved from trusted storage by its key identifier.
+//!
+//! [RFC 7800]: https://datatracker.ietf.org/doc/html/rfc7800
+
+use alloc::string::String;
+
+use jose_jwk::{Jwk, Key};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{thumbprint, Reason};
+
+/// The confirmation claim of a proof-of-possession token (i.e. `cnf`)
+///
+/// This type contains the confirmation methods defined in [RFC 7800 Section
+/// 3.1] and the JWK Thumbprint method of [RFC 9449 Section 6.1]. Any other
+/// methods (e.g. `x5t#S256`) are collected in `oth`.
+///
+/// [RFC 7800 Section 3.1]: https://datatracker.ietf.org/doc/html/rfc7800#section-3.1
+/// [RFC 9449 Section 6.1]: https://datatracker.ietf.org/doc/html/rfc9449#section-6.1
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Confirmation {
+    /// RFC 7800 Section 3.2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jwk: Option<Jwk>,
+
+    /// RFC 7800 Section 3.3
+    ///
+    /// This is an encrypted JWK in the JWE compact serialization.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jwe: Option<String>,
+
+    /// RFC 7800 Section 3.4
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub kid: Option<String>,
+
+    /// RFC 7800 Section 3.5
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jku: Option<String>,
+
+    /// RFC 9449 Section 6.1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jkt: Option<String>,
+
+    /// Other confirmation methods.
+    #[serde(flatten)]
+    pub oth: Map<String, Value>,
+}
+
+impl Confirmation {
+    /// Creates a confirmation by the JWK Thumbprint of the key (i.e. `jkt`).
+    ///
+    /// Returns `None` if the key type has no thumbprint.
+    pub fn thumbprint(key: &Key) -> Option<Self> {
+        Some(Self {
+            jkt: Some(thumbprint(key)?),
+            ..Default::default()
+        })
+    }
+
+    /// Checks that the presenter's key matches the confirmation methods.
+    ///
+    /// The key must have the thumbprint (i.e. `jkt`) and the public key
+    /// members (i.e. `jwk`) of each method which is present, and at least one
+    /// of these methods must be present. Since a symmetric key has no public
+    /// members, it never matches an embedded key.
+    ///
+    /// The key is typically asserted by the presenter itself (e.g. the `jwk`
+    /// of a DPoP proof), so its key identifier proves nothing and the `kid`
+    /// method is not checked: a confirmation by `kid` alone always fails. Use
+    /// [`Confirmation::confirm_resolved()`] for a key which was resolved from
+    /// trusted storage.
+    ///
+    /// An encrypted key (i.e. `jwe`) must first be decrypted and confirmed
+    /// as an embedded key. A key set URL (i.e. `jku`) only identifies where
+    /// the key identifier may be resolved, so it is not checked here.
+    pub fn confirm(&self, key: &Key) -> Result<(), Reason> {
+        if self.jkt.is_none() && self.jwk.is_none() {
+            return Err(Reason::Confirmation);
+        }
+
+        self.matches(key)
+    }
+
+    /// Checks that a key resolved from trusted storage matches the
+    /// confirmation methods.
+    ///
+    /// The key must have been resolved by the caller from storage it trusts
+    /// (e.g. the key set registered for the presenter), never taken from the
+    /// presentation, since its key identifier (i.e. `kid`) is matched as
+    /// well. Otherwise, this is the same as [`Confirmation::confirm()`].
+    pub fn confirm_resolved(&self, key: &Jwk) -> Result<(), Reason> {
+        if self.jkt.is_none() && self.kid.is_none() && self.jwk.is_none() {
+            return Err(Reason::Confirmation);
+        }
+
+        if let Some(kid) = &self.kid {
+            if key.prm.kid.as_ref() != Some(kid) {
+                return Err(Reason::Confirmation);
+            }
+        }
+
+        self.matches(&key.key)
+    }
+
+    /// Checks the key against the `jkt` and `jwk` methods which are present.
+    fn matches(&self, key: &Key) -> Result<(), Reason> {
+        if let Some(jkt) = &self.jkt {
+            if thumbprint(key).as_ref() != Some(jkt) {
+                return Err(Reason::Confirmation);
+            }
+        }
+
+        if let Some(jwk) = &self.jwk {
+            if !public_eq(&jwk.key, key) {
+                return Err(Reason::Confirmation);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the public members of the keys are equal.
+fn public_eq(lhs: &Key, rhs: &Key) -> bool {
+    match (lhs, rhs) {
+        (Key::Ec(l), Key::Ec(r)) => l.crv == r.crv && l.x == r.x && l.y == r.y,
+        (Key::Rsa(l), Key::Rsa(r)) => l.n == r.n && l.e == r.e,
+        (Key::Okp(l), Key::Okp(r)) => l.crv == r.crv && l.x == r.x,
+        _ => false,
+    }
+}
@@ -0,0 +1,314 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! DPoP Proofs
+//!
+//! Demonstrating Proof of Possession ([RFC 9449]) binds an access token to a
+//! key held by the client. With each request, the client sends a DPoP proof:
+//! a JWT signed by that key, with the public key embedded in its header. The
+//! proof names the HTTP method and URI of the request and, when an access
+//! token is presented, the hash of the access token.
+//!
+//! The client creates proofs with [`sign()`]. The server verifies them with
+//! [`decode()`], which returns the JWK Thumbprint of the key. This is compared
+//! with the `jkt` confirmation method of a bound access token, or is used as
+//! the confirmation method of a newly issued one.
+//!
+//! [RFC 9449]: https://datatracker.ietf.org/doc/html/rfc9449
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_b64::serde::Bytes;
+use jose_jwa::Signing;
+use jose_jwk::{Jwk, Key};
+use jose_jws::crypto::{Signer, SigningKey};
+use jose_jws::{Flattened, Protected};
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::jws::{claims, typ};
+use crate::{thumbprint, Claim, Claims, Clock, Error, NumericDate, Reason, VerifyingKeys};
+
+/// The token type of a DPoP proof (RFC 9449 Section 4.2).
+pub const TYP: &str = "dpop+jwt";
+
+/// The claims of a DPoP proof
+///
+/// The claims defined in [RFC 9449 Section 4.2] (see [`Claims`]).
+///
+/// [RFC 9449 Section 4.2]: https://datatracker.ietf.org/doc/html/rfc9449#section-4.2
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proof<T = Map<String, Value>> {
+    /// The HTTP method of the request.
+    pub htm: String,
+
+    /// The HTTP URI of the request, without query and fragment parts.
+    pub htu: String,
+
+    /// The hash of the access token (see [`hash()`]).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ath: Option<Bytes>,
+
+    /// A nonce provided by the server.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<String>,
+
+    /// Other claims that may appear in the proof.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Proof<T> {
+    /// Creates a proof for a request with the HTTP method and URI.
+    pub fn new(htm: impl Into<String>, htu: impl Into<String>) -> Self {
+        Self {
+            htm: htm.into(),
+            htu: htu.into(),
+            ath: None,
+            nonce: None,
+            oth: T::default(),
+        }
+    }
+
+    /// Creates the claims of the proof, issued at the given time with a
+    /// random JWT ID.
+    pub fn claims(self, rng: &mut (impl CryptoRng + RngCore), now: NumericDate) -> Claims<Self> {
+        let mut jti = [0u8; 16];
+        rng.fill_bytes(&mut jti);
+
+        Claims {
+            iss: None,
+            sub: None,
+            aud: None,
+            exp: None,
+            nbf: None,
+            iat: Some(now),
+            jti: Some(Base64UrlUnpadded::encode_string(&jti)),
+            oth: self,
+        }
+    }
+}
+
+/// Computes the access token hash of a DPoP proof (i.e. `ath`).
+///
+/// Unlike the hashes in an ID Token, this is the entire SHA-256 hash of the
+/// access token (Section 4.2).
+pub fn hash(token: &str) -> Vec<u8> {
+    Sha256::digest(token).to_vec()
+}
+
+/// Returns whether the key contains no private material.
+///
+/// A symmetric key is never public.
+fn public(key: &Key) -> bool {
+    match key {
+        Key::Ec(ec) => ec.d.is_none(),
+        Key::Rsa(rsa) => rsa.prv.is_none(),
+        Key::Okp(okp) => okp.d.is_none(),
+        _ => false,
+    }
+}
+
+/// Signs a DPoP proof, returning the token in the compact serialization.
+///
+/// The header contains the `dpop+jwt` token type, the algorithm and the
+/// public key, which must correspond to the signing key. This fails with
+/// [`Reason::Key`] if the public key contains private material.
+pub fn sign<'a, T, K>(
+    claims: &Claims<Proof<T>>,
+    key: &'a K,
+    rng: &mut (impl CryptoRng + RngCore),
+    alg: Signing,
+    jwk: Jwk,
+) -> Result<String, Error<K::StartError>>
+where
+    T: Serialize,
+    K: SigningKey<'a> + ?Sized,
+    K::Signer: Signer<FinishError = K::StartError>,
+{
+    if !public(&jwk.key) {
+        return Err(Error::Rejected(Reason::Key));
+    }
+
+    let header = Protected {
+        oth: jose_jws::Unprotected {
+            alg: Some(alg),
+            typ: Some(TYP.into()),
+            jwk: Some(jwk),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    crate::encode(claims, key, rng, header)
+}
+
+/// Normalizes an HTTP URI for comparison (Section 4.3).
+///
+/// The query and fragment are ignored. The scheme and authority are
+/// case-insensitive and the default port of the scheme is equivalent to its
+/// omission, as is an empty path to `/` (RFC 3986 Section 6.2.3).
+fn normalize(uri: &str) -> Option<String> {
+    let uri = &uri[..uri.find(['?', '#']).unwrap_or(uri.len())];
+    let (scheme, rest) = uri.split_once("://")?;
+    let scheme = scheme.to_ascii_lowercase();
+
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+
+    let mut authority = authority.to_ascii_lowercase();
+    let port = match scheme.as_str() {
+        "http" => ":80",
+        "https" => ":443",
+        _ => "",
+    };
+
+    if !port.is_empty() && authority.ends_with(port) {
+        authority.truncate(authority.len() - port.len());
+    }
+
+    Some(format!("{}://{}{}", scheme, authority, path))
+}
+
+/// A policy for validating a DPoP proof
+///
+/// A proof is bound to a single HTTP request by its method and URI (i.e.
+/// `htm` and `htu`). It may also be bound to a nonce provided by the server
+/// and to the access token presented with it.
+///
+/// The JWT ID is not checked for replay; the server may record it, for
+/// example in an [`crate::assertion::ReplayCache`], until the proof is no
+/// longer fresh.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The token type and freshness of the proof.
+    ///
+    /// [`Validation::new()`] requires the `dpop+jwt` type and the `iat` and
+    /// `jti` claims. It sets `max_age` so that proofs issued more than 60
+    /// seconds ago are rejected (Section 4.3).
+    pub jwt: crate::Validation<C>,
+
+    /// The HTTP method of the request (i.e. `htm`).
+    pub htm: String,
+
+    /// The HTTP URI of the request (i.e. `htu`).
+    pub htu: String,
+
+    /// The nonce most recently provided by the server (i.e. `nonce`).
+    pub nonce: Option<String>,
+
+    /// The access token presented with the proof.
+    ///
+    /// If set, the proof must contain its hash (i.e. `ath`).
+    pub access_token: Option<String>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for proofs of a request with the HTTP method and URI.
+    pub fn new(clock: C, htm: impl Into<String>, htu: impl Into<String>) -> Self {
+        let mut jwt = crate::Validation::new(clock);
+        jwt.types = vec![TYP.into()];
+        jwt.required = vec![Claim::Iat, Claim::Jti];
+        jwt.max_age = Some(60);
+
+        Self {
+            jwt,
+            htm: htm.into(),
+            htu: htu.into(),
+            nonce: None,
+            access_token: None,
+        }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of a proof.
+    ///
+    /// This performs the checks of Section 4.3 which concern the claims.
+    pub fn validate<T>(&self, typ: Option<&str>, claims: &Claims<Proof<T>>) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+        let proof = &claims.oth;
+
+        if proof.htm != self.htm {
+            return Err(Reason::Method);
+        }
+
+        let htu = normalize(&proof.htu).ok_or(Reason::Uri)?;
+        if Some(htu) != normalize(&self.htu) {
+            return Err(Reason::Uri);
+        }
+
+        if let Some(nonce) = &self.nonce {
+            if proof.nonce.as_ref() != Some(nonce) {
+                return Err(Reason::Nonce);
+            }
+        }
+
+        if let Some(token) = &self.access_token {
+            let ath = proof.ath.as_deref().map(|x| &x[..]);
+            if ath != Some(&hash(token)[..]) {
+                return Err(Reason::AccessTokenHash);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A verified DPoP proof
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Verified<T = Map<String, Value>> {
+    /// The claims of the proof.
+    pub claims: Claims<Proof<T>>,
+
+    /// The public key which signed the proof (i.e. `jwk`).
+    ///
+    /// A bound access token is confirmed with this key (see
+    /// [`crate::cnf::Confirmation::confirm()`]).
+    pub jwk: Jwk,
+
+    /// The JWK Thumbprint of the key which signed the proof (i.e. `jkt`).
+    pub jkt: String,
+}
+
+/// Verifies a DPoP proof in the compact serialization and validates its
+/// claims.
+///
+/// The proof is verified with the public key embedded in its header, which
+/// is converted to the verifying key `K`. The proof is rejected with
+/// [`Reason::Key`] if the embedded key is absent, cannot be converted or
+/// contains private material.
+pub fn decode<T, K, E>(
+    token: &str,
+    validation: &Validation<impl Clock>,
+) -> Result<Verified<T>, Error<E>>
+where
+    T: DeserializeOwned,
+    K: for<'a> TryFrom<&'a Key>,
+    K: for<'a> VerifyingKeys<'a, E>,
+{
+    let jws: Flattened = token.parse().map_err(|_| Error::Malformed)?;
+
+    let prot = jws.signature.protected.as_ref();
+    let jwk = prot.and_then(|x| x.oth.jwk.as_ref()).ok_or(Reason::Key)?;
+    if !public(&jwk.key) {
+        return Err(Error::Rejected(Reason::Key));
+    }
+
+    let jkt = thumbprint(&jwk.key).ok_or(Reason::Key)?;
+    let key = K::try_from(&jwk.key).map_err(|_| Reason::Key)?;
+    key.verify_jwt(&jws)?;
+
+    let claims = claims(&jws)?;
+    validation.validate(typ(&jws), &claims)?;
+    Ok(Verified {
+        claims,
+        jwk: jwk.clone(),
+        jkt,
+    })
+}
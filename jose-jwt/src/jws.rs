@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::string::{String, ToString};
+
+use jose_jwa::Signing;
+use jose_jws::crypto::{Signer, SigningKey, Verifier, VerifyingKey};
+use jose_jws::{Flattened, Protected};
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Claims, Clock, Error, Reason, Validation};
+
+/// Signs a JWT Claims Set, returning the token in the compact serialization.
+///
+/// The algorithm (i.e. `alg`) and any other header parameters are taken from
+/// the supplied protected header. The unencoded payload option (RFC 7797)
+/// cannot be used with a JWT.
+pub fn encode<'a, T, K>(
+    claims: &Claims<T>,
+    key: &'a K,
+    rng: &mut (impl CryptoRng + RngCore),
+    header: Protected,
+) -> Result<String, Error<K::StartError>>
+where
+    T: Serialize,
+    K: SigningKey<'a> + ?Sized,
+    K::Signer: Signer<FinishError = K::StartError>,
+{
+    if !header.b64 {
+        return Err(Error::Malformed);
+    }
+
+    // Unsecured tokens may only be created explicitly (see `unsecured`).
+    if header.oth.alg == Some(Signing::Null) {
+        return Err(Error::Unsecured);
+    }
+
+    let payload = serde_json::to_vec(claims).map_err(|_| Error::Malformed)?;
+    let signer = key.sign(Some(header), None).map_err(Error::Crypto)?;
+    let signature = sign(signer, &payload, rng).map_err(Error::Crypto)?;
+
+    let jws = Flattened {
+        payload: Some(payload.into()),
+        signature,
+    };
+
+    Ok(jws.to_string())
+}
+
+/// A key, or set of keys, which verifies the signature of a JWT
+///
+/// This is implemented for every [`VerifyingKey`] of a flattened JWS (e.g. a
+/// single key, a slice of keys or a [`jose_jws::crypto::KeySet`]). The
+/// functions which verify a JWT require it for every lifetime (i.e.
+/// `V: for<'a> VerifyingKeys<'a, E>`).
+pub trait VerifyingKeys<'a, E> {
+    /// Verifies the signature of a JWS containing a JWT.
+    ///
+    /// Unencoded payloads (RFC 7797) and unsecured tokens are rejected.
+    fn verify_jwt(&'a self, jws: &'a Flattened) -> Result<(), Error<E>>;
+}
+
+impl<'a, V, E> VerifyingKeys<'a, E> for V
+where
+    V: VerifyingKey<'a, &'a Flattened, StartError = E> + ?Sized,
+    V::Verifier: Verifier<'a, FinishError = E>,
+{
+    fn verify_jwt(&'a self, jws: &'a Flattened) -> Result<(), Error<E>> {
+        let prot = jws.signature.protected.as_ref();
+        if prot.map_or(false, |x| !x.b64) {
+            return Err(Error::Malformed);
+        }
+
+        // Never rely on the key to reject unsecured tokens (RFC 8725 Section 3.1).
+        let head = jws.signature.header.as_ref();
+        let alg = prot.and_then(|x| x.oth.alg).or(head.and_then(|x| x.alg));
+        if alg == Some(Signing::Null) {
+            return Err(Error::Unsecured);
+        }
+
+        let payload = jws.payload.as_deref().ok_or(Error::Malformed)?;
+        let ver = self.verify(jws).map_err(Error::Crypto)?;
+        check(ver, payload).map_err(Error::Crypto)
+    }
+}
+
+/// Verifies a JWT in the compact serialization and validates its claims.
+///
+/// The signature must be valid for one of the keys; a set of keys may be
+/// supplied as a [`jose_jws::crypto::KeySet`]. No claims are parsed until the
+/// signature has been verified.
+pub fn decode<C, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<C>, Error<E>>
+where
+    C: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })
+}
+
+/// Verifies a JWT in the compact serialization, then parses its claims and
+/// validates them, with the protected header, using the supplied policy.
+pub(crate) fn decode_with<C, V, E>(
+    token: &str,
+    keys: &V,
+    validate: impl FnOnce(&Protected, &Claims<C>) -> Result<(), Reason>,
+) -> Result<Claims<C>, Error<E>>
+where
+    C: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    let jws = verified(token, keys)?;
+    let prot = jws.signature.protected.as_ref().ok_or(Error::Malformed)?;
+    let claims = claims(&jws)?;
+    validate(prot, &claims)?;
+    Ok(claims)
+}
+
+/// Parses a JWS in the compact serialization and verifies its signature.
+pub(crate) fn verified<V, E>(token: &str, keys: &V) -> Result<Flattened, Error<E>>
+where
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    let jws: Flattened = token.parse().map_err(|_| Error::Malformed)?;
+    keys.verify_jwt(&jws)?;
+    Ok(jws)
+}
+
+/// Parses the claims of a verified JWS.
+pub(crate) fn claims<C: DeserializeOwned, E>(jws: &Flattened) -> Result<Claims<C>, Error<E>> {
+    let payload = jws.payload.as_deref().ok_or(Error::Malformed)?;
+    serde_json::from_slice(payload).map_err(|_| Error::Malformed)
+}
+
+/// Returns the token type (i.e. `typ`) of a JWS.
+pub(crate) fn typ(jws: &Flattened) -> Option<&str> {
+    let prot = jws.signature.protected.as_ref();
+    prot.and_then(|x| x.oth.typ.as_deref())
+}
+
+/// Signs the payload.
+fn sign<T: Signer>(
+    mut signer: T,
+    payload: &[u8],
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<jose_jws::Signature, T::FinishError> {
+    signer.update(payload)?;
+    signer.finish(rng)
+}
+
+/// Verifies the signature over the payload.
+fn check<'a, T: Verifier<'a>>(mut ver: T, payload: &[u8]) -> Result<(), T::FinishError> {
+    ver.update(payload)?;
+    ver.finish()
+}
@@ -15,3 +15,58 @@
     unused_lifetimes,
     unused_qualifications
 )]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod access;
+pub mod assertion;
+pub mod cnf;
+pub mod dpop;
+pub mod logout;
+pub mod nested;
+pub mod oidc;
+pub mod request;
+pub mod sd;
+pub mod secevent;
+pub mod unsecured;
+
+mod claims;
+mod jws;
+mod thumbprint;
+mod validation;
+
+pub use claims::{Audience, Claims, NumericDate, Scope};
+pub use jws::{decode, encode, VerifyingKeys};
+pub use thumbprint::thumbprint;
+pub use validation::{Claim, Clock, Reason, Validation};
+
+#[cfg(feature = "std")]
+pub use validation::SystemClock;
+
+/// An error that occurs while processing a JWT.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// The token could not be parsed.
+    Malformed,
+
+    /// The token has an unexpected content type (i.e. `cty`).
+    ContentType,
+
+    /// The token is unsecured (i.e. `alg` is `none`).
+    Unsecured,
+
+    /// A cryptographic operation failed.
+    Crypto(E),
+
+    /// The token was rejected by the validation policy.
+    Rejected(Reason),
+}
+
+impl<E> From<Reason> for Error<E> {
+    fn from(value: Reason) -> Self {
+        Error::Rejected(value)
+    }
+}
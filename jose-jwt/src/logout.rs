@@ -0,0 +1,245 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! OpenID Connect Back-Channel Logout Tokens
+//!
+//! When an end-user logs out, the OpenID Provider sends a Logout Token to
+//! each relying party with which the end-user has a session ([OpenID Connect
+//! Back-Channel Logout 1.0 Section 2.4]). A Logout Token is a
+//! [Security Event Token](crate::secevent) containing the back-channel logout
+//! event, which identifies the end-user (i.e. `sub`), the session (i.e.
+//! `sid`) or both.
+//!
+//! The OpenID Provider creates Logout Tokens with [`Logout`], once for each
+//! relying party. The relying party validates them with [`decode()`].
+//!
+//! [OpenID Connect Back-Channel Logout 1.0 Section 2.4]: https://openid.net/specs/openid-connect-backchannel-1_0.html#LogoutToken
+
+use alloc::{string::String, vec};
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_jws::crypto::{Signer, SigningKey};
+use jose_jws::Protected;
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::jws::decode_with;
+use crate::secevent::{Events, SecurityEventToken};
+use crate::{Claim, Claims, Clock, Error, NumericDate, Reason, VerifyingKeys};
+
+/// The token type of a Logout Token (Section 2.4).
+pub const TYP: &str = "logout+jwt";
+
+/// The URI of the back-channel logout event.
+pub const EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// The logout claims of a Logout Token
+///
+/// The claims defined in [OpenID Connect Back-Channel Logout 1.0 Section 2.4]
+/// (see [`Claims`]), nested in a [`SecurityEventToken`].
+///
+/// [OpenID Connect Back-Channel Logout 1.0 Section 2.4]: https://openid.net/specs/openid-connect-backchannel-1_0.html#LogoutToken
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogoutToken<T = Map<String, Value>> {
+    /// The session identifier.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sid: Option<String>,
+
+    /// A nonce, which is prohibited so that a Logout Token cannot be used as
+    /// an ID Token.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<String>,
+
+    /// Other claims that may appear in the token.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for LogoutToken<T> {
+    fn default() -> Self {
+        Self {
+            sid: None,
+            nonce: None,
+            oth: T::default(),
+        }
+    }
+}
+
+/// A Logout Token builder
+///
+/// Each token identifies the OpenID Provider as its issuer, the end-user
+/// and/or the session being logged out, and has a random JWT ID. One token is
+/// created for each relying party, which is its audience.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Logout {
+    /// The issuer identifier of the OpenID Provider (i.e. `iss`).
+    pub issuer: String,
+
+    /// The end-user being logged out (i.e. `sub`).
+    pub sub: Option<String>,
+
+    /// The session being logged out (i.e. `sid`).
+    pub sid: Option<String>,
+
+    /// The lifetime of each token, in seconds.
+    pub lifetime: u64,
+}
+
+impl Logout {
+    /// Creates a builder for Logout Tokens from the OpenID Provider.
+    ///
+    /// At least one of `sub` and `sid` must be set before signing. Tokens are
+    /// valid for 120 seconds by default.
+    pub fn new(issuer: impl Into<String>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            sub: None,
+            sid: None,
+            lifetime: 120,
+        }
+    }
+
+    /// Creates the claims of a token for the relying party, identified by its
+    /// client identifier, issued at the given time.
+    ///
+    /// The expiration time saturates at the latest representable date.
+    pub fn claims(
+        &self,
+        rng: &mut (impl CryptoRng + RngCore),
+        client_id: &str,
+        now: NumericDate,
+    ) -> Claims<SecurityEventToken<LogoutToken>> {
+        let mut jti = [0u8; 16];
+        rng.fill_bytes(&mut jti);
+
+        let mut events = Events::new();
+        events.insert(EVENT.into(), Map::new());
+
+        Claims {
+            iss: Some(self.issuer.clone()),
+            sub: self.sub.clone(),
+            aud: Some(client_id.into()),
+            exp: Some(now.saturating_add(self.lifetime)),
+            iat: Some(now),
+            jti: Some(Base64UrlUnpadded::encode_string(&jti)),
+            oth: SecurityEventToken {
+                events,
+                oth: LogoutToken {
+                    sid: self.sid.clone(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Creates a signed token for the relying party, issued at the given
+    /// time.
+    ///
+    /// The algorithm (i.e. `alg`) is taken from the protected header and the
+    /// token type is set to `logout+jwt`. This fails with
+    /// [`Reason::Session`] if neither `sub` nor `sid` is set.
+    pub fn sign<'a, K>(
+        &self,
+        key: &'a K,
+        rng: &mut (impl CryptoRng + RngCore),
+        mut header: Protected,
+        client_id: &str,
+        now: NumericDate,
+    ) -> Result<String, Error<K::StartError>>
+    where
+        K: SigningKey<'a> + ?Sized,
+        K::Signer: Signer<FinishError = K::StartError>,
+    {
+        if self.sub.is_none() && self.sid.is_none() {
+            return Err(Error::Rejected(Reason::Session));
+        }
+
+        header.oth.typ = Some(TYP.into());
+        let claims = self.claims(rng, client_id, now);
+        crate::encode(&claims, key, rng, header)
+    }
+}
+
+/// A policy for validating a Logout Token
+///
+/// A Logout Token must contain the back-channel logout event and identify
+/// the end-user, the session or both (i.e. `sub` and `sid`). It must not
+/// contain a nonce, so that it cannot be mistaken for an ID Token (Section
+/// 2.4).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The token type, issuer, audience and lifetime of the token.
+    ///
+    /// [`Validation::new()`] accepts the `logout+jwt` and the generic
+    /// `secevent+jwt` types. It requires the OpenID Provider as the issuer,
+    /// the relying party as an audience and the `iss`, `aud`, `iat`, `exp`
+    /// and `jti` claims (Section 2.6).
+    pub jwt: crate::Validation<C>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for tokens from the issuer to the relying party,
+    /// which is identified by its client identifier.
+    pub fn new(clock: C, issuer: impl Into<String>, client_id: impl Into<String>) -> Self {
+        let mut jwt = crate::Validation::new(clock);
+        jwt.types = vec![TYP.into(), crate::secevent::TYP.into()];
+        jwt.issuers = vec![issuer.into()];
+        jwt.audiences = vec![client_id.into()];
+        jwt.required = vec![Claim::Iss, Claim::Aud, Claim::Iat, Claim::Exp, Claim::Jti];
+
+        Self { jwt }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of a token.
+    ///
+    /// This performs the checks of Section 2.6 which concern the claims: the
+    /// token must contain the back-channel logout event, must identify the
+    /// end-user or session and must not contain a nonce.
+    pub fn validate<T>(
+        &self,
+        typ: Option<&str>,
+        claims: &Claims<SecurityEventToken<LogoutToken<T>>>,
+    ) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+
+        if !claims.oth.events.contains_key(EVENT) {
+            return Err(Reason::Events);
+        }
+
+        let logout = &claims.oth.oth;
+        if claims.sub.is_none() && logout.sid.is_none() {
+            return Err(Reason::Session);
+        }
+
+        if logout.nonce.is_some() {
+            return Err(Reason::Nonce);
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies a Logout Token in the compact serialization and validates its
+/// claims.
+///
+/// The JWT ID is not checked for replay; the relying party may record it,
+/// for example in an [`crate::assertion::ReplayCache`], until the token
+/// expires.
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<SecurityEventToken<LogoutToken<T>>>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })
+}
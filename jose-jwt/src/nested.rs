@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Nested JWTs
+//!
+//! A Nested JWT is a JWT used as the payload of another JWS or JWE (RFC 7519
+//! Section 5.2). Most commonly, a signed JWT is encrypted so that its claims
+//! are both attributable to the issuer and confidential, as with encrypted
+//! OpenID Connect ID Tokens and request objects.
+//!
+//! The inner JWS is encrypted in its compact serialization and the `cty`
+//! header parameter of the JWE is set to `JWT`. On receipt, the JWE is
+//! decrypted and the signature of the inner JWS is verified before any of its
+//! contents are returned.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::str::from_utf8;
+
+use jose_jwe::crypto::{DecryptingKey, Decryptor, EncryptingKey, Encryptor};
+use jose_jwe::Compact;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{Error, VerifyingKeys};
+
+/// The content type of a Nested JWT (RFC 7519 Section 5.2).
+const CTY: &str = "JWT";
+
+/// Encrypts a signed JWT, producing a Nested JWT.
+///
+/// The JWE is created with the supplied protected header, to which the `cty`
+/// header parameter is added. This fails with [`Error::Malformed`] if the JWS
+/// cannot be represented in the compact serialization.
+pub fn encrypt<'a, K>(
+    key: &'a K,
+    rng: &mut (impl CryptoRng + RngCore),
+    mut prot: jose_jwe::Protected,
+    jws: &jose_jws::Flattened,
+) -> Result<Compact, Error<K::StartError>>
+where
+    K: EncryptingKey<'a>,
+    K::Encryptor: Encryptor<FinishError = K::StartError>,
+{
+    let compact = jws.signature.protected.is_some() && jws.signature.header.is_none();
+    if !compact || jws.payload.is_none() {
+        return Err(Error::Malformed);
+    }
+
+    prot.oth.cty = Some(CTY.into());
+
+    let enc = key.encrypt(rng, Some(prot), None).map_err(Error::Crypto)?;
+    let jwe = seal(enc, jws.to_string().as_bytes()).map_err(Error::Crypto)?;
+    Compact::try_from(jwe).map_err(|_| Error::Malformed)
+}
+
+/// Decrypts a Nested JWT and verifies the inner JWS.
+///
+/// The token must be in the compact serialization and have a `cty` header
+/// parameter of `JWT`. The inner JWS is only returned if its signature is
+/// valid; its payload is the JWT Claims Set.
+pub fn decrypt<D, V, E>(
+    dkey: &D,
+    vkey: &V,
+    rng: &mut (impl CryptoRng + RngCore),
+    token: &str,
+) -> Result<jose_jws::Flattened, Error<E>>
+where
+    D: for<'a> DecryptingKey<'a, &'a jose_jwe::Flattened, StartError = E>,
+    for<'a> <D as DecryptingKey<'a, &'a jose_jwe::Flattened>>::Decryptor:
+        Decryptor<'a, FinishError = E>,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    let jwe: jose_jwe::Flattened = token.parse().map_err(|_| Error::Malformed)?;
+
+    let cty = jwe.protected.as_ref().and_then(|x| x.oth.cty.as_deref());
+    if !cty.map_or(false, |x| x.eq_ignore_ascii_case(CTY)) {
+        return Err(Error::ContentType);
+    }
+
+    let dec = dkey.decrypt(rng, &jwe).map_err(Error::Crypto)?;
+    let plaintext = open(dec, &jwe.ciphertext).map_err(Error::Crypto)?;
+
+    let jws = from_utf8(&plaintext).map_err(|_| Error::Malformed)?;
+    let jws: jose_jws::Flattened = jws.parse().map_err(|_| Error::Malformed)?;
+
+    vkey.verify_jwt(&jws)?;
+    Ok(jws)
+}
+
+/// Encrypts the plaintext.
+fn seal<T: Encryptor>(mut enc: T, plaintext: &[u8]) -> Result<jose_jwe::Flattened, T::FinishError> {
+    enc.update(plaintext)?;
+    enc.finish()
+}
+
+/// Decrypts the ciphertext.
+fn open<'a, T: Decryptor<'a>>(mut dec: T, ciphertext: &[u8]) -> Result<Vec<u8>, T::FinishError> {
+    dec.update(ciphertext)?;
+    dec.finish()
+}
@@ -0,0 +1,258 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! OpenID Connect ID Tokens
+//!
+//! An ID Token is a JWT containing claims about the authentication of an
+//! end-user by an OpenID Provider ([OpenID Connect Core 1.0 Section 2]). It
+//! is validated by the relying party as described in Section 3.1.3.7, which
+//! extends the validation of the registered JWT claims.
+//!
+//! [OpenID Connect Core 1.0 Section 2]: https://openid.net/specs/openid-connect-core-1_0.html#IDToken
+
+use alloc::{string::String, vec, vec::Vec};
+
+use jose_b64::serde::Bytes;
+use jose_jwa::Signing;
+use jose_jws::Protected;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::jws::decode_with;
+use crate::validation::acr;
+use crate::{Claim, Claims, Clock, Error, Reason, VerifyingKeys};
+
+/// The claims of an ID Token
+///
+/// The claims defined in [OpenID Connect Core 1.0 Section 2] (see [`Claims`]).
+///
+/// [OpenID Connect Core 1.0 Section 2]: https://openid.net/specs/openid-connect-core-1_0.html#IDToken
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdToken<T = Map<String, Value>> {
+    /// The value passed in the authentication request.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<String>,
+
+    /// The time when the end-user authenticated.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub auth_time: Option<crate::NumericDate>,
+
+    /// The authentication context class reference.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub acr: Option<String>,
+
+    /// The authentication method references.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub amr: Option<Vec<String>>,
+
+    /// The authorized party, to which the ID Token was issued.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub azp: Option<String>,
+
+    /// The access token hash.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub at_hash: Option<Bytes>,
+
+    /// The authorization code hash.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub c_hash: Option<Bytes>,
+
+    /// Other claims that may appear in the ID Token.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for IdToken<T> {
+    fn default() -> Self {
+        Self {
+            nonce: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            azp: None,
+            at_hash: None,
+            c_hash: None,
+            oth: T::default(),
+        }
+    }
+}
+
+/// Computes the hash of a token issued alongside an ID Token.
+///
+/// This is the left-most half of the hash of the ASCII representation of
+/// the token, using the hash algorithm of the ID Token's signature algorithm
+/// (i.e. `alg`). It is used for the `at_hash` and `c_hash` claims. `None` is
+/// returned if the algorithm does not determine a hash.
+pub fn hash(alg: Signing, token: &str) -> Option<Vec<u8>> {
+    let mut hash = match alg {
+        Signing::Es256 | Signing::Es256K | Signing::Hs256 | Signing::Ps256 | Signing::Rs256 => {
+            Sha256::digest(token).to_vec()
+        }
+
+        Signing::Es384 | Signing::Hs384 | Signing::Ps384 | Signing::Rs384 => {
+            Sha384::digest(token).to_vec()
+        }
+
+        Signing::Es512 | Signing::Hs512 | Signing::Ps512 | Signing::Rs512 => {
+            Sha512::digest(token).to_vec()
+        }
+
+        _ => return None,
+    };
+
+    hash.truncate(hash.len() / 2);
+    Some(hash)
+}
+
+/// A policy for validating an ID Token
+///
+/// An ID Token must be intended for the client: the client must be one of
+/// its audiences and every other audience must be trusted. If there are
+/// several audiences, or an authorized party (i.e. `azp`) is given, the
+/// client must be the authorized party. The remaining claims are checked
+/// against the authentication request and its response, as far as they are
+/// known.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The issuer, the trusted audiences, the lifetime of the ID Token and
+    /// the claims it must contain.
+    ///
+    /// [`Validation::new()`] trusts only the client as an audience and
+    /// requires the `iss`, `sub`, `aud`, `exp` and `iat` claims (Section 2).
+    /// Additional trusted audiences may be added to `audiences`.
+    pub jwt: crate::Validation<C>,
+
+    /// The client identifier of the relying party.
+    pub client_id: String,
+
+    /// The `nonce` sent in the authentication request.
+    ///
+    /// If set, the ID Token must contain the same value.
+    pub nonce: Option<String>,
+
+    /// The `max_age` sent in the authentication request, in seconds.
+    ///
+    /// If set, the ID Token must contain the `auth_time` claim and the
+    /// end-user must have authenticated within this time.
+    pub max_age: Option<u64>,
+
+    /// The acceptable authentication context class references (i.e. `acr`).
+    pub acr: Vec<String>,
+
+    /// The access token issued with the ID Token.
+    ///
+    /// If set, the ID Token must contain a matching `at_hash` claim.
+    pub access_token: Option<String>,
+
+    /// The authorization code issued with the ID Token.
+    ///
+    /// If set, the ID Token must contain a matching `c_hash` claim.
+    pub code: Option<String>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for ID Tokens from the issuer to the client.
+    pub fn new(clock: C, issuer: impl Into<String>, client_id: impl Into<String>) -> Self {
+        let client_id = client_id.into();
+
+        let mut jwt = crate::Validation::new(clock);
+        jwt.issuers = vec![issuer.into()];
+        jwt.audiences = vec![client_id.clone()];
+        jwt.required = vec![Claim::Iss, Claim::Sub, Claim::Aud, Claim::Exp, Claim::Iat];
+
+        Self {
+            jwt,
+            client_id,
+            nonce: None,
+            max_age: None,
+            acr: Vec::new(),
+            access_token: None,
+            code: None,
+        }
+    }
+
+    /// Validates the claims of an ID Token with the given protected header.
+    ///
+    /// This performs the checks of Section 3.1.3.7, other than the signature
+    /// verification, along with those of the `at_hash` and `c_hash` claims.
+    pub fn validate<T>(
+        &self,
+        header: &Protected,
+        claims: &Claims<IdToken<T>>,
+    ) -> Result<(), Reason> {
+        self.jwt.validate(header.oth.typ.as_deref(), claims)?;
+        let id = &claims.oth;
+
+        // Every audience must be trusted.
+        let aud = claims.aud.as_ref().ok_or(Reason::Missing(Claim::Aud))?;
+        if aud
+            .as_slice()
+            .iter()
+            .any(|x| !self.jwt.audiences.contains(x))
+        {
+            return Err(Reason::Audience);
+        }
+
+        // The ID Token must be intended for the client.
+        if !aud.contains(&self.client_id) {
+            return Err(Reason::Audience);
+        }
+
+        // With multiple audiences, the client must be the authorized party.
+        if aud.as_slice().len() > 1 && id.azp.is_none() {
+            return Err(Reason::AuthorizedParty);
+        }
+
+        if id.azp.as_ref().map_or(false, |x| *x != self.client_id) {
+            return Err(Reason::AuthorizedParty);
+        }
+
+        if let Some(nonce) = &self.nonce {
+            if id.nonce.as_ref() != Some(nonce) {
+                return Err(Reason::Nonce);
+            }
+        }
+
+        acr(&self.acr, id.acr.as_ref())?;
+        self.jwt.authenticated(self.max_age, id.auth_time)?;
+
+        let alg = header.oth.alg;
+
+        if let Some(token) = &self.access_token {
+            let expected = alg.and_then(|alg| hash(alg, token));
+            if expected.is_none() || expected.as_deref() != id.at_hash.as_deref().map(|x| &x[..]) {
+                return Err(Reason::AccessTokenHash);
+            }
+        }
+
+        if let Some(code) = &self.code {
+            let expected = alg.and_then(|alg| hash(alg, code));
+            if expected.is_none() || expected.as_deref() != id.c_hash.as_deref().map(|x| &x[..]) {
+                return Err(Reason::CodeHash);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies an ID Token in the compact serialization and validates its
+/// claims.
+///
+/// An encrypted ID Token must first be decrypted with
+/// [`nested::decrypt()`](crate::nested::decrypt()).
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<IdToken<T>>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot, claims)
+    })
+}
@@ -0,0 +1,178 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! JWT-Secured Authorization Requests
+//!
+//! A client may send the parameters of an authorization request as the
+//! claims of a JWT, known as a Request Object ([RFC 9101]). The Request
+//! Object is signed by the client and may also be encrypted to the
+//! authorization server, in which case it is a Nested JWT.
+//!
+//! Only signed Request Objects are accepted; unsecured Request Objects (i.e.
+//! `alg` is `none`) are rejected.
+//!
+//! [RFC 9101]: https://datatracker.ietf.org/doc/html/rfc9101
+
+use alloc::{string::String, vec};
+
+use jose_jwe::crypto::{DecryptingKey, Decryptor};
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::jws::{claims, decode_with, typ};
+use crate::{nested, Claims, Clock, Error, Reason, Scope, VerifyingKeys};
+
+/// The token type of a Request Object (RFC 9101 Section 10.8).
+pub const TYP: &str = "oauth-authz-req+jwt";
+
+/// The claims of a Request Object
+///
+/// The parameters of [RFC 6749 Section 4.1.1] and [OpenID Connect Core 1.0
+/// Section 3.1.2.1] (see [`Claims`]).
+///
+/// [RFC 6749 Section 4.1.1]: https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.1
+/// [OpenID Connect Core 1.0 Section 3.1.2.1]: https://openid.net/specs/openid-connect-core-1_0.html#AuthRequest
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestObject<T = Map<String, Value>> {
+    /// The response type (e.g. `code`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub response_type: Option<String>,
+
+    /// The client identifier.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub client_id: Option<String>,
+
+    /// The redirection endpoint.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub redirect_uri: Option<String>,
+
+    /// The scope of the access request.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub scope: Option<Scope>,
+
+    /// The value returned to the client with the response.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<String>,
+
+    /// The value bound to the ID Token (OpenID Connect).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<String>,
+
+    /// Other parameters of the authorization request.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for RequestObject<T> {
+    fn default() -> Self {
+        Self {
+            response_type: None,
+            client_id: None,
+            redirect_uri: None,
+            scope: None,
+            state: None,
+            nonce: None,
+            oth: T::default(),
+        }
+    }
+}
+
+/// A policy for validating a Request Object
+///
+/// A Request Object carries the parameters of a single authorization
+/// request, so its `client_id` claim must match the `client_id` parameter of
+/// the request which carried it (Section 5).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The token type, issuer and audience of the Request Object.
+    ///
+    /// [`Validation::new()`] requires the token type of Section 10.8, the
+    /// client as the issuer and the authorization server as an audience. To
+    /// accept Request Objects from clients which do not set the token type,
+    /// clear `types`.
+    pub jwt: crate::Validation<C>,
+
+    /// The `client_id` parameter of the outer authorization request.
+    pub client_id: String,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for Request Objects from the client to the
+    /// authorization server.
+    ///
+    /// The `audience` is the issuer identifier of the authorization server.
+    /// The `client_id` is the parameter of the authorization request which
+    /// carried the Request Object; the client is also the issuer of the
+    /// Request Object.
+    pub fn new(clock: C, audience: impl Into<String>, client_id: impl Into<String>) -> Self {
+        let client_id = client_id.into();
+
+        let mut jwt = crate::Validation::new(clock);
+        jwt.issuers = vec![client_id.clone()];
+        jwt.audiences = vec![audience.into()];
+        jwt.types = vec![TYP.into()];
+
+        Self { jwt, client_id }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of a Request Object.
+    ///
+    /// This performs the checks of Section 6.3, other than the decryption
+    /// and signature verification.
+    pub fn validate<T>(
+        &self,
+        typ: Option<&str>,
+        claims: &Claims<RequestObject<T>>,
+    ) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+
+        // The Request Object must be for the outer request (Section 5).
+        if claims.oth.client_id.as_ref() != Some(&self.client_id) {
+            return Err(Reason::ClientId);
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies a signed Request Object and validates its claims.
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<RequestObject<T>>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })
+}
+
+/// Decrypts a signed-then-encrypted Request Object, verifies the signature
+/// and validates its claims.
+///
+/// The Request Object is decrypted with the key of the authorization server
+/// and verified with the keys of the client.
+pub fn decrypt<T, D, V, E>(
+    token: &str,
+    dkey: &D,
+    vkey: &V,
+    rng: &mut (impl CryptoRng + RngCore),
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<RequestObject<T>>, Error<E>>
+where
+    T: DeserializeOwned,
+    D: for<'a> DecryptingKey<'a, &'a jose_jwe::Flattened, StartError = E>,
+    for<'a> <D as DecryptingKey<'a, &'a jose_jwe::Flattened>>::Decryptor:
+        Decryptor<'a, FinishError = E>,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    let jws = nested::decrypt(dkey, vkey, rng, token)?;
+    let claims = claims(&jws)?;
+    validation.validate(typ(&jws), &claims)?;
+    Ok(claims)
+}
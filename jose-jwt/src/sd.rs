@@ -0,0 +1,747 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Selective Disclosure JWTs
+//!
+//! An SD-JWT ([RFC 9901]) is a JWT whose issuer has replaced some claims with
+//! digests of disclosures. Each disclosure contains a random salt and the
+//! claim, so the holder may reveal any subset of the claims to a verifier
+//! without invalidating the issuer's signature. An object claim is replaced
+//! by a digest in the `_sd` array of its object, while an array element is
+//! replaced by a `{"...": <digest>}` placeholder. The hash algorithm is named
+//! by the `_sd_alg` claim.
+//!
+//! The issuer creates an SD-JWT with [`Issuer`]. The holder removes the
+//! disclosures that it does not want to reveal from the [`SdJwt`] and, if
+//! the issuer bound the SD-JWT to the holder's key (i.e. `cnf`), adds a Key
+//! Binding JWT with [`SdJwt::bind()`]. The verifier reconstructs the
+//! disclosed claims with [`decode()`].
+//!
+//! [RFC 9901]: https://datatracker.ietf.org/doc/html/rfc9901
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{format, string::String, string::ToString, vec, vec::Vec};
+use core::fmt::Display;
+use core::str::FromStr;
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_b64::serde::Bytes;
+use jose_b64::stream::Error as ParseError;
+use jose_jwk::Key;
+use jose_jws::crypto::{Signer, SigningKey};
+use jose_jws::{Flattened, Protected};
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::cnf::Confirmation;
+use crate::jws::{typ, verified};
+use crate::{Claim, Claims, Clock, Error, NumericDate, Reason, VerifyingKeys};
+
+/// The token type of a Key Binding JWT (RFC 9901 Section 4.3).
+pub const KB_TYP: &str = "kb+jwt";
+
+/// The claim containing the digests of an object's disclosures.
+const SD: &str = "_sd";
+
+/// The claim naming the hash algorithm of the digests.
+const SD_ALG: &str = "_sd_alg";
+
+/// The confirmation claim, which binds the SD-JWT to the holder's key.
+const CNF: &str = "cnf";
+
+/// The key of an array element placeholder.
+const ELLIPSIS: &str = "...";
+
+/// The hash algorithm of the digests (i.e. `_sd_alg`)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Hash {
+    /// SHA-256
+    #[default]
+    #[serde(rename = "sha-256")]
+    Sha256,
+
+    /// SHA-384
+    #[serde(rename = "sha-384")]
+    Sha384,
+
+    /// SHA-512
+    #[serde(rename = "sha-512")]
+    Sha512,
+}
+
+impl Hash {
+    /// Returns the hash of the ASCII string.
+    fn hash(self, value: &str) -> Vec<u8> {
+        match self {
+            Self::Sha256 => Sha256::digest(value).to_vec(),
+            Self::Sha384 => Sha384::digest(value).to_vec(),
+            Self::Sha512 => Sha512::digest(value).to_vec(),
+        }
+    }
+
+    /// Returns the base64url-encoded hash of the ASCII string.
+    fn digest(self, value: &str) -> String {
+        Base64UrlUnpadded::encode_string(&self.hash(value))
+    }
+
+    /// Reads the hash algorithm of an issuer-signed JWT payload.
+    ///
+    /// SHA-256 is used if `_sd_alg` is absent (Section 4.1.1).
+    fn of(payload: &Map<String, Value>) -> Result<Self, Reason> {
+        match payload.get(SD_ALG) {
+            None => Ok(Self::Sha256),
+            Some(alg) => Self::deserialize(alg).map_err(|_| Reason::Digest),
+        }
+    }
+}
+
+/// Returns a random base64url-encoded salt of 128 bits.
+fn salt(rng: &mut (impl CryptoRng + RngCore)) -> String {
+    let mut salt = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    Base64UrlUnpadded::encode_string(&salt)
+}
+
+/// A disclosure of an object claim or an array element
+///
+/// The digest of a disclosure is computed over its encoded form, which is
+/// preserved when parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disclosure {
+    encoded: String,
+    salt: String,
+    name: Option<String>,
+    value: Value,
+}
+
+impl Disclosure {
+    /// Creates a disclosure with a random salt.
+    ///
+    /// The name is the claim name of an object claim, or `None` for an array
+    /// element.
+    pub fn new(rng: &mut (impl CryptoRng + RngCore), name: Option<&str>, value: Value) -> Self {
+        let salt = salt(rng);
+
+        let array = match name {
+            Some(name) => vec![salt.as_str().into(), name.into(), value.clone()],
+            None => vec![salt.as_str().into(), value.clone()],
+        };
+
+        let json = Value::Array(array).to_string();
+
+        Self {
+            encoded: Base64UrlUnpadded::encode_string(json.as_bytes()),
+            salt,
+            name: name.map(Into::into),
+            value,
+        }
+    }
+
+    /// The salt.
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    /// The claim name, which is `None` for an array element.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The claim value or array element.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Computes the digest of the disclosure.
+    pub fn digest(&self, hash: Hash) -> String {
+        hash.digest(&self.encoded)
+    }
+}
+
+impl FromStr for Disclosure {
+    type Err = ParseError<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let json = Base64UrlUnpadded::decode_vec(s)?;
+        let array: Vec<Value> = serde_json::from_slice(&json).map_err(ParseError::Inner)?;
+
+        let (salt, name, value) = match <[Value; 3]>::try_from(array) {
+            Ok([salt, Value::String(name), value]) => (salt, Some(name), value),
+            Ok(..) => return Err(ParseError::Value),
+            Err(array) => match <[Value; 2]>::try_from(array) {
+                Ok([salt, value]) => (salt, None, value),
+                Err(..) => return Err(ParseError::Length),
+            },
+        };
+
+        let salt = match salt {
+            Value::String(salt) => salt,
+            _ => return Err(ParseError::Value),
+        };
+
+        Ok(Self {
+            encoded: s.into(),
+            salt,
+            name,
+            value,
+        })
+    }
+}
+
+impl Display for Disclosure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+/// Inserts a digest into the `_sd` array of an object.
+///
+/// The digests are kept sorted so that their order reveals nothing about the
+/// original order of the claims (Section 4.2.4.1). Fails if the object has
+/// an `_sd` claim which is not an array.
+fn insert(obj: &mut Map<String, Value>, digest: String) -> Result<(), Reason> {
+    let sd = obj.entry(SD).or_insert_with(|| Value::Array(Vec::new()));
+    let sd = sd.as_array_mut().ok_or(Reason::Digest)?;
+
+    sd.push(digest.into());
+    sd.sort_by(|lhs, rhs| lhs.as_str().cmp(&rhs.as_str()));
+    Ok(())
+}
+
+/// An SD-JWT issuer
+///
+/// Claims are made selectively disclosable by replacing them with digests in
+/// the claims set, while their disclosures are collected. Nested claims must
+/// be concealed before the claim that contains them.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Issuer {
+    /// The hash algorithm of the digests.
+    pub hash: Hash,
+
+    /// The disclosures of the concealed claims.
+    pub disclosures: Vec<Disclosure>,
+}
+
+impl Issuer {
+    /// Creates an issuer using the hash algorithm.
+    pub fn new(hash: Hash) -> Self {
+        Self {
+            hash,
+            disclosures: Vec::new(),
+        }
+    }
+
+    /// Makes a claim of the object selectively disclosable.
+    ///
+    /// Returns `false` if the object does not contain the claim. Fails with
+    /// [`Reason::Digest`], leaving the object unchanged, if the object has an
+    /// `_sd` claim which is not an array.
+    pub fn conceal(
+        &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
+        obj: &mut Map<String, Value>,
+        name: &str,
+    ) -> Result<bool, Reason> {
+        if name == SD || name == ELLIPSIS {
+            return Ok(false);
+        }
+
+        let value = match obj.remove(name) {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+
+        let disclosure = Disclosure::new(rng, Some(name), value);
+        if let Err(reason) = insert(obj, disclosure.digest(self.hash)) {
+            obj.insert(name.into(), disclosure.value);
+            return Err(reason);
+        }
+
+        self.disclosures.push(disclosure);
+        Ok(true)
+    }
+
+    /// Makes an element of the array selectively disclosable, replacing it
+    /// with a placeholder.
+    ///
+    /// Returns `false` if the array does not contain the element.
+    pub fn conceal_element(
+        &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
+        array: &mut [Value],
+        index: usize,
+    ) -> bool {
+        let element = match array.get_mut(index) {
+            Some(element) => element,
+            None => return false,
+        };
+
+        let disclosure = Disclosure::new(rng, None, element.take());
+
+        let mut placeholder = Map::new();
+        placeholder.insert(ELLIPSIS.into(), disclosure.digest(self.hash).into());
+        *element = placeholder.into();
+
+        self.disclosures.push(disclosure);
+        true
+    }
+
+    /// Adds a decoy digest to the object, which hides the number of claims
+    /// that it contains (Section 4.2.5).
+    ///
+    /// Fails with [`Reason::Digest`] if the object has an `_sd` claim which
+    /// is not an array.
+    pub fn decoy(
+        &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
+        obj: &mut Map<String, Value>,
+    ) -> Result<(), Reason> {
+        insert(obj, self.hash.digest(&salt(rng)))
+    }
+
+    /// Signs the claims set, returning the SD-JWT with all disclosures.
+    ///
+    /// The `_sd_alg` claim is added to the claims set. The registered claims
+    /// are always disclosed. The algorithm (i.e. `alg`) and any other header
+    /// parameters are taken from the supplied protected header.
+    pub fn sign<'a, K>(
+        self,
+        mut claims: Claims,
+        key: &'a K,
+        rng: &mut (impl CryptoRng + RngCore),
+        header: Protected,
+    ) -> Result<SdJwt, Error<K::StartError>>
+    where
+        K: SigningKey<'a> + ?Sized,
+        K::Signer: Signer<FinishError = K::StartError>,
+    {
+        let alg = serde_json::to_value(self.hash).map_err(|_| Error::Malformed)?;
+        claims.oth.insert(SD_ALG.into(), alg);
+
+        Ok(SdJwt {
+            jwt: crate::encode(&claims, key, rng, header)?,
+            disclosures: self.disclosures,
+            kb: None,
+        })
+    }
+}
+
+/// The claims of a Key Binding JWT
+///
+/// The claims defined in [RFC 9901 Section 4.3] (see [`Claims`]).
+///
+/// [RFC 9901 Section 4.3]: https://datatracker.ietf.org/doc/html/rfc9901#section-4.3
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBinding<T = Map<String, Value>> {
+    /// The nonce provided by the verifier.
+    pub nonce: String,
+
+    /// The hash of the presentation (see [`SdJwt::sd_hash()`]).
+    pub sd_hash: Bytes,
+
+    /// Other claims that may appear in the token.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+/// An SD-JWT in the `~`-separated serialization (Section 4)
+///
+/// This is the issuer-signed JWT, followed by the disclosures and then the
+/// Key Binding JWT, if any. The holder presents a subset of the claims by
+/// removing the other disclosures.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdJwt {
+    /// The issuer-signed JWT.
+    pub jwt: String,
+
+    /// The disclosures.
+    pub disclosures: Vec<Disclosure>,
+
+    /// The Key Binding JWT.
+    pub kb: Option<String>,
+}
+
+impl SdJwt {
+    /// Computes the hash of the presentation (i.e. `sd_hash`).
+    ///
+    /// This covers the issuer-signed JWT and the disclosures, but not the Key
+    /// Binding JWT, using the hash algorithm of the issuer-signed JWT.
+    pub fn sd_hash(&self) -> Result<Vec<u8>, Reason> {
+        let hash = Hash::of(&payload(&self.jwt).ok_or(Reason::Digest)?)?;
+        Ok(hash.hash(&self.presentation()))
+    }
+
+    /// Adds a Key Binding JWT, signed by the holder's key and issued at the
+    /// given time, to the presentation.
+    ///
+    /// The disclosures must not be changed afterwards. The algorithm (i.e.
+    /// `alg`) is taken from the protected header and the token type is set
+    /// to `kb+jwt`.
+    pub fn bind<'a, K>(
+        &mut self,
+        key: &'a K,
+        rng: &mut (impl CryptoRng + RngCore),
+        mut header: Protected,
+        audience: &str,
+        nonce: &str,
+        now: NumericDate,
+    ) -> Result<(), Error<K::StartError>>
+    where
+        K: SigningKey<'a> + ?Sized,
+        K::Signer: Signer<FinishError = K::StartError>,
+    {
+        let claims = Claims {
+            iss: None,
+            sub: None,
+            aud: Some(audience.into()),
+            exp: None,
+            nbf: None,
+            iat: Some(now),
+            jti: None,
+            oth: KeyBinding {
+                nonce: nonce.into(),
+                sd_hash: self.sd_hash()?.into(),
+                oth: Map::new(),
+            },
+        };
+
+        header.oth.typ = Some(KB_TYP.into());
+        self.kb = Some(crate::encode(&claims, key, rng, header)?);
+        Ok(())
+    }
+
+    /// Returns the serialization without the Key Binding JWT.
+    fn presentation(&self) -> String {
+        let mut out = format!("{}~", self.jwt);
+
+        for disclosure in &self.disclosures {
+            out.push_str(&disclosure.encoded);
+            out.push('~');
+        }
+
+        out
+    }
+}
+
+impl FromStr for SdJwt {
+    type Err = ParseError<serde_json::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('~').collect();
+        if parts.len() < 2 {
+            return Err(ParseError::Length);
+        }
+
+        let kb = parts.pop().filter(|x| !x.is_empty()).map(Into::into);
+        let jwt = parts.remove(0);
+        if jwt.is_empty() {
+            return Err(ParseError::Length);
+        }
+
+        Ok(Self {
+            jwt: jwt.into(),
+            disclosures: parts
+                .into_iter()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+            kb,
+        })
+    }
+}
+
+impl Display for SdJwt {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.presentation())?;
+
+        if let Some(kb) = &self.kb {
+            f.write_str(kb)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the payload of a JWS in the compact serialization, without
+/// verifying it.
+fn payload(jws: &str) -> Option<Map<String, Value>> {
+    let jws: Flattened = jws.parse().ok()?;
+    serde_json::from_slice(jws.payload.as_deref()?).ok()
+}
+
+/// The state of the reconstruction of the disclosed claims (Section 7.1)
+struct Reconstruction<'a> {
+    disclosures: BTreeMap<String, (&'a Disclosure, bool)>,
+    seen: BTreeSet<String>,
+}
+
+impl<'a> Reconstruction<'a> {
+    /// Indexes the disclosures by digest.
+    fn new(hash: Hash, disclosures: &'a [Disclosure]) -> Result<Self, Reason> {
+        let mut map = BTreeMap::new();
+
+        for disclosure in disclosures {
+            if map
+                .insert(disclosure.digest(hash), (disclosure, false))
+                .is_some()
+            {
+                return Err(Reason::Disclosure);
+            }
+        }
+
+        Ok(Self {
+            disclosures: map,
+            seen: BTreeSet::new(),
+        })
+    }
+
+    /// Returns the disclosure of a digest, if disclosed.
+    ///
+    /// Every digest may only appear once.
+    fn disclose(&mut self, digest: &Value) -> Result<Option<&'a Disclosure>, Reason> {
+        let digest = digest.as_str().ok_or(Reason::Digest)?;
+        if !self.seen.insert(digest.into()) {
+            return Err(Reason::Digest);
+        }
+
+        Ok(self.disclosures.get_mut(digest).map(|(disclosure, used)| {
+            *used = true;
+            *disclosure
+        }))
+    }
+
+    /// Replaces the digests within the value with the disclosed claims,
+    /// removing those which are not disclosed.
+    fn process(&mut self, value: &mut Value) -> Result<(), Reason> {
+        match value {
+            Value::Object(obj) => {
+                let sd = obj.remove(SD);
+
+                for value in obj.values_mut() {
+                    self.process(value)?;
+                }
+
+                let sd = match sd {
+                    Some(Value::Array(sd)) => sd,
+                    Some(..) => return Err(Reason::Digest),
+                    None => return Ok(()),
+                };
+
+                for digest in &sd {
+                    let disclosure = match self.disclose(digest)? {
+                        Some(disclosure) => disclosure,
+                        None => continue,
+                    };
+
+                    let name = disclosure.name().ok_or(Reason::Disclosure)?;
+                    if name == SD || name == ELLIPSIS || obj.contains_key(name) {
+                        return Err(Reason::Disclosure);
+                    }
+
+                    let mut value = disclosure.value().clone();
+                    self.process(&mut value)?;
+                    obj.insert(name.into(), value);
+                }
+            }
+
+            Value::Array(array) => {
+                for mut element in core::mem::take(array) {
+                    let placeholder = match &element {
+                        Value::Object(obj) if obj.len() == 1 => obj.get(ELLIPSIS),
+                        _ => None,
+                    };
+
+                    if let Some(digest) = placeholder {
+                        let disclosure = match self.disclose(digest)? {
+                            Some(disclosure) => disclosure,
+                            None => continue,
+                        };
+
+                        if disclosure.name().is_some() {
+                            return Err(Reason::Disclosure);
+                        }
+
+                        element = disclosure.value().clone();
+                    }
+
+                    self.process(&mut element)?;
+                    array.push(element);
+                }
+            }
+
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every disclosure was referenced by a digest.
+    fn finish(self) -> Result<(), Reason> {
+        if self.disclosures.values().any(|(_, used)| !used) {
+            return Err(Reason::Disclosure);
+        }
+
+        Ok(())
+    }
+}
+
+/// A policy for validating a Key Binding JWT
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Binding {
+    /// The verifier, which must be the audience (i.e. `aud`).
+    pub audience: String,
+
+    /// The nonce provided by the verifier (i.e. `nonce`).
+    ///
+    /// The verifier must generate a fresh nonce for each presentation, which
+    /// is what prevents the replay of a Key Binding JWT.
+    pub nonce: String,
+
+    /// The maximum time, in seconds, since the Key Binding JWT was issued.
+    pub max_age: u64,
+}
+
+impl Binding {
+    /// Creates a policy for Key Binding JWTs for the verifier, which provided
+    /// the nonce.
+    ///
+    /// Key Binding JWTs are accepted for 300 seconds by default.
+    pub fn new(audience: impl Into<String>, nonce: impl Into<String>) -> Self {
+        Self {
+            audience: audience.into(),
+            nonce: nonce.into(),
+            max_age: 300,
+        }
+    }
+}
+
+/// A policy for validating an SD-JWT
+///
+/// If `key_binding` is set, a Key Binding JWT signed by the key confirmed in
+/// the issuer-signed payload (i.e. `cnf.jwk`) is required. Otherwise, any
+/// Key Binding JWT is ignored.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The issuer, token type and lifetime of the issuer-signed JWT.
+    ///
+    /// These are checked against the reconstructed claims, so a claim which
+    /// is concealed and not disclosed counts as missing. [`Validation::new()`]
+    /// trusts only the issuer and requires only `exp`; the holder may present
+    /// the SD-JWT to any verifier, so no audience is expected unless one is
+    /// added to `audiences`. The clock and leeway also apply to the Key
+    /// Binding JWT.
+    pub jwt: crate::Validation<C>,
+
+    /// The policy for the Key Binding JWT.
+    pub key_binding: Option<Binding>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for SD-JWTs from the issuer.
+    pub fn new(clock: C, issuer: impl Into<String>) -> Self {
+        let mut jwt = crate::Validation::new(clock);
+        jwt.issuers = vec![issuer.into()];
+
+        Self {
+            jwt,
+            key_binding: None,
+        }
+    }
+}
+
+/// Verifies an SD-JWT and reconstructs the disclosed claims.
+///
+/// The issuer-signed JWT must be signed by one of the keys. The claims
+/// contain only those claims which are disclosed; other digests are removed,
+/// as are `_sd` and `_sd_alg`. The SD-JWT is rejected with [`Reason::Digest`]
+/// if a digest appears more than once, and with [`Reason::Disclosure`] if a
+/// disclosure is not referenced, conflicts with another claim or discloses
+/// any part of the confirmation claim (i.e. `cnf`).
+///
+/// The Key Binding JWT, if required, is verified with the confirmed key,
+/// which is converted to the verifying key `K`.
+pub fn decode<T, V, K, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<T>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+    K: for<'a> TryFrom<&'a Key>,
+    K: for<'a> VerifyingKeys<'a, E>,
+{
+    let sd: SdJwt = token.parse().map_err(|_| Error::Malformed)?;
+
+    let jws = verified(&sd.jwt, keys)?;
+    let payload = jws.payload.as_deref().ok_or(Error::Malformed)?;
+    let mut payload: Map<String, Value> =
+        serde_json::from_slice(payload).map_err(|_| Error::Malformed)?;
+
+    let hash = Hash::of(&payload)?;
+    payload.remove(SD_ALG);
+
+    // The holder's key is taken from the issuer-signed payload.
+    let cnf = payload.get(CNF).cloned();
+
+    let mut value = Value::Object(payload);
+    let mut reconstruction = Reconstruction::new(hash, &sd.disclosures)?;
+    reconstruction.process(&mut value)?;
+    reconstruction.finish()?;
+
+    // The confirmation claim may not be (even partly) disclosed, since the
+    // holder chooses which disclosures to present.
+    if value.get(CNF) != cnf.as_ref() {
+        return Err(Error::Rejected(Reason::Disclosure));
+    }
+
+    if let Some(binding) = &validation.key_binding {
+        let cnf = cnf.as_ref().ok_or(Reason::KeyBinding)?;
+        let cnf = Confirmation::deserialize(cnf).map_err(|_| Reason::KeyBinding)?;
+        let jwk = cnf.jwk.ok_or(Reason::KeyBinding)?;
+        let key = K::try_from(&jwk.key).map_err(|_| Reason::KeyBinding)?;
+
+        let kb = sd.kb.as_deref().ok_or(Reason::KeyBinding)?;
+        let kb: Flattened = kb.parse().map_err(|_| Error::Malformed)?;
+        key.verify_jwt(&kb)?;
+
+        let claims: Claims<KeyBinding> = crate::jws::claims(&kb)?;
+        bound(&validation.jwt, binding, typ(&kb), &claims)?;
+
+        if !bool::from(claims.oth.sd_hash.ct_eq(&sd.sd_hash()?)) {
+            return Err(Error::Rejected(Reason::KeyBinding));
+        }
+    }
+
+    let claims: Claims<T> = serde_json::from_value(value).map_err(|_| Error::Malformed)?;
+    validation.jwt.validate(typ(&jws), &claims)?;
+    Ok(claims)
+}
+
+/// Validates the claims and token type (i.e. `typ`) of a Key Binding JWT.
+fn bound<C: Clock>(
+    jwt: &crate::Validation<C>,
+    binding: &Binding,
+    typ: Option<&str>,
+    claims: &Claims<KeyBinding>,
+) -> Result<(), Reason> {
+    let mut kb = crate::Validation::new(jwt.clock.now());
+    kb.leeway = jwt.leeway;
+    kb.audiences = vec![binding.audience.clone()];
+    kb.required = vec![Claim::Aud, Claim::Iat];
+    kb.max_age = Some(binding.max_age);
+    kb.types = vec![KB_TYP.into()];
+    kb.validate(typ, claims)?;
+
+    if claims.oth.nonce != binding.nonce {
+        return Err(Reason::Nonce);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Security Event Tokens
+//!
+//! A Security Event Token (SET) is a JWT describing one or more security
+//! events, which is sent by a transmitter to the receivers that are
+//! interested in the subject of the events ([RFC 8417]). Each event is
+//! identified by a URI and is described by a JSON object. Profiles of SETs,
+//! such as OpenID Connect Back-Channel Logout (see [`crate::logout`]), define
+//! their own events.
+//!
+//! [RFC 8417]: https://datatracker.ietf.org/doc/html/rfc8417
+
+use alloc::collections::BTreeMap;
+use alloc::{string::String, vec, vec::Vec};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::jws::decode_with;
+use crate::{Claim, Claims, Clock, Error, NumericDate, Reason, VerifyingKeys};
+
+/// The token type of a Security Event Token (RFC 8417 Section 2.3).
+pub const TYP: &str = "secevent+jwt";
+
+/// The events of a Security Event Token, keyed by event URI.
+pub type Events = BTreeMap<String, Map<String, Value>>;
+
+/// The claims of a Security Event Token
+///
+/// The claims defined in [RFC 8417 Section 2.2] (see [`Claims`]).
+///
+/// [RFC 8417 Section 2.2]: https://datatracker.ietf.org/doc/html/rfc8417#section-2.2
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SecurityEventToken<T = Map<String, Value>> {
+    /// The events, each of which is a JSON object keyed by its URI.
+    pub events: Events,
+
+    /// The transaction identifier.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub txn: Option<String>,
+
+    /// The time at which the event occurred.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub toe: Option<NumericDate>,
+
+    /// Other claims that may appear in the token.
+    #[serde(flatten)]
+    pub oth: T,
+}
+
+impl<T: Default> Default for SecurityEventToken<T> {
+    fn default() -> Self {
+        Self {
+            events: Events::new(),
+            txn: None,
+            toe: None,
+            oth: T::default(),
+        }
+    }
+}
+
+/// A policy for validating a Security Event Token
+///
+/// A token must describe at least one event. The receiver may also require
+/// particular events, although a token which contains others is accepted.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The token type, transmitter and receiver of the token.
+    ///
+    /// [`Validation::new()`] requires the `secevent+jwt` type, which keeps a
+    /// SET from being accepted as another kind of JWT (Section 2.3), the
+    /// transmitter as the issuer, the receiver as an audience and the `iss`,
+    /// `iat` and `jti` claims (Section 2.2). A SET need not expire, so `exp`
+    /// is not required.
+    pub jwt: crate::Validation<C>,
+
+    /// The events which must be present (i.e. `events`).
+    ///
+    /// If this is empty, any event is accepted.
+    pub events: Vec<String>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a policy for tokens from the transmitter to the receiver,
+    /// which is identified by the audience.
+    pub fn new(clock: C, issuer: impl Into<String>, audience: impl Into<String>) -> Self {
+        let mut jwt = crate::Validation::new(clock);
+        jwt.types = vec![TYP.into()];
+        jwt.issuers = vec![issuer.into()];
+        jwt.audiences = vec![audience.into()];
+        jwt.required = vec![Claim::Iss, Claim::Iat, Claim::Jti];
+
+        Self {
+            jwt,
+            events: Vec::new(),
+        }
+    }
+
+    /// Validates the claims and token type (i.e. `typ`) of a token.
+    ///
+    /// A token must contain at least one event.
+    pub fn validate<T>(
+        &self,
+        typ: Option<&str>,
+        claims: &Claims<SecurityEventToken<T>>,
+    ) -> Result<(), Reason> {
+        self.jwt.validate(typ, claims)?;
+
+        let events = &claims.oth.events;
+        if events.is_empty() || self.events.iter().any(|x| !events.contains_key(x)) {
+            return Err(Reason::Events);
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies a Security Event Token in the compact serialization and validates
+/// its claims.
+pub fn decode<T, V, E>(
+    token: &str,
+    keys: &V,
+    validation: &Validation<impl Clock>,
+) -> Result<Claims<SecurityEventToken<T>>, Error<E>>
+where
+    T: DeserializeOwned,
+    V: for<'a> VerifyingKeys<'a, E> + ?Sized,
+{
+    decode_with(token, keys, |prot, claims| {
+        validation.validate(prot.oth.typ.as_deref(), claims)
+    })
+}
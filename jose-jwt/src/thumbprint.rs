@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::{format, string::String};
+
+use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+use jose_jwk::Key;
+use sha2::{Digest, Sha256};
+
+/// Computes the JWK Thumbprint of a key ([RFC 7638]), as used by the `jkt`
+/// confirmation method.
+///
+/// The thumbprint is the base64url-encoded SHA-256 hash of the required
+/// members of the key, serialized in lexicographic order without whitespace
+/// (Section 3.2). Returns `None` for key types without a defined set of
+/// required members.
+///
+/// [RFC 7638]: https://datatracker.ietf.org/doc/html/rfc7638
+pub fn thumbprint(key: &Key) -> Option<String> {
+    let b64 = |x: &[u8]| Base64UrlUnpadded::encode_string(x);
+
+    let json = match key {
+        Key::Ec(ec) => {
+            let crv = serde_json::to_string(&ec.crv).ok()?;
+            format!(
+                r#"{{"crv":{},"kty":"EC","x":"{}","y":"{}"}}"#,
+                crv,
+                b64(&ec.x),
+                b64(&ec.y)
+            )
+        }
+
+        Key::Rsa(rsa) => format!(
+            r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+            b64(&rsa.e),
+            b64(&rsa.n)
+        ),
+
+        Key::Oct(oct) => format!(r#"{{"k":"{}","kty":"oct"}}"#, b64(&oct.k)),
+
+        Key::Okp(okp) => {
+            let crv = serde_json::to_string(&okp.crv).ok()?;
+            format!(r#"{{"crv":{},"kty":"OKP","x":"{}"}}"#, crv, b64(&okp.x))
+        }
+
+        _ => return None,
+    };
+
+    Some(b64(&Sha256::digest(json)))
+}
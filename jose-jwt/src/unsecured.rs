@@ -0,0 +1,96 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Unsecured JWTs
+//!
+//! An Unsecured JWT is a JWS using the `none` algorithm (RFC 7519 Section 6),
+//! so its claims are neither integrity protected nor attributable to anyone.
+//! Such tokens are only appropriate when the claims are secured by other
+//! means, such as the channel over which they are received.
+//!
+//! Unsecured JWTs are never accepted by [`decode()`](crate::decode()) or by
+//! any [`VerifyingKey`](jose_jws::crypto::VerifyingKey); they may only be
+//! decoded by this module, which returns the claims wrapped in [`Unsecured`]
+//! so that they cannot be mistaken for verified claims.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+use jose_b64::serde::Json;
+use jose_jwa::Signing;
+use jose_jws::{Flattened, Protected, Signature};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::jws::{claims, typ};
+use crate::{Claims, Clock, Error, Validation};
+
+/// Claims which have not been secured
+///
+/// The claims are only available through explicit accessors, so that code
+/// expecting verified claims cannot receive these by accident.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unsecured<T>(T);
+
+impl<T> Unsecured<T> {
+    /// Returns the unsecured claims.
+    pub fn claims(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns the unsecured claims, consuming the wrapper.
+    pub fn into_claims(self) -> T {
+        self.0
+    }
+}
+
+/// Creates an Unsecured JWT in the compact serialization.
+///
+/// The `alg` header parameter is set to `none` and the signature is empty.
+pub fn encode<T: Serialize>(
+    claims: &Claims<T>,
+    mut header: Protected,
+) -> Result<String, Error<Infallible>> {
+    if !header.b64 {
+        return Err(Error::Malformed);
+    }
+
+    header.oth.alg = Some(Signing::Null);
+    let payload = serde_json::to_vec(claims).map_err(|_| Error::Malformed)?;
+
+    let jws = Flattened {
+        payload: Some(payload.into()),
+        signature: Signature {
+            protected: Some(Json::new(header).map_err(|_| Error::Malformed)?),
+            header: None,
+            signature: Vec::new().into(),
+        },
+    };
+
+    Ok(jws.to_string())
+}
+
+/// Decodes an Unsecured JWT and validates its claims.
+///
+/// The token must use the `none` algorithm and have an empty signature. Any
+/// other token is rejected, even if it carries a valid signature.
+pub fn decode<C: DeserializeOwned>(
+    token: &str,
+    validation: &Validation<impl Clock>,
+) -> Result<Unsecured<Claims<C>>, Error<Infallible>> {
+    let jws: Flattened = token.parse().map_err(|_| Error::Malformed)?;
+
+    let prot = jws.signature.protected.as_ref().ok_or(Error::Malformed)?;
+    if prot.oth.alg != Some(Signing::Null) || !jws.signature.signature.is_empty() {
+        return Err(Error::Malformed);
+    }
+
+    // The only extension understood by the signed path does not apply.
+    if prot.crit.is_some() || !prot.b64 {
+        return Err(Error::Malformed);
+    }
+
+    let claims = claims(&jws)?;
+    validation.validate(typ(&jws), &claims)?;
+    Ok(Unsecured(claims))
+}
@@ -0,0 +1,341 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{Claims, NumericDate};
+
+/// A source of the current time
+///
+/// This is implemented for [`NumericDate`], which is a clock fixed at that
+/// date, and for closures returning a [`NumericDate`]. With the `std` feature,
+/// `SystemClock` reads the system time.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> NumericDate;
+}
+
+impl Clock for NumericDate {
+    fn now(&self) -> NumericDate {
+        *self
+    }
+}
+
+impl<F: Fn() -> NumericDate> Clock for F {
+    fn now(&self) -> NumericDate {
+        self()
+    }
+}
+
+/// A clock reading the system time
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> NumericDate {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let secs = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(x) => i64::try_from(x.as_secs()).unwrap_or(i64::MAX),
+            Err(e) => i64::try_from(e.duration().as_secs()).map_or(i64::MIN, |x| -x),
+        };
+
+        NumericDate::new(secs)
+    }
+}
+
+/// A registered claim
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Claim {
+    /// The issuer (i.e. `iss`)
+    Iss,
+
+    /// The subject (i.e. `sub`)
+    Sub,
+
+    /// The audience (i.e. `aud`)
+    Aud,
+
+    /// The expiration time (i.e. `exp`)
+    Exp,
+
+    /// The not before time (i.e. `nbf`)
+    Nbf,
+
+    /// The issued at time (i.e. `iat`)
+    Iat,
+
+    /// The JWT ID (i.e. `jti`)
+    Jti,
+}
+
+impl Claim {
+    /// Returns whether the claim is present in the claims set.
+    pub fn is_present<T>(self, claims: &Claims<T>) -> bool {
+        match self {
+            Self::Iss => claims.iss.is_some(),
+            Self::Sub => claims.sub.is_some(),
+            Self::Aud => claims.aud.is_some(),
+            Self::Exp => claims.exp.is_some(),
+            Self::Nbf => claims.nbf.is_some(),
+            Self::Iat => claims.iat.is_some(),
+            Self::Jti => claims.jti.is_some(),
+        }
+    }
+}
+
+/// The reason a JWT was rejected by a [`Validation`] policy
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Reason {
+    /// A required claim is absent.
+    Missing(Claim),
+
+    /// The token has expired (i.e. `exp`).
+    Expired,
+
+    /// The token is not valid yet (i.e. `nbf`).
+    NotYetValid,
+
+    /// The token was issued in the future (i.e. `iat`).
+    IssuedInFuture,
+
+    /// The token was issued longer ago than the maximum age.
+    TooOld,
+
+    /// The issuer is not expected (i.e. `iss`).
+    Issuer,
+
+    /// The token is not intended for any expected audience (i.e. `aud`).
+    Audience,
+
+    /// The token type is not expected (i.e. `typ`).
+    Type,
+
+    /// The client is not the authorized party (i.e. `azp`).
+    AuthorizedParty,
+
+    /// The nonce does not match the request (i.e. `nonce`).
+    Nonce,
+
+    /// The authentication context class is not acceptable (i.e. `acr`).
+    Acr,
+
+    /// The end-user authenticated too long ago (i.e. `auth_time`).
+    AuthTime,
+
+    /// The access token hash does not match (i.e. `at_hash` or `ath`).
+    AccessTokenHash,
+
+    /// The authorization code hash does not match (i.e. `c_hash`).
+    CodeHash,
+
+    /// The client is absent or not expected (i.e. `client_id`).
+    ClientId,
+
+    /// A required scope was not granted (i.e. `scope`).
+    Scope,
+
+    /// The token has been used before (i.e. `jti`).
+    Replay,
+
+    /// The HTTP method does not match the request (i.e. `htm`).
+    Method,
+
+    /// The HTTP URI does not match the request (i.e. `htu`).
+    Uri,
+
+    /// The embedded key is absent, unsupported or contains private material
+    /// (i.e. `jwk`).
+    Key,
+
+    /// An expected event is absent (i.e. `events`).
+    Events,
+
+    /// Neither the end-user nor the session is identified (i.e. `sub` or
+    /// `sid`).
+    Session,
+
+    /// The presenter's key does not match the confirmation method (i.e.
+    /// `cnf`).
+    Confirmation,
+
+    /// A disclosure is not referenced by a digest or conflicts with another
+    /// claim.
+    Disclosure,
+
+    /// A digest is malformed or appears more than once, or its algorithm is
+    /// not supported (i.e. `_sd` or `_sd_alg`).
+    Digest,
+
+    /// The Key Binding JWT is absent or does not match the presentation
+    /// (i.e. `sd_hash`).
+    KeyBinding,
+}
+
+/// Compares media types, as used by the `typ` and `cty` header parameters.
+///
+/// Media types are case-insensitive and the `application/` prefix may be
+/// omitted (RFC 7515 Section 4.1.9).
+pub(crate) fn media_type_eq(lhs: &str, rhs: &str) -> bool {
+    fn strip(value: &str) -> &str {
+        const PREFIX: &str = "application/";
+
+        match value.get(..PREFIX.len()) {
+            Some(x) if x.eq_ignore_ascii_case(PREFIX) => &value[PREFIX.len()..],
+            _ => value,
+        }
+    }
+
+    strip(lhs).eq_ignore_ascii_case(strip(rhs))
+}
+
+/// A policy for validating a JWT Claims Set
+///
+/// The times in the claims set are compared with the time from the clock,
+/// allowing for clock skew of up to `leeway` seconds. A temporal claim which
+/// is present is always checked, even if it is not required.
+///
+/// Where a list of expected values (e.g. `issuers`) is empty, any value is
+/// accepted. Otherwise, the corresponding claim is required and must match
+/// one of the values.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Validation<C> {
+    /// The source of the current time.
+    pub clock: C,
+
+    /// The allowed clock skew, in seconds.
+    pub leeway: u64,
+
+    /// The expected issuers (i.e. `iss`).
+    pub issuers: Vec<String>,
+
+    /// The audiences that the recipient identifies with (i.e. `aud`).
+    ///
+    /// A token containing an audience is rejected unless one of its values
+    /// is listed (RFC 7519 Section 4.1.3).
+    pub audiences: Vec<String>,
+
+    /// The claims which must be present.
+    pub required: Vec<Claim>,
+
+    /// The maximum time, in seconds, since the token was issued (i.e. `iat`).
+    pub max_age: Option<u64>,
+
+    /// The expected token types (i.e. the `typ` header parameter).
+    pub types: Vec<String>,
+}
+
+impl<C: Clock> Validation<C> {
+    /// Creates a validation policy using the clock.
+    ///
+    /// By default, only the expiration time (i.e. `exp`) is required.
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            leeway: 0,
+            issuers: Vec::new(),
+            audiences: Vec::new(),
+            required: vec![Claim::Exp],
+            max_age: None,
+            types: Vec::new(),
+        }
+    }
+
+    /// Validates the claims set and the token type (i.e. `typ`).
+    pub fn validate<T>(&self, typ: Option<&str>, claims: &Claims<T>) -> Result<(), Reason> {
+        if !self.types.is_empty() {
+            let typ = typ.ok_or(Reason::Type)?;
+            if !self.types.iter().any(|x| media_type_eq(x, typ)) {
+                return Err(Reason::Type);
+            }
+        }
+
+        if let Some(claim) = self.required.iter().find(|x| !x.is_present(claims)) {
+            return Err(Reason::Missing(*claim));
+        }
+
+        // Widen so that the leeway cannot overflow.
+        let now = i128::from(self.clock.now().secs());
+        let leeway = i128::from(self.leeway);
+        let date = |x: NumericDate| i128::from(x.secs());
+
+        if let Some(exp) = claims.exp {
+            if now - leeway >= date(exp) {
+                return Err(Reason::Expired);
+            }
+        }
+
+        if let Some(nbf) = claims.nbf {
+            if now + leeway < date(nbf) {
+                return Err(Reason::NotYetValid);
+            }
+        }
+
+        if let Some(iat) = claims.iat {
+            if now + leeway < date(iat) {
+                return Err(Reason::IssuedInFuture);
+            }
+        }
+
+        if let Some(max) = self.max_age {
+            let iat = claims.iat.ok_or(Reason::Missing(Claim::Iat))?;
+            if now - leeway > date(iat) + i128::from(max) {
+                return Err(Reason::TooOld);
+            }
+        }
+
+        if !self.issuers.is_empty() {
+            let iss = claims.iss.as_ref().ok_or(Reason::Missing(Claim::Iss))?;
+            if !self.issuers.contains(iss) {
+                return Err(Reason::Issuer);
+            }
+        }
+
+        if !self.audiences.is_empty() && claims.aud.is_none() {
+            return Err(Reason::Missing(Claim::Aud));
+        }
+
+        if let Some(aud) = &claims.aud {
+            if !self.audiences.iter().any(|x| aud.contains(x)) {
+                return Err(Reason::Audience);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the end-user authenticated (i.e. `auth_time`) within the
+    /// maximum time, in seconds, if any.
+    pub(crate) fn authenticated(
+        &self,
+        max_age: Option<u64>,
+        auth_time: Option<NumericDate>,
+    ) -> Result<(), Reason> {
+        if let Some(max) = max_age {
+            let now = i128::from(self.clock.now().secs());
+            let auth = i128::from(auth_time.ok_or(Reason::AuthTime)?.secs());
+            if now - i128::from(self.leeway) > auth + i128::from(max) {
+                return Err(Reason::AuthTime);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the authentication context class (i.e. `acr`) is acceptable.
+///
+/// Any class is acceptable if none are listed.
+pub(crate) fn acr(acceptable: &[String], acr: Option<&String>) -> Result<(), Reason> {
+    if !acceptable.is_empty() && !acr.map_or(false, |x| acceptable.contains(x)) {
+        return Err(Reason::Acr);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,2314 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+/// Fixtures shared by the tests of the JWT profiles
+mod common {
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwk::Jwk;
+    use jose_jwt::{Claims, NumericDate};
+
+    /// The time at which tokens are issued and validated.
+    pub const NOW: NumericDate = NumericDate::new(1_700_000_000);
+
+    /// Returns a protected header with the algorithm and token type.
+    pub fn header(alg: Signing, typ: Option<&str>) -> jose_jws::Protected {
+        jose_jws::Protected {
+            oth: jose_jws::Unprotected {
+                alg: Some(alg),
+                typ: typ.map(Into::into),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Returns a new P-256 signing key and its public key.
+    pub fn p256() -> (Key, Key) {
+        let sk = p256::SecretKey::random(&mut rand::thread_rng());
+        (Key::from(sk.clone()), Key::from(sk.public_key()))
+    }
+
+    /// Returns the JWK of a public key.
+    pub fn jwk(key: &Key) -> Jwk {
+        Jwk {
+            key: key.into(),
+            prm: Default::default(),
+        }
+    }
+
+    /// Returns a claims set from the issuer to the audience, issued just
+    /// before [`NOW`] and valid for five minutes.
+    pub fn claims<T: Default>(iss: &str, aud: &str, oth: T) -> Claims<T> {
+        Claims {
+            iss: Some(iss.into()),
+            aud: Some(aud.into()),
+            exp: NOW.checked_add(300),
+            iat: NOW.checked_sub(10),
+            oth,
+            ..Default::default()
+        }
+    }
+}
+
+mod nested {
+    use jose_b64::stream::Update;
+    use jose_jwa::{Encryption, KeyManagement, Signing};
+    use jose_jwe::crypto::rcrypto::Error as JweError;
+    use jose_jwe::crypto::{EncryptingKey, Encryptor};
+    use jose_jwk::crypto::rcrypto::{Error, Key};
+    use jose_jws::crypto::{Signer, SigningKey};
+    use jose_jwt::{nested, Error as JwtError};
+
+    const CLAIMS: &[u8] = br#"{"iss":"joe","exp":1300819380}"#;
+
+    fn keys() -> (Key, Key, Key) {
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let pk = Key::from(sk.public_key());
+        let kek = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        (Key::from(sk), pk, kek)
+    }
+
+    fn sign(key: &Key, payload: &[u8]) -> jose_jws::Flattened {
+        let mut rng = rand::thread_rng();
+        let prot = jose_jws::Protected {
+            oth: jose_jws::Unprotected {
+                alg: Some(Signing::Es256),
+                typ: Some("JWT".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let signature = key
+            .sign(Some(prot), None)
+            .unwrap()
+            .chain(payload)
+            .unwrap()
+            .finish(&mut rng)
+            .unwrap();
+
+        jose_jws::Flattened {
+            payload: Some(payload.to_vec().into()),
+            signature,
+        }
+    }
+
+    fn protected() -> jose_jwe::Protected {
+        jose_jwe::Protected {
+            oth: jose_jwe::Unprotected {
+                alg: Some(KeyManagement::A128Kw),
+                enc: Some(Encryption::A128Gcm),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn encrypt(kek: &Key, jws: &jose_jws::Flattened) -> String {
+        let mut rng = rand::thread_rng();
+        nested::encrypt(kek, &mut rng, protected(), jws)
+            .unwrap()
+            .to_string()
+    }
+
+    fn decrypt(kek: &Key, pk: &Key, token: &str) -> Result<jose_jws::Flattened, JwtError<Error>> {
+        let mut rng = rand::thread_rng();
+        nested::decrypt(kek, pk, &mut rng, token)
+    }
+
+    #[test]
+    fn roundtrip() {
+        let (sk, pk, kek) = keys();
+        let token = encrypt(&kek, &sign(&sk, CLAIMS));
+
+        let jwe: jose_jwe::Compact = token.parse().unwrap();
+        assert_eq!(jwe.protected.oth.cty.as_deref(), Some("JWT"));
+
+        let jws = decrypt(&kek, &pk, &token).unwrap();
+        assert_eq!(jws.payload.as_deref().map(|x| &x[..]), Some(CLAIMS));
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let (sk, pk, kek) = keys();
+
+        let mut jws = sign(&sk, CLAIMS);
+        jws.payload = Some(br#"{"iss":"joe","exp":4102444800}"#.to_vec().into());
+        let token = encrypt(&kek, &jws);
+        assert_eq!(
+            decrypt(&kek, &pk, &token).err(),
+            Some(JwtError::Crypto(Error::Invalid))
+        );
+
+        // A signature by another key is also rejected.
+        let (other, ..) = keys();
+        let token = encrypt(&kek, &sign(&other, CLAIMS));
+        assert_eq!(
+            decrypt(&kek, &pk, &token).err(),
+            Some(JwtError::Crypto(Error::Invalid))
+        );
+    }
+
+    #[test]
+    fn invalid_encryption() {
+        let (sk, pk, kek) = keys();
+        let token = encrypt(&kek, &sign(&sk, CLAIMS));
+
+        let other = Key::Oct(vec![8u8; 16].into_boxed_slice().into());
+        let err = decrypt(&other, &pk, &token).err();
+        assert_eq!(err, Some(JwtError::Crypto(JweError::Invalid)));
+
+        // Only the compact serialization of a JWE is accepted.
+        let jws = sign(&sk, CLAIMS).to_string();
+        assert_eq!(decrypt(&kek, &pk, &jws).err(), Some(JwtError::Malformed));
+    }
+
+    #[test]
+    fn content_type() {
+        let (sk, pk, kek) = keys();
+        let mut rng = rand::thread_rng();
+
+        // The JWE must declare that it contains a JWT.
+        let jws = sign(&sk, CLAIMS).to_string();
+        let jwe = kek
+            .encrypt(&mut rng, Some(protected()), None)
+            .unwrap()
+            .chain(jws)
+            .unwrap()
+            .finish()
+            .unwrap();
+        let token = jose_jwe::Compact::try_from(jwe).unwrap().to_string();
+        assert_eq!(
+            decrypt(&kek, &pk, &token).err(),
+            Some(JwtError::ContentType)
+        );
+    }
+
+    #[test]
+    fn not_compact() {
+        let (sk, _, kek) = keys();
+        let mut rng = rand::thread_rng();
+
+        let mut jws = sign(&sk, CLAIMS);
+        jws.signature.header = Some(Default::default());
+        let err = nested::encrypt(&kek, &mut rng, protected(), &jws).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+    }
+}
+
+mod claims {
+    use jose_jwt::{Audience, Claims, NumericDate};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[test]
+    fn rfc7519_3_1() {
+        let value = json!({
+            "iss": "joe",
+            "exp": 1300819380,
+            "http://example.com/is_root": true
+        });
+
+        let claims: Claims = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(claims.iss.as_deref(), Some("joe"));
+        assert_eq!(claims.exp, Some(NumericDate::new(1300819380)));
+        assert_eq!(claims.oth["http://example.com/is_root"], json!(true));
+        assert_eq!(serde_json::to_value(&claims).unwrap(), value);
+    }
+
+    #[test]
+    fn application() {
+        #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+        struct App {
+            name: String,
+            admin: bool,
+        }
+
+        let claims = Claims {
+            sub: Some("1234567890".into()),
+            iat: Some(NumericDate::new(1516239022)),
+            oth: App {
+                name: "John Doe".into(),
+                admin: true,
+            },
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&claims).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "sub": "1234567890",
+                "iat": 1516239022,
+                "name": "John Doe",
+                "admin": true
+            })
+        );
+
+        let decoded: Claims<App> = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded, claims);
+    }
+
+    #[test]
+    fn audience() {
+        let claims: Claims = serde_json::from_value(json!({ "aud": "a" })).unwrap();
+        assert_eq!(claims.aud, Some(Audience::One("a".into())));
+        assert!(claims.aud.unwrap().contains("a"));
+
+        let claims: Claims = serde_json::from_value(json!({ "aud": ["a", "b"] })).unwrap();
+        let aud = claims.aud.unwrap();
+        assert_eq!(aud, Audience::Many(vec!["a".into(), "b".into()]));
+        assert!(aud.contains("b"));
+        assert!(!aud.contains("c"));
+        assert_eq!(serde_json::to_value(&aud).unwrap(), json!(["a", "b"]));
+
+        assert!(serde_json::from_value::<Claims>(json!({ "aud": 1 })).is_err());
+    }
+
+    #[test]
+    fn numeric_date() {
+        let date = |x| serde_json::from_value::<NumericDate>(x).map(NumericDate::secs);
+
+        assert_eq!(date(json!(1300819380)).unwrap(), 1300819380);
+        assert_eq!(date(json!(-1)).unwrap(), -1);
+
+        // Fractional values are rounded down.
+        assert_eq!(date(json!(1300819380.999)).unwrap(), 1300819380);
+        assert_eq!(date(json!(-1.5)).unwrap(), -2);
+        assert_eq!(date(json!(1e3)).unwrap(), 1000);
+
+        // Values that cannot be represented are rejected.
+        assert!(date(json!(u64::MAX)).is_err());
+        assert!(date(json!(1e19)).is_err());
+        assert!(date(json!(-1e19)).is_err());
+        assert!(date(json!("1300819380")).is_err());
+        assert!(date(json!(null)).is_err());
+
+        let now = NumericDate::new(i64::MAX - 1);
+        assert_eq!(now.checked_add(1), Some(NumericDate::new(i64::MAX)));
+        assert_eq!(now.checked_add(2), None);
+        assert_eq!(now.saturating_add(2), NumericDate::new(i64::MAX));
+        assert_eq!(now.saturating_add(u64::MAX), NumericDate::new(i64::MAX));
+        assert_eq!(
+            NumericDate::EPOCH.checked_sub(1),
+            Some(NumericDate::new(-1))
+        );
+        assert_eq!(NumericDate::new(i64::MIN).checked_sub(1), None);
+
+        let value = serde_json::to_value(NumericDate::new(1300819380)).unwrap();
+        assert_eq!(value, json!(1300819380));
+    }
+}
+
+mod validation {
+    use jose_jwt::{Audience, Claim, Claims, NumericDate, Reason, Validation};
+
+    const NOW: NumericDate = NumericDate::new(1_700_000_000);
+
+    fn claims() -> Claims {
+        Claims {
+            iss: Some("https://issuer.example".into()),
+            aud: Some("api".into()),
+            exp: NOW.checked_add(300),
+            iat: NOW.checked_sub(10),
+            ..Default::default()
+        }
+    }
+
+    fn validation() -> Validation<NumericDate> {
+        let mut validation = Validation::new(NOW);
+        validation.audiences = vec!["api".into()];
+        validation
+    }
+
+    #[test]
+    fn default() {
+        let validation = validation();
+        assert_eq!(validation.validate(None, &claims()), Ok(()));
+
+        // The expiration time is required by default.
+        let mut claims = claims();
+        claims.exp = None;
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Exp)));
+    }
+
+    #[test]
+    fn times() {
+        let mut validation = Validation::new(NOW);
+        validation.required.clear();
+
+        let at = |offset: i64| NumericDate::new(NOW.secs() + offset);
+        let check = |validation: &Validation<_>, claims: Claims| validation.validate(None, &claims);
+
+        let exp = |x| Claims {
+            exp: Some(at(x)),
+            ..Default::default()
+        };
+        let nbf = |x| Claims {
+            nbf: Some(at(x)),
+            ..Default::default()
+        };
+        let iat = |x| Claims {
+            iat: Some(at(x)),
+            ..Default::default()
+        };
+
+        assert_eq!(check(&validation, exp(1)), Ok(()));
+        assert_eq!(check(&validation, exp(0)), Err(Reason::Expired));
+        assert_eq!(check(&validation, nbf(0)), Ok(()));
+        assert_eq!(check(&validation, nbf(1)), Err(Reason::NotYetValid));
+        assert_eq!(check(&validation, iat(0)), Ok(()));
+        assert_eq!(check(&validation, iat(1)), Err(Reason::IssuedInFuture));
+
+        validation.leeway = 30;
+        assert_eq!(check(&validation, exp(-29)), Ok(()));
+        assert_eq!(check(&validation, exp(-30)), Err(Reason::Expired));
+        assert_eq!(check(&validation, nbf(30)), Ok(()));
+        assert_eq!(check(&validation, nbf(31)), Err(Reason::NotYetValid));
+        assert_eq!(check(&validation, iat(30)), Ok(()));
+        assert_eq!(check(&validation, iat(31)), Err(Reason::IssuedInFuture));
+
+        // The leeway must not overflow at the limits of the date range.
+        let max = Claims {
+            nbf: Some(NumericDate::new(i64::MAX)),
+            ..Default::default()
+        };
+        assert_eq!(check(&validation, max), Err(Reason::NotYetValid));
+        let min = Claims {
+            exp: Some(NumericDate::new(i64::MIN)),
+            ..Default::default()
+        };
+        assert_eq!(check(&validation, min), Err(Reason::Expired));
+    }
+
+    #[test]
+    fn clock() {
+        let validation = Validation::new(|| NOW.checked_add(301).unwrap());
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::Expired));
+    }
+
+    #[test]
+    fn max_age() {
+        let mut validation = validation();
+        validation.max_age = Some(10);
+        assert_eq!(validation.validate(None, &claims()), Ok(()));
+
+        validation.max_age = Some(9);
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::TooOld));
+
+        validation.leeway = 1;
+        assert_eq!(validation.validate(None, &claims()), Ok(()));
+
+        let mut claims = claims();
+        claims.iat = None;
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Iat)));
+    }
+
+    #[test]
+    fn issuer() {
+        let mut validation = validation();
+        validation.issuers = vec!["https://other.example".into()];
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::Issuer));
+
+        validation.issuers.push("https://issuer.example".into());
+        assert_eq!(validation.validate(None, &claims()), Ok(()));
+
+        let mut claims = claims();
+        claims.iss = None;
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Iss)));
+    }
+
+    #[test]
+    fn audience() {
+        let mut validation = Validation::new(NOW);
+
+        // A token with an audience is rejected by an unidentified recipient.
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::Audience));
+
+        validation.audiences = vec!["other".into()];
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::Audience));
+
+        let mut claims = claims();
+        claims.aud = Some(Audience::Many(vec!["api".into(), "other".into()]));
+        assert_eq!(validation.validate(None, &claims), Ok(()));
+
+        claims.aud = None;
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Aud)));
+    }
+
+    #[test]
+    fn required() {
+        let mut validation = validation();
+        validation.required = vec![Claim::Exp, Claim::Sub, Claim::Jti];
+
+        let mut claims = claims();
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Sub)));
+
+        claims.sub = Some("alice".into());
+        let err = validation.validate(None, &claims);
+        assert_eq!(err, Err(Reason::Missing(Claim::Jti)));
+
+        claims.jti = Some("1".into());
+        assert_eq!(validation.validate(None, &claims), Ok(()));
+    }
+
+    #[test]
+    fn types() {
+        let mut validation = validation();
+        assert_eq!(validation.validate(Some("anything"), &claims()), Ok(()));
+
+        validation.types = vec!["at+jwt".into()];
+        assert_eq!(validation.validate(Some("at+jwt"), &claims()), Ok(()));
+        assert_eq!(validation.validate(Some("AT+JWT"), &claims()), Ok(()));
+        let typ = Some("application/at+jwt");
+        assert_eq!(validation.validate(typ, &claims()), Ok(()));
+
+        assert_eq!(
+            validation.validate(Some("JWT"), &claims()),
+            Err(Reason::Type)
+        );
+        assert_eq!(validation.validate(None, &claims()), Err(Reason::Type));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_clock() {
+        use jose_jwt::{Clock, SystemClock};
+
+        // 2023-11-14T22:13:20Z
+        assert!(SystemClock.now() > NOW);
+    }
+}
+
+mod jws {
+    use jose_b64::stream::Update;
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::{Error, Key};
+    use jose_jws::crypto::{KeySet, Signer, SigningKey};
+    use jose_jwt::{decode, encode, Claims, Error as JwtError, NumericDate, Reason, Validation};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    const NOW: NumericDate = NumericDate::new(1_700_000_000);
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct App {
+        scope: String,
+    }
+
+    fn header(alg: Signing) -> jose_jws::Protected {
+        jose_jws::Protected {
+            oth: jose_jws::Unprotected {
+                alg: Some(alg),
+                typ: Some("JWT".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn claims() -> Claims<App> {
+        Claims {
+            sub: Some("alice".into()),
+            exp: NOW.checked_add(60),
+            oth: App {
+                scope: "read".into(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn hs256() -> Key {
+        let jwk: jose_jwk::Key = serde_json::from_value(json!({
+            "kty": "oct",
+            "k": "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+        }))
+        .unwrap();
+
+        Key::try_from(&jwk).unwrap()
+    }
+
+    #[test]
+    fn rfc7519_3_1() {
+        const JWT: &str = concat!(
+            "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.",
+            "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ.",
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+        );
+
+        let validation = Validation::new(NumericDate::new(1300819379));
+        let claims: Claims = decode(JWT, &hs256(), &validation).unwrap();
+        assert_eq!(claims.iss.as_deref(), Some("joe"));
+        assert_eq!(claims.oth["http://example.com/is_root"], json!(true));
+
+        let validation = Validation::new(NumericDate::new(1300819380));
+        let err = decode::<serde_json::Value, _, _>(JWT, &hs256(), &validation).err();
+        assert_eq!(err, Some(JwtError::Rejected(Reason::Expired)));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+        let sk = p256::SecretKey::random(&mut rng);
+        let pk = Key::from(sk.public_key());
+        let sk = Key::from(sk);
+
+        let token = encode(&claims(), &sk, &mut rng, header(Signing::Es256)).unwrap();
+        assert_eq!(token.split('.').count(), 3);
+
+        let validation = Validation::new(NOW);
+        let decoded: Claims<App> = decode(&token, &pk, &validation).unwrap();
+        assert_eq!(decoded, claims());
+
+        // A set of keys may contain keys for other algorithms.
+        let keys = [hs256(), pk];
+        let decoded: Claims<App> = decode(&token, &KeySet(&keys), &validation).unwrap();
+        assert_eq!(decoded, claims());
+
+        let err = decode::<App, _, _>(&token, &hs256(), &validation).err();
+        assert_eq!(err, Some(JwtError::Crypto(Error::AlgMismatch)));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut rng = rand::thread_rng();
+        let key = hs256();
+        let validation = Validation::new(NOW);
+
+        let token = encode(&claims(), &key, &mut rng, header(Signing::Hs256)).unwrap();
+        let (head, rest) = token.split_once('.').unwrap();
+        let (_, sig) = rest.split_once('.').unwrap();
+
+        // The claims are not parsed until the signature has been verified.
+        let forged = format!("{head}.bm90IGpzb24.{sig}");
+        let err = decode::<App, _, _>(&forged, &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Crypto(Error::Invalid)));
+
+        let err = decode::<App, _, _>("not a token", &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+
+        // Signed, but not a claims set.
+        let signer = key.sign(Some(header(Signing::Hs256)), None).unwrap();
+        let payload = jose_jws::Flattened {
+            payload: Some(b"[]".to_vec().into()),
+            signature: signer.chain(b"[]").unwrap().finish(&mut rng).unwrap(),
+        };
+        let err = decode::<App, _, _>(&payload.to_string(), &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+    }
+
+    #[test]
+    fn validation() {
+        let mut rng = rand::thread_rng();
+        let key = hs256();
+        let token = encode(&claims(), &key, &mut rng, header(Signing::Hs256)).unwrap();
+
+        let mut validation = Validation::new(NOW);
+        validation.types = vec!["at+jwt".into()];
+        let err = decode::<App, _, _>(&token, &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Rejected(Reason::Type)));
+
+        let validation = Validation::new(NOW.checked_add(60).unwrap());
+        let err = decode::<App, _, _>(&token, &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Rejected(Reason::Expired)));
+    }
+
+    #[test]
+    fn unencoded() {
+        let mut rng = rand::thread_rng();
+        let mut header = header(Signing::Hs256);
+        header.b64 = false;
+        header.crit = Some(vec!["b64".into()]);
+
+        let err = encode(&claims(), &hs256(), &mut rng, header).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+    }
+}
+
+mod unsecured {
+    use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+    use jose_b64::stream::Update;
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::{Error, Key};
+    use jose_jws::crypto::{Signer, SigningKey};
+    use jose_jwt::{decode, encode, unsecured, Claims, Error as JwtError, NumericDate, Validation};
+    use rsa::traits::PublicKeyParts;
+
+    const NOW: NumericDate = NumericDate::new(1_700_000_000);
+
+    fn claims() -> Claims {
+        Claims {
+            sub: Some("alice".into()),
+            exp: NOW.checked_add(60),
+            ..Default::default()
+        }
+    }
+
+    fn header(alg: Signing) -> jose_jws::Protected {
+        jose_jws::Protected {
+            oth: jose_jws::Unprotected {
+                alg: Some(alg),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn b64(value: &str) -> String {
+        Base64UrlUnpadded::encode_string(value.as_bytes())
+    }
+
+    #[test]
+    fn rfc7519_6_1() {
+        const JWT: &str = concat!(
+            "eyJhbGciOiJub25lIn0.",
+            "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ.",
+        );
+
+        let validation = Validation::new(NumericDate::new(1300819379));
+        let claims = unsecured::decode::<serde_json::Value>(JWT, &validation).unwrap();
+        assert_eq!(claims.claims().iss.as_deref(), Some("joe"));
+
+        // Unsecured tokens are still validated.
+        let validation = Validation::new(NumericDate::new(1300819380));
+        assert!(unsecured::decode::<serde_json::Value>(JWT, &validation).is_err());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let token = unsecured::encode(&claims(), Default::default()).unwrap();
+        assert!(token.starts_with("eyJhbGciOiJub25lIn0."));
+        assert!(token.ends_with('.'));
+
+        let validation = Validation::new(NOW);
+        let decoded = unsecured::decode(&token, &validation).unwrap();
+        assert_eq!(decoded.into_claims(), claims());
+    }
+
+    fn oct(byte: u8) -> Key {
+        Key::Oct(vec![byte; 32].into_boxed_slice().into())
+    }
+
+    #[test]
+    fn signed() {
+        let mut rng = rand::thread_rng();
+        let key = oct(1);
+        let token = encode(&claims(), &key, &mut rng, header(Signing::Hs256)).unwrap();
+
+        // Signed tokens are not accepted as unsecured.
+        let validation = Validation::new(NOW);
+        let err = unsecured::decode::<serde_json::Value>(&token, &validation).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+
+        // Nor may a signature accompany the `none` algorithm.
+        let (_, rest) = token.split_once('.').unwrap();
+        let forged = format!("{}.{rest}", b64(r#"{"alg":"none"}"#));
+        let err = unsecured::decode::<serde_json::Value>(&forged, &validation).err();
+        assert_eq!(err, Some(JwtError::Malformed));
+    }
+
+    #[test]
+    fn none_bypass() {
+        let mut rng = rand::thread_rng();
+        let key = oct(1);
+        let validation = Validation::new(NOW);
+
+        let token = encode(&claims(), &key, &mut rng, header(Signing::Hs256)).unwrap();
+        let (_, rest) = token.split_once('.').unwrap();
+        let (payload, sig) = rest.split_once('.').unwrap();
+
+        // Replace the header, with and without the original signature.
+        let keys = [oct(2), oct(1)];
+        let none = b64(r#"{"alg":"none","typ":"JWT"}"#);
+        for forged in [
+            format!("{none}.{payload}."),
+            format!("{none}.{payload}.{sig}"),
+        ] {
+            let err = decode::<serde_json::Value, _, _>(&forged, &key, &validation).err();
+            assert_eq!(err, Some(JwtError::Unsecured));
+
+            let err = decode(&forged, &keys[..], &validation)
+                .map(|_: Claims| ())
+                .err();
+            assert_eq!(err, Some(JwtError::Unsecured));
+        }
+
+        // Variations on the algorithm name are not recognized at all.
+        for alg in ["None", "NONE", "nOnE", ""] {
+            let head = b64(&format!(r#"{{"alg":"{alg}"}}"#));
+            let forged = format!("{head}.{payload}.");
+            let err = decode::<serde_json::Value, _, _>(&forged, &key, &validation).err();
+            assert_eq!(err, Some(JwtError::Malformed));
+        }
+
+        // Unsecured tokens cannot be created through the signed path.
+        let err = encode(&claims(), &key, &mut rng, header(Signing::Null)).err();
+        assert_eq!(err, Some(JwtError::Unsecured));
+
+        // Keys never support the `none` algorithm.
+        let err = key.sign(Some(header(Signing::Null)), None).err();
+        assert_eq!(err, Some(Error::AlgMismatch));
+    }
+
+    /// Signs a token with HMAC, using public key material as the secret.
+    fn hmac(secret: &[u8], alg: Signing) -> String {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(secret.to_vec().into_boxed_slice().into());
+        let payload = serde_json::to_vec(&claims()).unwrap();
+
+        let signer = key.sign(Some(header(alg)), None).unwrap();
+        let signature = signer.chain(&payload).unwrap().finish(&mut rng).unwrap();
+
+        let jws = jose_jws::Flattened {
+            payload: Some(payload.into()),
+            signature,
+        };
+
+        jws.to_string()
+    }
+
+    #[test]
+    fn rsa_confusion() {
+        let mut rng = rand::thread_rng();
+        let sk = rsa::RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let pk = sk.to_public_key();
+        let validation = Validation::new(NOW);
+
+        // The attacker knows the public key and uses it as an HMAC secret.
+        let secret = pk.n().to_bytes_be();
+        let key = Key::from(pk);
+
+        for alg in [Signing::Hs256, Signing::Hs384, Signing::Hs512] {
+            let token = hmac(&secret, alg);
+            let err = decode::<serde_json::Value, _, _>(&token, &key, &validation).err();
+            assert_eq!(err, Some(JwtError::Crypto(Error::AlgMismatch)));
+        }
+
+        // The genuine token is accepted.
+        let token = encode(&claims(), &Key::from(sk), &mut rng, header(Signing::Rs256)).unwrap();
+        assert!(decode::<serde_json::Value, _, _>(&token, &key, &validation).is_ok());
+    }
+
+    #[test]
+    fn ecdsa_confusion() {
+        let mut rng = rand::thread_rng();
+        let pk = p256::SecretKey::random(&mut rng).public_key();
+        let validation = Validation::new(NOW);
+
+        let secret = p256::EncodedPoint::from(pk);
+        let token = hmac(secret.as_bytes(), Signing::Hs256);
+
+        let key = Key::from(pk);
+        let err = decode::<serde_json::Value, _, _>(&token, &key, &validation).err();
+        assert_eq!(err, Some(JwtError::Crypto(Error::AlgMismatch)));
+    }
+}
+
+mod oidc {
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwt::oidc::{decode, hash, IdToken, Validation};
+    use jose_jwt::{encode, Audience, Claim, Claims, Error, NumericDate, Reason};
+
+    use super::common::{header, NOW};
+
+    const ISSUER: &str = "https://server.example.com";
+    const ACCESS_TOKEN: &str = "jHkWEdUXMU1BwAsC4vtUsZwnNvTIxEl0z9K3vx5KF0Y";
+    const CODE: &str = "Qcb0Orv1zh30vL1MPRsbm-diHiMwcLyZvn1arpZv-Jxf_11jnpEX3Tgfvk";
+
+    fn claims() -> Claims<IdToken> {
+        let id = IdToken {
+            nonce: Some("n-0S6_WzA2Mj".into()),
+            auth_time: NOW.checked_sub(60),
+            acr: Some("urn:mace:incommon:iap:silver".into()),
+            at_hash: Some(hash(Signing::Hs256, ACCESS_TOKEN).unwrap().into()),
+            c_hash: Some(hash(Signing::Hs256, CODE).unwrap().into()),
+            ..Default::default()
+        };
+
+        Claims {
+            sub: Some("248289761001".into()),
+            ..super::common::claims(ISSUER, "s6BhdRkqt3", id)
+        }
+    }
+
+    fn validation() -> Validation<NumericDate> {
+        let mut validation = Validation::new(NOW, ISSUER, "s6BhdRkqt3");
+        validation.nonce = Some("n-0S6_WzA2Mj".into());
+        validation
+    }
+
+    fn check(validation: &Validation<NumericDate>, claims: &Claims<IdToken>) -> Result<(), Reason> {
+        validation.validate(&header(Signing::Hs256, None), claims)
+    }
+
+    fn b64(value: &str) -> Vec<u8> {
+        use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+        Base64UrlUnpadded::decode_vec(value).unwrap()
+    }
+
+    #[test]
+    fn hashes() {
+        // OpenID Connect Core 1.0 Appendix A.3 and A.4
+        let at_hash = hash(Signing::Rs256, ACCESS_TOKEN).unwrap();
+        assert_eq!(at_hash, b64("77QmUPtjPfzWtF2AnpK9RQ"));
+        let c_hash = hash(Signing::Rs256, CODE).unwrap();
+        assert_eq!(c_hash, b64("LDktKdoQak3Pk0cnXxCltA"));
+
+        assert_eq!(hash(Signing::Es384, CODE).unwrap().len(), 24);
+        assert_eq!(hash(Signing::Ps512, CODE).unwrap().len(), 32);
+        assert_eq!(hash(Signing::Null, CODE), None);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(vec![1u8; 32].into_boxed_slice().into());
+
+        let token = encode(&claims(), &key, &mut rng, header(Signing::Hs256, None)).unwrap();
+
+        let mut validation = validation();
+        validation.access_token = Some(ACCESS_TOKEN.into());
+        validation.code = Some(CODE.into());
+        let decoded: Claims<IdToken> = decode(&token, &key, &validation).unwrap();
+        assert_eq!(decoded, claims());
+
+        validation.nonce = Some("other".into());
+        let res: Result<Claims<IdToken>, _> = decode(&token, &key, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Nonce)));
+    }
+
+    #[test]
+    fn required() {
+        let validation = validation();
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        let mut claims = claims();
+        claims.iat = None;
+        assert_eq!(
+            check(&validation, &claims),
+            Err(Reason::Missing(Claim::Iat))
+        );
+
+        claims.sub = None;
+        assert_eq!(
+            check(&validation, &claims),
+            Err(Reason::Missing(Claim::Sub))
+        );
+    }
+
+    #[test]
+    fn issuer() {
+        let mut claims = claims();
+        claims.iss = Some("https://server.example.com/".into());
+        assert_eq!(check(&validation(), &claims), Err(Reason::Issuer));
+    }
+
+    #[test]
+    fn audience() {
+        let mut validation = validation();
+        let mut claims = claims();
+
+        // Every audience must be trusted.
+        claims.aud = Some(Audience::Many(vec!["s6BhdRkqt3".into(), "api".into()]));
+        claims.oth.azp = Some("s6BhdRkqt3".into());
+        assert_eq!(check(&validation, &claims), Err(Reason::Audience));
+
+        validation.jwt.audiences.push("api".into());
+        assert_eq!(check(&validation, &claims), Ok(()));
+
+        // With multiple audiences, the authorized party is required.
+        claims.oth.azp = None;
+        assert_eq!(check(&validation, &claims), Err(Reason::AuthorizedParty));
+
+        claims.oth.azp = Some("api".into());
+        assert_eq!(check(&validation, &claims), Err(Reason::AuthorizedParty));
+
+        // The client must be an audience, even if others are trusted.
+        claims.aud = Some("api".into());
+        assert_eq!(check(&validation, &claims), Err(Reason::Audience));
+
+        claims.aud = Some("other".into());
+        claims.oth.azp = None;
+        assert_eq!(check(&validation, &claims), Err(Reason::Audience));
+    }
+
+    #[test]
+    fn client_audience() {
+        let mut validation = validation();
+        validation.jwt.audiences = vec!["s6BhdRkqt3".into(), "other".into()];
+
+        let mut claims = claims();
+        claims.aud = Some(Audience::Many(vec!["other".into()]));
+        assert_eq!(check(&validation, &claims), Err(Reason::Audience));
+    }
+
+    #[test]
+    fn nonce() {
+        let mut claims = claims();
+        claims.oth.nonce = None;
+        assert_eq!(check(&validation(), &claims), Err(Reason::Nonce));
+
+        // The nonce is only checked if it was sent.
+        let mut validation = validation();
+        validation.nonce = None;
+        assert_eq!(check(&validation, &claims), Ok(()));
+    }
+
+    #[test]
+    fn auth_time() {
+        let mut validation = validation();
+        validation.max_age = Some(60);
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        validation.max_age = Some(59);
+        assert_eq!(check(&validation, &claims()), Err(Reason::AuthTime));
+
+        let mut claims = claims();
+        claims.oth.auth_time = None;
+        validation.max_age = Some(3600);
+        assert_eq!(check(&validation, &claims), Err(Reason::AuthTime));
+    }
+
+    #[test]
+    fn acr() {
+        let mut validation = validation();
+        validation.acr = vec!["urn:mace:incommon:iap:gold".into()];
+        assert_eq!(check(&validation, &claims()), Err(Reason::Acr));
+
+        validation.acr.push("urn:mace:incommon:iap:silver".into());
+        assert_eq!(check(&validation, &claims()), Ok(()));
+    }
+
+    #[test]
+    fn token_hashes() {
+        let mut validation = validation();
+        validation.access_token = Some(ACCESS_TOKEN.into());
+        validation.code = Some(CODE.into());
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        // The hash is determined by the signature algorithm.
+        let err = validation.validate(&header(Signing::Hs384, None), &claims());
+        assert_eq!(err, Err(Reason::AccessTokenHash));
+
+        validation.access_token = Some("other".into());
+        assert_eq!(check(&validation, &claims()), Err(Reason::AccessTokenHash));
+
+        validation.access_token = None;
+        validation.code = Some("other".into());
+        assert_eq!(check(&validation, &claims()), Err(Reason::CodeHash));
+
+        let mut claims = claims();
+        claims.oth.c_hash = None;
+        validation.code = Some(CODE.into());
+        assert_eq!(check(&validation, &claims), Err(Reason::CodeHash));
+    }
+}
+
+mod access {
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwt::access::{decode, AccessToken, Validation};
+    use jose_jwt::{encode, Claim, Claims, Error, NumericDate, Reason, Scope};
+    use serde_json::json;
+
+    use super::common::{header, NOW};
+
+    const ISSUER: &str = "https://authorization-server.example.com/";
+    const RESOURCE: &str = "https://rs.example.com/";
+
+    fn claims() -> Claims<AccessToken> {
+        let at = AccessToken {
+            client_id: Some("s6BhdRkqt3".into()),
+            scope: Some("openid profile reademail".into()),
+            auth_time: NOW.checked_sub(60),
+            roles: Some(vec!["admin".into()]),
+            ..Default::default()
+        };
+
+        Claims {
+            sub: Some("5ba552d67".into()),
+            jti: Some("dbe39bf3a3ba4238a513f51d6e1691c4".into()),
+            ..super::common::claims(ISSUER, RESOURCE, at)
+        }
+    }
+
+    fn check(
+        validation: &Validation<NumericDate>,
+        claims: &Claims<AccessToken>,
+    ) -> Result<(), Reason> {
+        validation.validate(Some("at+jwt"), claims)
+    }
+
+    #[test]
+    fn rfc9068_2_2() {
+        let claims: Claims<AccessToken> = serde_json::from_value(json!({
+            "iss": "https://authorization-server.example.com/",
+            "sub": "5ba552d67",
+            "aud": "https://rs.example.com/",
+            "exp": 1639528912,
+            "iat": 1618354090,
+            "jti": "dbe39bf3a3ba4238a513f51d6e1691c4",
+            "client_id": "s6BhdRkqt3",
+            "scope": "openid profile reademail"
+        }))
+        .unwrap();
+
+        let scope = claims.oth.scope.as_ref().unwrap();
+        assert_eq!(scope.as_slice(), ["openid", "profile", "reademail"]);
+        assert!(scope.contains("profile"));
+        assert!(!scope.contains("read"));
+
+        let value = serde_json::to_value(&claims).unwrap();
+        assert_eq!(value["scope"], json!("openid profile reademail"));
+    }
+
+    #[test]
+    fn scope() {
+        let scope = Scope::from(" a  b ");
+        assert_eq!(scope.as_slice(), ["a", "b"]);
+        assert_eq!(scope.to_string(), "a b");
+        assert_eq!(Scope::from("").as_slice(), [] as [String; 0]);
+
+        assert!(serde_json::from_value::<Scope>(json!(["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(vec![1u8; 32].into_boxed_slice().into());
+        let validation = Validation::new(NOW, ISSUER, RESOURCE);
+
+        for typ in ["at+jwt", "application/at+jwt", "AT+JWT"] {
+            let head = header(Signing::Hs256, Some(typ));
+            let token = encode(&claims(), &key, &mut rng, head).unwrap();
+            let decoded: Claims<AccessToken> = decode(&token, &key, &validation).unwrap();
+            assert_eq!(decoded, claims());
+        }
+
+        // Other JWTs, such as ID Tokens, are not access tokens.
+        for typ in [None, Some("JWT"), Some("application/jwt")] {
+            let head = header(Signing::Hs256, typ);
+            let token = encode(&claims(), &key, &mut rng, head).unwrap();
+            let res: Result<Claims<AccessToken>, _> = decode(&token, &key, &validation);
+            assert_eq!(res.err(), Some(Error::Rejected(Reason::Type)));
+        }
+    }
+
+    #[test]
+    fn required() {
+        let validation = Validation::new(NOW, ISSUER, RESOURCE);
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        let mut claims = claims();
+        claims.jti = None;
+        assert_eq!(
+            check(&validation, &claims),
+            Err(Reason::Missing(Claim::Jti))
+        );
+
+        let mut claims = self::claims();
+        claims.oth.client_id = None;
+        assert_eq!(check(&validation, &claims), Err(Reason::ClientId));
+
+        let mut claims = self::claims();
+        claims.aud = Some("https://other.example.com/".into());
+        assert_eq!(check(&validation, &claims), Err(Reason::Audience));
+    }
+
+    #[test]
+    fn policy() {
+        let mut validation = Validation::new(NOW, ISSUER, RESOURCE);
+
+        validation.clients = vec!["other".into()];
+        assert_eq!(check(&validation, &claims()), Err(Reason::ClientId));
+        validation.clients.push("s6BhdRkqt3".into());
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        validation.scopes = vec!["profile".into(), "writeemail".into()];
+        assert_eq!(check(&validation, &claims()), Err(Reason::Scope));
+        validation.scopes.pop();
+        assert_eq!(check(&validation, &claims()), Ok(()));
+
+        validation.acr = vec!["urn:mace:incommon:iap:silver".into()];
+        assert_eq!(check(&validation, &claims()), Err(Reason::Acr));
+        validation.acr.clear();
+
+        validation.max_age = Some(59);
+        assert_eq!(check(&validation, &claims()), Err(Reason::AuthTime));
+        validation.max_age = Some(60);
+        assert_eq!(check(&validation, &claims()), Ok(()));
+    }
+}
+
+mod assertion {
+    use super::common::{header, p256, NOW};
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwt::assertion::{decode, Assertion, MemoryCache, ReplayCache, Validation};
+    use jose_jwt::{Claim, Claims, Error, NumericDate, Reason};
+
+    const CLIENT: &str = "s6BhdRkqt3";
+    const ENDPOINT: &str = "https://server.example.com/token";
+
+    #[test]
+    fn claims() {
+        let mut rng = rand::thread_rng();
+        let assertion = Assertion::new(CLIENT, ENDPOINT);
+
+        let claims = assertion.claims(&mut rng, NOW);
+        assert_eq!(claims.iss.as_deref(), Some(CLIENT));
+        assert_eq!(claims.sub.as_deref(), Some(CLIENT));
+        assert!(claims.aud.as_ref().unwrap().contains(ENDPOINT));
+        assert_eq!(claims.iat, Some(NOW));
+        assert_eq!(claims.exp, NOW.checked_add(60));
+
+        // The expiration time saturates rather than being dropped.
+        let mut forever = Assertion::new(CLIENT, ENDPOINT);
+        forever.lifetime = u64::MAX;
+        let claims = forever.claims(&mut rng, NOW);
+        assert_eq!(claims.exp, Some(NumericDate::new(i64::MAX)));
+
+        let claims = assertion.claims(&mut rng, NOW);
+
+        // Every assertion has a distinct JWT ID.
+        let other = assertion.claims(&mut rng, NOW);
+        assert_eq!(claims.jti.as_ref().unwrap().len(), 22);
+        assert_ne!(claims.jti, other.jti);
+    }
+
+    #[test]
+    fn private_key_jwt() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+
+        let assertion = Assertion::new(CLIENT, ENDPOINT);
+        let token = assertion
+            .sign(&sk, &mut rng, header(Signing::Es256, None), NOW)
+            .unwrap();
+
+        let validation = Validation::new(NOW, CLIENT, ENDPOINT);
+        let mut cache = MemoryCache::default();
+        let claims: Claims<serde_json::Value> =
+            decode(&token, &pk, &validation, &mut cache).unwrap();
+        assert_eq!(claims.sub.as_deref(), Some(CLIENT));
+        assert_eq!(cache.len(), 1);
+
+        // Each assertion may only be used once.
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &pk, &validation, &mut cache);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Replay)));
+
+        let token = assertion
+            .sign(&sk, &mut rng, header(Signing::Es256, None), NOW)
+            .unwrap();
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &pk, &validation, &mut cache);
+        assert!(res.is_ok());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn replay_within_leeway() {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(vec![1u8; 32].into_boxed_slice().into());
+
+        let assertion = Assertion::new(CLIENT, ENDPOINT);
+        let token = assertion
+            .sign(&key, &mut rng, header(Signing::Hs256, None), NOW)
+            .unwrap();
+
+        let mut cache = MemoryCache::default();
+        let mut validation = Validation::new(NOW, CLIENT, ENDPOINT);
+        validation.jwt.leeway = 30;
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &key, &validation, &mut cache);
+        assert!(res.is_ok());
+
+        // After it expires, the assertion is still accepted within the
+        // leeway, so the record of its use must not have been discarded.
+        validation.jwt.clock = NOW.checked_add(70).unwrap();
+
+        let other = assertion
+            .sign(&key, &mut rng, header(Signing::Hs256, None), NOW)
+            .unwrap();
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&other, &key, &validation, &mut cache);
+        assert!(res.is_ok());
+
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &key, &validation, &mut cache);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Replay)));
+    }
+
+    #[test]
+    fn client_secret_jwt() {
+        let mut rng = rand::thread_rng();
+        let key = Key::Oct(vec![1u8; 32].into_boxed_slice().into());
+
+        let assertion = Assertion::new(CLIENT, ENDPOINT);
+        let token = assertion
+            .sign(&key, &mut rng, header(Signing::Hs256, None), NOW)
+            .unwrap();
+
+        // Invalid assertions are not recorded.
+        let mut cache = MemoryCache::default();
+        let validation = Validation::new(NOW, CLIENT, "https://other.example.com/token");
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &key, &validation, &mut cache);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Audience)));
+        assert!(cache.is_empty());
+
+        let validation = Validation::new(NOW, "other", ENDPOINT);
+        let res: Result<Claims<serde_json::Value>, _> =
+            decode(&token, &key, &validation, &mut cache);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Issuer)));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn validation() {
+        let mut rng = rand::thread_rng();
+        let validation = Validation::new(NOW, CLIENT, ENDPOINT);
+        let claims = Assertion::new(CLIENT, ENDPOINT).claims(&mut rng, NOW);
+        assert_eq!(validation.validate(None, &claims), Ok(()));
+
+        let mut other = claims.clone();
+        other.sub = Some("other".into());
+        assert_eq!(validation.validate(None, &other), Err(Reason::ClientId));
+
+        let mut other = claims.clone();
+        other.jti = None;
+        let err = validation.validate(None, &other);
+        assert_eq!(err, Err(Reason::Missing(Claim::Jti)));
+
+        let mut other = claims;
+        other.exp = None;
+        let err = validation.validate(None, &other);
+        assert_eq!(err, Err(Reason::Missing(Claim::Exp)));
+    }
+
+    #[test]
+    fn memory_cache() {
+        let mut cache = MemoryCache::default();
+        let exp = NOW.checked_add(60).unwrap();
+
+        assert!(cache.insert("a", "1", exp, NOW));
+        assert!(!cache.insert("a", "1", exp, NOW));
+        assert!(cache.insert("b", "1", exp, NOW));
+
+        // Records are removed once they expire.
+        let later = exp.checked_add(1).unwrap();
+        assert!(cache.insert("a", "2", later.checked_add(60).unwrap(), later));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.insert("a", "1", later.checked_add(60).unwrap(), later));
+    }
+}
+
+mod request {
+    use jose_jwa::{Encryption, KeyManagement, Signing};
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwt::request::{decode, decrypt, RequestObject, Validation, TYP};
+    use jose_jwt::{encode, nested, Claims, Error, Reason};
+
+    use super::common::{header, p256, NOW};
+
+    const SERVER: &str = "https://server.example.com";
+    const CLIENT: &str = "s6BhdRkqt3";
+
+    fn claims() -> Claims<RequestObject> {
+        let req = RequestObject {
+            response_type: Some("code id_token".into()),
+            client_id: Some(CLIENT.into()),
+            redirect_uri: Some("https://client.example.org/cb".into()),
+            scope: Some("openid".into()),
+            state: Some("af0ifjsldkj".into()),
+            nonce: Some("n-0S6_WzA2Mj".into()),
+            ..Default::default()
+        };
+
+        super::common::claims(CLIENT, SERVER, req)
+    }
+
+    #[test]
+    fn signed() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let validation = Validation::new(NOW, SERVER, CLIENT);
+
+        let token = encode(&claims(), &sk, &mut rng, header(Signing::Es256, Some(TYP))).unwrap();
+        let decoded: Claims<RequestObject> = decode(&token, &pk, &validation).unwrap();
+        assert_eq!(decoded, claims());
+
+        // The Request Object must be signed by the client.
+        let (other, _) = p256();
+        let token = encode(
+            &claims(),
+            &other,
+            &mut rng,
+            header(Signing::Es256, Some(TYP)),
+        )
+        .unwrap();
+        let res: Result<Claims<RequestObject>, _> = decode(&token, &pk, &validation);
+        assert!(matches!(res, Err(Error::Crypto(..))));
+    }
+
+    #[test]
+    fn client_id() {
+        let validation = Validation::new(NOW, SERVER, CLIENT);
+        assert_eq!(validation.validate(Some(TYP), &claims()), Ok(()));
+
+        // The client must match the outer request.
+        let validation = Validation::new(NOW, SERVER, "other");
+        let mut claims = claims();
+        claims.iss = Some("other".into());
+        assert_eq!(
+            validation.validate(Some(TYP), &claims),
+            Err(Reason::ClientId)
+        );
+
+        claims.oth.client_id = None;
+        assert_eq!(
+            validation.validate(Some(TYP), &claims),
+            Err(Reason::ClientId)
+        );
+    }
+
+    #[test]
+    fn issuer_audience() {
+        let validation = Validation::new(NOW, SERVER, CLIENT);
+
+        let mut claims = claims();
+        claims.iss = Some("other".into());
+        assert_eq!(validation.validate(Some(TYP), &claims), Err(Reason::Issuer));
+
+        let mut claims = self::claims();
+        claims.aud = Some("https://other.example.com".into());
+        assert_eq!(
+            validation.validate(Some(TYP), &claims),
+            Err(Reason::Audience)
+        );
+    }
+
+    #[test]
+    fn typ() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let validation = Validation::new(NOW, SERVER, CLIENT);
+
+        // The token type is required by default.
+        for typ in [None, Some("JWT"), Some("at+jwt")] {
+            let mut header = header(Signing::Es256, Some(TYP));
+            header.oth.typ = typ.map(Into::into);
+            let token = encode(&claims(), &sk, &mut rng, header).unwrap();
+            let res: Result<Claims<RequestObject>, _> = decode(&token, &pk, &validation);
+            assert_eq!(res.err(), Some(Error::Rejected(Reason::Type)));
+        }
+
+        let mut header = header(Signing::Es256, Some(TYP));
+        header.oth.typ = Some("application/oauth-authz-req+jwt".into());
+        let token = encode(&claims(), &sk, &mut rng, header).unwrap();
+        let res: Result<Claims<RequestObject>, _> = decode(&token, &pk, &validation);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn encrypted() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let kek = Key::Oct(vec![7u8; 16].into_boxed_slice().into());
+        let validation = Validation::new(NOW, SERVER, CLIENT);
+
+        let jws = encode(&claims(), &sk, &mut rng, header(Signing::Es256, Some(TYP))).unwrap();
+        let prot = jose_jwe::Protected {
+            oth: jose_jwe::Unprotected {
+                alg: Some(KeyManagement::A128Kw),
+                enc: Some(Encryption::A128Gcm),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let jwe = nested::encrypt(&kek, &mut rng, prot, &jws.parse().unwrap()).unwrap();
+        let token = jwe.to_string();
+
+        let decoded: Claims<RequestObject> =
+            decrypt(&token, &kek, &pk, &mut rng, &validation).unwrap();
+        assert_eq!(decoded, claims());
+
+        // A signed Request Object cannot be decrypted.
+        let res: Result<Claims<RequestObject>, _> = decrypt(&jws, &kek, &pk, &mut rng, &validation);
+        assert_eq!(res.err(), Some(Error::Malformed));
+
+        let other = Key::Oct(vec![8u8; 16].into_boxed_slice().into());
+        let res: Result<Claims<RequestObject>, _> =
+            decrypt(&token, &other, &pk, &mut rng, &validation);
+        assert!(matches!(res, Err(Error::Crypto(..))));
+
+        let validation = Validation::new(NOW, SERVER, "other");
+        let res: Result<Claims<RequestObject>, _> =
+            decrypt(&token, &kek, &pk, &mut rng, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Issuer)));
+    }
+}
+
+mod dpop {
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwk::Jwk;
+    use jose_jwt::dpop::{decode, hash, sign, Proof, Validation, Verified, TYP};
+    use jose_jwt::{encode, thumbprint, Claims, Error, Reason};
+
+    use super::common::{jwk, p256, NOW};
+
+    const HTU: &str = "https://server.example.com/token";
+
+    fn keys() -> (Key, Jwk) {
+        let (sk, pk) = p256();
+        (sk, jwk(&pk))
+    }
+
+    fn proof(sk: &Key, jwk: Jwk, claims: &Claims<Proof>) -> String {
+        let mut rng = rand::thread_rng();
+        sign(claims, sk, &mut rng, Signing::Es256, jwk).unwrap()
+    }
+
+    fn claims() -> Claims<Proof> {
+        Proof::new("POST", HTU).claims(&mut rand::thread_rng(), NOW)
+    }
+
+    #[test]
+    fn rfc7638_3_1() {
+        let key: jose_jwk::Key = serde_json::from_value(serde_json::json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            thumbprint(&key).as_deref(),
+            Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let (sk, jwk) = keys();
+        let jkt = thumbprint(&jwk.key).unwrap();
+        let claims = claims();
+        let token = proof(&sk, jwk, &claims);
+
+        let validation = Validation::new(NOW, "POST", HTU);
+        let verified: Verified = decode::<_, Key, _>(&token, &validation).unwrap();
+        assert_eq!(verified.claims, claims);
+        assert_eq!(verified.jkt, jkt);
+
+        // The URI is compared without its query and fragment.
+        let validation = Validation::new(NOW, "POST", "HTTPS://Server.Example.COM:443/token?x=1");
+        assert!(decode::<serde_json::Value, Key, _>(&token, &validation).is_ok());
+
+        let validation = Validation::new(NOW, "GET", HTU);
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Method)));
+
+        let validation = Validation::new(NOW, "POST", "https://server.example.com/other");
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Uri)));
+    }
+
+    #[test]
+    fn freshness() {
+        let (sk, jwk) = keys();
+        let token = proof(&sk, jwk, &claims());
+
+        let validation = Validation::new(NOW.checked_add(61).unwrap(), "POST", HTU);
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::TooOld)));
+
+        let validation = Validation::new(NOW.checked_sub(1).unwrap(), "POST", HTU);
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::IssuedInFuture)));
+
+        let mut claims = claims();
+        claims.jti = None;
+        let validation = Validation::new(NOW, "POST", HTU);
+        assert_eq!(
+            validation.validate(Some(TYP), &claims),
+            Err(Reason::Missing(jose_jwt::Claim::Jti))
+        );
+    }
+
+    #[test]
+    fn access_token_nonce() {
+        let (sk, jwk) = keys();
+        let mut claims = claims();
+        claims.oth.ath = Some(hash("Kz~8mXK1EalYznwH-LC-1fBAo.4Ljp~zsPE_NeO.gxU").into());
+        claims.oth.nonce = Some("eyJ7S_zG.eyJH0-Z.HX4w-7v".into());
+
+        let mut validation = Validation::new(NOW, "POST", HTU);
+        validation.access_token = Some("Kz~8mXK1EalYznwH-LC-1fBAo.4Ljp~zsPE_NeO.gxU".into());
+        validation.nonce = Some("eyJ7S_zG.eyJH0-Z.HX4w-7v".into());
+        assert_eq!(validation.validate(Some(TYP), &claims), Ok(()));
+
+        let token = proof(&sk, jwk, &claims);
+        assert!(decode::<serde_json::Value, Key, _>(&token, &validation).is_ok());
+
+        validation.access_token = Some("other".into());
+        assert_eq!(
+            validation.validate(Some(TYP), &claims),
+            Err(Reason::AccessTokenHash)
+        );
+
+        validation.access_token = None;
+        validation.nonce = Some("other".into());
+        assert_eq!(validation.validate(Some(TYP), &claims), Err(Reason::Nonce));
+    }
+
+    #[test]
+    fn embedded_key() {
+        let mut rng = rand::thread_rng();
+        let (sk, jwk) = keys();
+        let validation = Validation::new(NOW, "POST", HTU);
+
+        // The embedded key must not contain private material.
+        let private = Jwk {
+            key: (&sk).into(),
+            prm: Default::default(),
+        };
+
+        let res = sign(&claims(), &sk, &mut rng, Signing::Es256, private.clone());
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Key)));
+
+        let header = |jwk: Option<Jwk>| jose_jws::Protected {
+            oth: jose_jws::Unprotected {
+                alg: Some(Signing::Es256),
+                typ: Some(TYP.into()),
+                jwk,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let token = encode(&claims(), &sk, &mut rng, header(Some(private))).unwrap();
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Key)));
+
+        let token = encode(&claims(), &sk, &mut rng, header(None)).unwrap();
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Key)));
+
+        // The proof must be signed by the embedded key.
+        let (other, _) = keys();
+        let token = encode(&claims(), &other, &mut rng, header(Some(jwk.clone()))).unwrap();
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert!(matches!(res, Err(Error::Crypto(..))));
+
+        // The token type is required.
+        let mut head = header(Some(jwk));
+        head.oth.typ = Some("JWT".into());
+        let token = encode(&claims(), &sk, &mut rng, head).unwrap();
+        let res = decode::<serde_json::Value, Key, _>(&token, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Type)));
+    }
+}
+
+mod secevent {
+    use jose_jwa::Signing;
+    use jose_jwt::logout::{self, Logout, LogoutToken, EVENT};
+    use jose_jwt::secevent::{decode, SecurityEventToken, Validation, TYP};
+    use jose_jwt::{encode, Claims, Error, NumericDate, Reason};
+    use serde_json::json;
+
+    use super::common::{p256, NOW};
+
+    const ISSUER: &str = "https://server.example.com";
+    const CLIENT: &str = "s6BhdRkqt3";
+
+    fn header(typ: &str) -> jose_jws::Protected {
+        super::common::header(Signing::Es256, Some(typ))
+    }
+
+    #[test]
+    fn rfc8417_2_1() {
+        let claims: Claims<SecurityEventToken> = serde_json::from_value(json!({
+            "iss": "https://scim.example.com",
+            "iat": 1458496404,
+            "jti": "4d3559ec67504aaba65d40b0363faad8",
+            "aud": [
+                "https://scim.example.com/Feeds/98d52461fa5bbc879593b7754",
+                "https://scim.example.com/Feeds/5d7604516b1d08641d7676ee7"
+            ],
+            "events": {
+                "urn:ietf:params:scim:event:create": {
+                    "ref": "https://scim.example.com/Users/44f6142df96bd6ab61e7521d9",
+                    "attributes": ["id", "name", "userName", "password", "emails"]
+                }
+            },
+            "txn": "6a4e6e2c"
+        }))
+        .unwrap();
+
+        let event = &claims.oth.events["urn:ietf:params:scim:event:create"];
+        assert_eq!(event["attributes"][0], "id");
+        assert_eq!(claims.oth.txn.as_deref(), Some("6a4e6e2c"));
+        assert_eq!(claims.oth.toe, None);
+
+        let mut validation = Validation::new(
+            NumericDate::new(1458496404),
+            "https://scim.example.com",
+            "https://scim.example.com/Feeds/98d52461fa5bbc879593b7754",
+        );
+        assert_eq!(validation.validate(Some(TYP), &claims), Ok(()));
+        assert_eq!(validation.validate(Some("JWT"), &claims), Err(Reason::Type));
+
+        validation.events = vec!["urn:ietf:params:scim:event:delete".into()];
+        assert_eq!(validation.validate(Some(TYP), &claims), Err(Reason::Events));
+
+        let mut claims = claims;
+        claims.oth.events.clear();
+        validation.events.clear();
+        assert_eq!(validation.validate(Some(TYP), &claims), Err(Reason::Events));
+    }
+
+    #[test]
+    fn signed() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let validation = Validation::new(NOW, ISSUER, CLIENT);
+
+        let set = SecurityEventToken {
+            events: [(EVENT.into(), Default::default())].into_iter().collect(),
+            toe: Some(NOW),
+            ..Default::default()
+        };
+
+        let claims = Claims {
+            jti: Some("756E69717565206964656E746966696572".into()),
+            ..super::common::claims(ISSUER, CLIENT, set)
+        };
+
+        let token = encode(&claims, &sk, &mut rng, header(TYP)).unwrap();
+        let decoded: Claims<SecurityEventToken> = decode(&token, &pk, &validation).unwrap();
+        assert_eq!(decoded, claims);
+
+        let token = encode(&claims, &sk, &mut rng, header("JWT")).unwrap();
+        let res: Result<Claims<SecurityEventToken>, _> = decode(&token, &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Type)));
+    }
+
+    #[test]
+    fn logout() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let validation = logout::Validation::new(NOW, ISSUER, CLIENT);
+
+        let mut builder = Logout::new(ISSUER);
+        let res = builder.sign(&sk, &mut rng, header(TYP), CLIENT, NOW);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Session)));
+
+        builder.sid = Some("08a5019c-17e1-4977-8f42-65a12843ea02".into());
+        let token = builder
+            .sign(&sk, &mut rng, header(TYP), CLIENT, NOW)
+            .unwrap();
+
+        let claims: Claims<SecurityEventToken<LogoutToken>> =
+            logout::decode(&token, &pk, &validation).unwrap();
+        assert_eq!(claims.oth.events[EVENT], serde_json::Map::new());
+        assert_eq!(claims.oth.oth.sid, builder.sid);
+        assert_eq!(claims.sub, None);
+
+        // Each relying party is sent its own token.
+        let other = logout::Validation::new(NOW, ISSUER, "other");
+        let res: Result<Claims<SecurityEventToken<LogoutToken>>, _> =
+            logout::decode(&token, &pk, &other);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Audience)));
+
+        // Logout Tokens may also use the generic token type.
+        let claims = builder.claims(&mut rng, CLIENT, NOW);
+        let token = encode(&claims, &sk, &mut rng, header(TYP)).unwrap();
+        let res: Result<Claims<SecurityEventToken<LogoutToken>>, _> =
+            logout::decode(&token, &pk, &validation);
+        assert!(res.is_ok());
+
+        let token = encode(&claims, &sk, &mut rng, header("JWT")).unwrap();
+        let res: Result<Claims<SecurityEventToken<LogoutToken>>, _> =
+            logout::decode(&token, &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Type)));
+    }
+
+    #[test]
+    fn logout_rules() {
+        let mut rng = rand::thread_rng();
+        let validation = logout::Validation::new(NOW, ISSUER, CLIENT);
+
+        let mut builder = Logout::new(ISSUER);
+        builder.sub = Some("248289761001".into());
+        let claims = builder.claims(&mut rng, CLIENT, NOW);
+        assert_eq!(validation.validate(Some(logout::TYP), &claims), Ok(()));
+
+        let mut invalid = claims.clone();
+        invalid.sub = None;
+        assert_eq!(
+            validation.validate(Some(logout::TYP), &invalid),
+            Err(Reason::Session)
+        );
+
+        let mut invalid = claims.clone();
+        invalid.oth.oth.nonce = Some("n-0S6_WzA2Mj".into());
+        assert_eq!(
+            validation.validate(Some(logout::TYP), &invalid),
+            Err(Reason::Nonce)
+        );
+
+        let mut invalid = claims.clone();
+        invalid.oth.events.clear();
+        invalid.oth.events.insert(
+            "urn:ietf:params:scim:event:create".into(),
+            Default::default(),
+        );
+        assert_eq!(
+            validation.validate(Some(logout::TYP), &invalid),
+            Err(Reason::Events)
+        );
+
+        let mut invalid = claims;
+        invalid.exp = None;
+        assert_eq!(
+            validation.validate(Some(logout::TYP), &invalid),
+            Err(Reason::Missing(jose_jwt::Claim::Exp))
+        );
+    }
+}
+
+mod cnf {
+    use super::common::{p256, NOW};
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwk::Jwk;
+    use jose_jwt::access::AccessToken;
+    use jose_jwt::cnf::Confirmation;
+    use jose_jwt::dpop::{self, Proof, Verified};
+    use jose_jwt::{thumbprint, Claims, Reason};
+    use serde_json::json;
+
+    fn jwk(kid: Option<&str>) -> (Key, Jwk) {
+        let (sk, pk) = p256();
+        let mut jwk = super::common::jwk(&pk);
+        jwk.prm.kid = kid.map(Into::into);
+        (sk, jwk)
+    }
+
+    #[test]
+    fn rfc7800_3() {
+        let claims: Claims<AccessToken> = serde_json::from_value(json!({
+            "iss": "https://server.example.com",
+            "aud": "https://client.example.org",
+            "exp": 1361398824,
+            "cnf": {
+                "jwk": {
+                    "kty": "EC",
+                    "use": "sig",
+                    "crv": "P-256",
+                    "x": "18wHLeIgW9wVN6VD1Txgpqy2LszYkMf6J8njVAibvhM",
+                    "y": "-V4dS4UaLMgP_4fY4j8ir7cl1TXlFdAgcx55o7TkcSA"
+                }
+            }
+        }))
+        .unwrap();
+
+        let cnf = claims.oth.cnf.as_ref().unwrap();
+        let key = cnf.jwk.clone().unwrap();
+        assert_eq!(cnf.confirm(&key.key), Ok(()));
+
+        // Only the public key members are compared.
+        let mut presented = key.clone();
+        presented.prm = Default::default();
+        assert_eq!(cnf.confirm_resolved(&presented), Ok(()));
+
+        let (_, other) = jwk(None);
+        assert_eq!(cnf.confirm(&other.key), Err(Reason::Confirmation));
+
+        let claims: Claims<AccessToken> = serde_json::from_value(json!({
+            "iss": "https://server.example.com",
+            "aud": "https://client.example.org",
+            "exp": 1361398824,
+            "cnf": {
+                "jwe": "eyJhbGciOiJSU0EtT0FFUCIsImVuYyI6IkExMjhDQkMtSFMyNTYifQ.K7y.Fw.Ci.Re",
+                "kid": "dfd1aa97-6d8d-4575-a0fe-34b96de2bfad",
+                "jku": "https://keys.example.net/pop-keys.json",
+                "x5t#S256": "bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2"
+            }
+        }))
+        .unwrap();
+
+        let cnf = claims.oth.cnf.unwrap();
+        assert!(cnf.jwe.is_some() && cnf.jku.is_some());
+        assert_eq!(
+            cnf.oth["x5t#S256"],
+            "bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2"
+        );
+
+        // The key identifier is only matched for a resolved key.
+        let (_, key) = jwk(Some("dfd1aa97-6d8d-4575-a0fe-34b96de2bfad"));
+        assert_eq!(cnf.confirm_resolved(&key), Ok(()));
+        assert_eq!(cnf.confirm(&key.key), Err(Reason::Confirmation));
+
+        let (_, key) = jwk(Some("other"));
+        assert_eq!(cnf.confirm_resolved(&key), Err(Reason::Confirmation));
+    }
+
+    #[test]
+    fn methods() {
+        let (_, key) = jwk(Some("kid"));
+
+        // There must be a method that can be checked.
+        let jku = Confirmation {
+            jku: Some("https://keys.example.net/pop-keys.json".into()),
+            ..Default::default()
+        };
+        assert_eq!(jku.confirm_resolved(&key), Err(Reason::Confirmation));
+
+        // Every method which is present must match.
+        let mut cnf = Confirmation::thumbprint(&key.key).unwrap();
+        assert_eq!(cnf.confirm(&key.key), Ok(()));
+        assert_eq!(cnf.confirm_resolved(&key), Ok(()));
+
+        cnf.kid = Some("other".into());
+        assert_eq!(cnf.confirm_resolved(&key), Err(Reason::Confirmation));
+
+        // A symmetric key has no public members.
+        let oct: Jwk = serde_json::from_value(json!({ "kty": "oct", "k": "AAAA" })).unwrap();
+        let cnf = Confirmation {
+            jwk: Some(oct.clone()),
+            ..Default::default()
+        };
+        assert_eq!(cnf.confirm(&oct.key), Err(Reason::Confirmation));
+    }
+
+    #[test]
+    fn dpop_bound() {
+        let mut rng = rand::thread_rng();
+        let (sk, key) = jwk(None);
+        let htu = "https://resource.example.org/protectedresource";
+
+        // The access token is bound to the key of the proof.
+        let claims: Claims<Proof> = Proof::new("GET", htu).claims(&mut rng, NOW);
+        let token = dpop::sign(&claims, &sk, &mut rng, Signing::Es256, key.clone()).unwrap();
+        let validation = dpop::Validation::new(NOW, "GET", htu);
+        let proof: Verified = dpop::decode::<_, Key, _>(&token, &validation).unwrap();
+
+        let cnf = Confirmation {
+            jkt: Some(proof.jkt.clone()),
+            ..Default::default()
+        };
+        assert_eq!(cnf.jkt, thumbprint(&key.key));
+        assert_eq!(cnf.confirm(&proof.jwk.key), Ok(()));
+
+        let (_, other) = jwk(None);
+        assert_eq!(cnf.confirm(&other.key), Err(Reason::Confirmation));
+    }
+
+    #[test]
+    fn self_asserted_kid() {
+        let mut rng = rand::thread_rng();
+        let htu = "https://resource.example.org/protectedresource";
+
+        // The access token is bound to the victim's key by its identifier.
+        let cnf = Confirmation {
+            kid: Some("victim".into()),
+            ..Default::default()
+        };
+
+        // The attacker signs a proof with its own key, claiming that same
+        // key identifier.
+        let (sk, key) = jwk(Some("victim"));
+        let claims: Claims<Proof> = Proof::new("GET", htu).claims(&mut rng, NOW);
+        let token = dpop::sign(&claims, &sk, &mut rng, Signing::Es256, key).unwrap();
+        let validation = dpop::Validation::new(NOW, "GET", htu);
+        let proof: Verified = dpop::decode::<_, Key, _>(&token, &validation).unwrap();
+        assert_eq!(proof.jwk.prm.kid.as_deref(), Some("victim"));
+
+        assert_eq!(cnf.confirm(&proof.jwk.key), Err(Reason::Confirmation));
+    }
+}
+
+mod sd {
+    use super::common::{jwk, p256, NOW};
+    use jose_jwa::Signing;
+    use jose_jwk::crypto::rcrypto::Key;
+    use jose_jwk::Jwk;
+    use jose_jwt::sd::{decode, Binding, Disclosure, Hash, Issuer, SdJwt, Validation};
+    use jose_jwt::{encode, Claims, Error, NumericDate, Reason};
+    use serde_json::{json, Value};
+
+    const ISSUER: &str = "https://issuer.example.com";
+    const VERIFIER: &str = "https://verifier.example.org";
+    const NONCE: &str = "1234567890";
+
+    type Res = Result<Claims, Error<jose_jwk::crypto::rcrypto::Error>>;
+
+    fn header() -> jose_jws::Protected {
+        super::common::header(Signing::Es256, None)
+    }
+
+    /// Returns a policy for SD-JWTs from the issuer to the verifier.
+    fn validation() -> Validation<NumericDate> {
+        let mut validation = Validation::new(NOW, ISSUER);
+        validation.jwt.audiences = vec![VERIFIER.into()];
+        validation
+    }
+
+    fn claims(holder: Option<&Jwk>) -> Claims {
+        let mut oth = json!({
+            "given_name": "Erika",
+            "family_name": "Mustermann",
+            "address": { "locality": "Berlin", "country": "DE" },
+            "nationalities": ["DE", "FR"],
+        });
+
+        if let Some(jwk) = holder {
+            oth["cnf"] = json!({ "jwk": jwk });
+        }
+
+        let oth = serde_json::from_value(oth).unwrap();
+        super::common::claims(ISSUER, VERIFIER, oth)
+    }
+
+    /// Issues an SD-JWT concealing all claims but the nested locality.
+    fn issue(sk: &Key, holder: Option<&Jwk>) -> SdJwt {
+        let mut rng = rand::thread_rng();
+        let mut claims = claims(holder);
+        let mut issuer = Issuer::new(Hash::Sha256);
+
+        let oth = &mut claims.oth;
+        let address = oth["address"].as_object_mut().unwrap();
+        assert_eq!(issuer.conceal(&mut rng, address, "country"), Ok(true));
+        assert_eq!(issuer.conceal(&mut rng, oth, "address"), Ok(true));
+        assert_eq!(issuer.conceal(&mut rng, oth, "given_name"), Ok(true));
+        assert_eq!(issuer.conceal(&mut rng, oth, "family_name"), Ok(true));
+        assert_eq!(issuer.conceal(&mut rng, oth, "birthdate"), Ok(false));
+
+        let nationalities = oth["nationalities"].as_array_mut().unwrap();
+        assert!(issuer.conceal_element(&mut rng, nationalities, 1));
+        assert!(!issuer.conceal_element(&mut rng, nationalities, 2));
+        issuer.decoy(&mut rng, oth).unwrap();
+
+        issuer.sign(claims, sk, &mut rng, header()).unwrap()
+    }
+
+    /// Keeps only the disclosures with the given names or values.
+    fn present(sd: &mut SdJwt, keep: &[&str]) {
+        sd.disclosures.retain(|x| {
+            let name = x.name().map(Value::from).unwrap_or_default();
+            keep.iter().any(|k| name == *k || x.value() == k)
+        });
+    }
+
+    #[test]
+    fn rfc9901_disclosures() {
+        let disclosure: Disclosure =
+            "WyJfMjZiYzRMVC1hYzZxMmtJNmNCVzVlcyIsICJmYW1pbHlfbmFtZSIsICJNw7ZiaXVzIl0"
+                .parse()
+                .unwrap();
+        assert_eq!(disclosure.salt(), "_26bc4LT-ac6q2kI6cBW5es");
+        assert_eq!(disclosure.name(), Some("family_name"));
+        assert_eq!(disclosure.value(), "Möbius");
+
+        let disclosure: Disclosure = "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgIkZSIl0"
+            .parse()
+            .unwrap();
+        assert_eq!(disclosure.name(), None);
+        assert_eq!(disclosure.value(), "FR");
+        assert_eq!(
+            disclosure.digest(Hash::Sha256),
+            "w0I8EKcdCtUPkGCNUrfwVp2xEgNjtoIDlOxc9-PlOhs"
+        );
+        assert_eq!(
+            disclosure.to_string(),
+            "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgIkZSIl0"
+        );
+
+        // A disclosure is an array of two or three elements.
+        assert!("WyJzYWx0Il0".parse::<Disclosure>().is_err());
+        assert!("WzEsICJGUiJd".parse::<Disclosure>().is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let (sk, _) = p256();
+        let sd = issue(&sk, None);
+        assert_eq!(sd.disclosures.len(), 5);
+
+        let token = sd.to_string();
+        assert_eq!(token.matches('~').count(), 6);
+        assert!(token.ends_with('~'));
+        assert_eq!(token.parse::<SdJwt>().unwrap(), sd);
+
+        assert!("".parse::<SdJwt>().is_err());
+        assert!("eyJ.eyJ.sig".parse::<SdJwt>().is_err());
+        assert!("eyJ.eyJ.sig~%%%~".parse::<SdJwt>().is_err());
+        assert_eq!(
+            "eyJ.eyJ.sig~kb".parse::<SdJwt>().unwrap().kb.as_deref(),
+            Some("kb")
+        );
+    }
+
+    #[test]
+    fn reconstruction() {
+        let (sk, pk) = p256();
+        let validation = validation();
+
+        // Every claim is disclosed.
+        let sd = issue(&sk, None);
+        let claims: Claims = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation).unwrap();
+        assert_eq!(claims, self::claims(None));
+
+        // Only the disclosed claims are reconstructed.
+        let mut sd = issue(&sk, None);
+        present(&mut sd, &["given_name", "address"]);
+        let claims: Claims = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation).unwrap();
+        assert_eq!(
+            Value::from(claims.oth),
+            json!({
+                "given_name": "Erika",
+                "address": { "locality": "Berlin" },
+                "nationalities": ["DE"],
+            })
+        );
+
+        let mut sd = issue(&sk, None);
+        present(&mut sd, &["FR"]);
+        let claims: Claims = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation).unwrap();
+        assert_eq!(
+            Value::from(claims.oth),
+            json!({ "nationalities": ["DE", "FR"] })
+        );
+
+        // A nested disclosure is not referenced without its parent.
+        let mut sd = issue(&sk, None);
+        present(&mut sd, &["country"]);
+        let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // The issuer-signed JWT is verified.
+        let (other, _) = p256();
+        let sd = issue(&other, None);
+        let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+        assert!(matches!(res, Err(Error::Crypto(..))));
+    }
+
+    #[test]
+    fn digests() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let validation = validation();
+
+        let disclosure = Disclosure::new(&mut rng, Some("given_name"), "Erika".into());
+        let digest = disclosure.digest(Hash::Sha256);
+        let element = Disclosure::new(&mut rng, None, "FR".into());
+
+        let sign = |oth: Value, disclosures: &[&Disclosure]| {
+            let mut claims = claims(None);
+            claims.oth = serde_json::from_value(oth).unwrap();
+
+            let jwt = encode(&claims, &sk, &mut rand::thread_rng(), header()).unwrap();
+            let sd = SdJwt {
+                jwt,
+                disclosures: disclosures.iter().map(|x| (*x).clone()).collect(),
+                kb: None,
+            };
+
+            let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+            res
+        };
+
+        assert!(sign(json!({ "_sd": [digest] }), &[&disclosure]).is_ok());
+
+        // A digest may only appear once.
+        let res = sign(json!({ "_sd": [digest, digest] }), &[&disclosure]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Digest)));
+
+        let res = sign(json!({ "_sd": [digest], "a": { "_sd": [digest] } }), &[]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Digest)));
+
+        let res = sign(json!({ "_sd": [digest], "a": [{ "...": digest }] }), &[]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Digest)));
+
+        // The same disclosure may not be presented twice.
+        let res = sign(json!({ "_sd": [digest] }), &[&disclosure, &disclosure]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // Every disclosure must be referenced, by a digest of the right kind.
+        let res = sign(json!({}), &[&disclosure]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        let res = sign(json!({ "a": [{ "...": digest }] }), &[&disclosure]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        let res = sign(
+            json!({ "_sd": [element.digest(Hash::Sha256)] }),
+            &[&element],
+        );
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // A disclosed claim may not replace an existing claim.
+        let res = sign(
+            json!({ "_sd": [digest], "given_name": "Max" }),
+            &[&disclosure],
+        );
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // The hash algorithm must be supported.
+        let res = sign(json!({ "_sd": [digest], "_sd_alg": "md5" }), &[&disclosure]);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Digest)));
+
+        let sha512 = disclosure.digest(Hash::Sha512);
+        let claims = sign(
+            json!({ "_sd": [sha512], "_sd_alg": "sha-512" }),
+            &[&disclosure],
+        );
+        assert_eq!(
+            Value::from(claims.unwrap().oth),
+            json!({ "given_name": "Erika" })
+        );
+    }
+
+    #[test]
+    fn key_binding() {
+        let (sk, pk) = p256();
+        let (hsk, hpk) = p256();
+        let holder = jwk(&hpk);
+
+        let mut validation = validation();
+        validation.key_binding = Some(Binding::new(VERIFIER, NONCE));
+
+        let bind = |sd: &mut SdJwt, key: &Key, aud: &str, nonce: &str, now: NumericDate| {
+            let mut rng = rand::thread_rng();
+            sd.bind(key, &mut rng, header(), aud, nonce, now).unwrap();
+        };
+
+        let mut sd = issue(&sk, Some(&holder));
+        present(&mut sd, &["family_name"]);
+        bind(&mut sd, &hsk, VERIFIER, NONCE, NOW);
+
+        let claims: Claims = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation).unwrap();
+        assert_eq!(claims.oth["family_name"], "Mustermann");
+        assert!(claims.oth.get("given_name").is_none());
+
+        // The Key Binding JWT covers the presented disclosures.
+        let mut tampered = sd.clone();
+        tampered.disclosures.clear();
+        let res: Res = decode::<_, _, Key, _>(&tampered.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::KeyBinding)));
+
+        // The Key Binding JWT is required.
+        let mut unbound = sd.clone();
+        unbound.kb = None;
+        let res: Res = decode::<_, _, Key, _>(&unbound.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::KeyBinding)));
+
+        let mut unconfirmed = issue(&sk, None);
+        bind(&mut unconfirmed, &hsk, VERIFIER, NONCE, NOW);
+        let res: Res = decode::<_, _, Key, _>(&unconfirmed.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::KeyBinding)));
+
+        // It must be signed by the holder, for this verifier and nonce.
+        let (other, _) = p256();
+        let mut wrong = issue(&sk, Some(&holder));
+        bind(&mut wrong, &other, VERIFIER, NONCE, NOW);
+        let res: Res = decode::<_, _, Key, _>(&wrong.to_string(), &pk, &validation);
+        assert!(matches!(res, Err(Error::Crypto(..))));
+
+        let mut wrong = issue(&sk, Some(&holder));
+        bind(&mut wrong, &hsk, "https://other.example.org", NONCE, NOW);
+        let res: Res = decode::<_, _, Key, _>(&wrong.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Audience)));
+
+        let mut wrong = issue(&sk, Some(&holder));
+        bind(&mut wrong, &hsk, VERIFIER, "other", NOW);
+        let res: Res = decode::<_, _, Key, _>(&wrong.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Nonce)));
+
+        let mut stale = issue(&sk, Some(&holder));
+        bind(
+            &mut stale,
+            &hsk,
+            VERIFIER,
+            NONCE,
+            NOW.checked_sub(301).unwrap(),
+        );
+        let res: Res = decode::<_, _, Key, _>(&stale.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::TooOld)));
+
+        // Without a policy, the Key Binding JWT is not required.
+        validation.key_binding = None;
+        let res: Res = decode::<_, _, Key, _>(&unbound.to_string(), &pk, &validation);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn disclosed_cnf() {
+        let mut rng = rand::thread_rng();
+        let (sk, pk) = p256();
+        let (hsk, hpk) = p256();
+        let holder = jwk(&hpk);
+
+        let mut validation = validation();
+        validation.key_binding = Some(Binding::new(VERIFIER, NONCE));
+
+        // The holder's key must be in the issuer-signed payload.
+        let mut claims = claims(Some(&holder));
+        let mut issuer = Issuer::new(Hash::Sha256);
+        assert_eq!(issuer.conceal(&mut rng, &mut claims.oth, "cnf"), Ok(true));
+        let mut sd = issuer.sign(claims, &sk, &mut rng, header()).unwrap();
+        sd.bind(&hsk, &mut rng, header(), VERIFIER, NONCE, NOW)
+            .unwrap();
+
+        let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // Nor may any part of it be disclosed.
+        let mut claims = self::claims(Some(&holder));
+        let mut issuer = Issuer::new(Hash::Sha256);
+        let cnf = claims.oth["cnf"].as_object_mut().unwrap();
+        assert_eq!(issuer.conceal(&mut rng, cnf, "jwk"), Ok(true));
+        let mut sd = issuer.sign(claims, &sk, &mut rng, header()).unwrap();
+        sd.bind(&hsk, &mut rng, header(), VERIFIER, NONCE, NOW)
+            .unwrap();
+
+        let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+
+        // Even if it is withheld.
+        sd.disclosures.clear();
+        validation.key_binding = None;
+        let res: Res = decode::<_, _, Key, _>(&sd.to_string(), &pk, &validation);
+        assert_eq!(res.err(), Some(Error::Rejected(Reason::Disclosure)));
+    }
+
+    #[test]
+    fn conceal_invalid_sd() {
+        let mut rng = rand::thread_rng();
+        let mut issuer = Issuer::new(Hash::Sha256);
+
+        // An existing `_sd` claim which is not an array is not overwritten.
+        let mut obj = json!({ "_sd": "digest", "given_name": "Erika" });
+        let obj = obj.as_object_mut().unwrap();
+        let res = issuer.conceal(&mut rng, obj, "given_name");
+        assert_eq!(res, Err(Reason::Digest));
+        assert_eq!(
+            Value::from(obj.clone()),
+            json!({ "_sd": "digest", "given_name": "Erika" })
+        );
+        assert_eq!(issuer.decoy(&mut rng, obj), Err(Reason::Digest));
+        assert!(issuer.disclosures.is_empty());
+    }
+}
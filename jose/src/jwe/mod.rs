@@ -0,0 +1,10 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! JSON Web Encryption (JWE)
+//!
+//! Content encryption is enabled by the `aes-gcm` feature, key wrapping by
+//! the `aes-kw` feature and compression (i.e. `"zip":"DEF"`) by the `deflate`
+//! feature.
+
+pub use jose_jwe::*;
@@ -13,6 +13,9 @@ extern crate alloc;
 
 pub mod alg;
 pub mod b64;
+#[cfg(feature = "jwe")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jwe")))]
+pub mod jwe;
 pub mod jwk;
 pub mod jws;
 pub mod key;
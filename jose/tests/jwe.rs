@@ -0,0 +1,262 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "aes-gcm")]
+
+mod rfc7516 {
+    use jose::jwe::crypto::rcrypto::Cek;
+    use jose::jwe::Compact;
+    use jose_jwa::Encryption;
+
+    const A1: &str = concat!(
+        "eyJhbGciOiJSU0EtT0FFUCIsImVuYyI6IkEyNTZHQ00ifQ.",
+        "OKOawDo13gRp2ojaHV7LFpZcgV7T6DVZKTyKOMTYUmKoTCVJRgckCL9kiMT03JGe",
+        "ipsEdY3mx_etLbbWSrFr05kLzcSr4qKAq7YN7e9jwQRb23nfa6c9d-StnImGyFDb",
+        "Sv04uVuxIp5Zms1gNxKKK2Da14B8S4rzVRltdYwam_lDp5XnZAYpQdb76FdIKLaV",
+        "mqgfwX7XWRxv2322i-vDxRfqNzo_tETKzpVLzfiwQyeyPGLBIO56YJ7eObdv0je8",
+        "1860ppamavo35UgoRdbYaBcoh9QcfylQr66oc6vFWXRcZ_ZT2LawVCWTIy3brGPi",
+        "6UklfCpIMfIjf7iGdXKHzg.",
+        "48V1_ALb6US04U3b.",
+        "5eym8TW_c8SuK0ltJ3rpYIzOeDQz7TALvtu6UG9oMo4vpzs9tX_EFShS8iB7j6ji",
+        "SdiwkIr3ajwQzaBtQD_A.",
+        "XFBoMYUZodetZdvTiFvSkQ",
+    );
+
+    #[test]
+    fn a1_decrypt() {
+        const CEK: [u8; 32] = [
+            177, 161, 244, 128, 84, 143, 225, 115, 63, 180, 3, 255, 107, 154, 212, 246, 138, 7,
+            110, 91, 112, 46, 34, 105, 47, 130, 203, 46, 122, 234, 64, 252,
+        ];
+
+        let jwe: Compact = A1.parse().unwrap();
+        let cek = Cek::new(Encryption::A256Gcm, CEK.to_vec().into_boxed_slice().into()).unwrap();
+
+        let aad = jwe.additional_data();
+        let mut buf = jwe.ciphertext.to_vec();
+        cek.decrypt(&jwe.iv, &aad, &mut buf, &jwe.tag).unwrap();
+        assert_eq!(
+            buf,
+            b"The true sign of intelligence is not knowledge but imagination."
+        );
+
+        // Re-encrypting with the same IV reproduces the ciphertext and tag.
+        let tag = cek.encrypt(&jwe.iv, &aad, &mut buf).unwrap();
+        assert_eq!(buf, &**jwe.ciphertext);
+        assert_eq!(tag, &**jwe.tag);
+
+        // Any modification of the AAD must be detected.
+        let mut bad = aad.clone();
+        bad[0] ^= 1;
+        assert!(cek.decrypt(&jwe.iv, &bad, &mut buf, &jwe.tag).is_err());
+    }
+}
+
+mod rfc7520 {
+    use jose::jwe::crypto::rcrypto::Cek;
+    use jose::jwe::{Compact, Flattened};
+    use jose_b64::base64ct::{Base64UrlUnpadded, Encoding};
+    use jose_jwa::Encryption;
+    use serde_json::json;
+
+    const PLAINTEXT: &str = concat!(
+        "You can trust us to stick with you through thick and thin\u{2013}to the ",
+        "bitter end. And you can trust us to keep any secret of yours\u{2013}closer ",
+        "than you keep it yourself. But you cannot trust us to let you face trouble ",
+        "alone, and go off without a word. We are your friends, Frodo.",
+    );
+
+    /// Decrypts the content with the CEK, checks that re-encrypting it
+    /// reproduces the ciphertext and tag and returns the plaintext.
+    fn decrypt(
+        enc: Encryption,
+        cek: &str,
+        aad: &[u8],
+        iv: &[u8],
+        ct: &[u8],
+        tag: &[u8],
+    ) -> Vec<u8> {
+        let cek = Base64UrlUnpadded::decode_vec(cek).unwrap();
+        let cek = Cek::new(enc, cek.into_boxed_slice().into()).unwrap();
+
+        let mut buf = ct.to_vec();
+        cek.decrypt(iv, aad, &mut buf, tag).unwrap();
+        let plaintext = buf.clone();
+
+        let tagged = cek.encrypt(iv, aad, &mut buf).unwrap();
+        assert_eq!(buf, ct);
+        assert_eq!(tagged, tag);
+
+        let mut bad = tag.to_vec();
+        bad[15] ^= 1;
+        assert!(cek.decrypt(iv, aad, &mut buf, &bad).is_err());
+
+        plaintext
+    }
+
+    fn compact(enc: Encryption, cek: &str, jwe: &str) -> Vec<u8> {
+        let jwe: Compact = jwe.parse().unwrap();
+        let aad = jwe.additional_data();
+        decrypt(enc, cek, &aad, &jwe.iv, &jwe.ciphertext, &jwe.tag)
+    }
+
+    fn flattened(enc: Encryption, cek: &str, jwe: serde_json::Value) -> Vec<u8> {
+        let jwe: Flattened = serde_json::from_value(jwe).unwrap();
+        let aad = jwe.additional_data();
+        let iv = jwe.iv.unwrap();
+        let tag = jwe.tag.unwrap();
+        decrypt(enc, cek, &aad, &iv, &jwe.ciphertext, &tag)
+    }
+
+    #[test]
+    fn s5_2() {
+        // The RSA-OAEP encrypted key is omitted; the content is decrypted with
+        // the CEK given in the example.
+        let plaintext = flattened(
+            Encryption::A256Gcm,
+            "mYMfsggkTAm0TbvtlFh2hyoXnbEzJQjMxmgLN3d8xXA",
+            json!({
+                "protected": concat!(
+                    "eyJhbGciOiJSU0EtT0FFUCIsImtpZCI6InNhbXdpc2UuZ2FtZ2VlQGhvYmJpdG",
+                    "9uLmV4YW1wbGUiLCJlbmMiOiJBMjU2R0NNIn0",
+                ),
+                "iv": "-nBoKLH0YkLZPSI9",
+                "ciphertext": concat!(
+                    "o4k2cnGN8rSSw3IDo1YuySkqeS_t2m1GXklSgqBdpACm6UJuJowOHC5ytjqYgR",
+                    "L-I-soPlwqMUf4UgRWWeaOGNw6vGW-xyM01lTYxrXfVzIIaRdhYtEMRBvBWbEw",
+                    "P7ua1DRfvaOjgZv6Ifa3brcAM64d8p5lhhNcizPersuhw5f-pGYzseva-TUaL8",
+                    "iWnctc-sSwy7SQmRkfhDjwbz0fz6kFovEgj64X1I5s7E6GLp5fnbYGLa1QUiML",
+                    "7Cc2GxgvI7zqWo0YIEc7aCflLG1-8BboVWFdZKLK9vNoycrYHumwzKluLWEbSV",
+                    "maPpOslY2n525DxDfWaVFUfKQxMF56vn4B9QMpWAbnypNimbM8zVOw",
+                ),
+                "tag": "UCGiqJxhBI3IFVdPalHHvA",
+            }),
+        );
+        assert_eq!(plaintext, PLAINTEXT.as_bytes());
+    }
+
+    #[test]
+    fn s5_6() {
+        // With direct encryption, the key is the CEK.
+        let plaintext = compact(
+            Encryption::A128Gcm,
+            "XctOhJAkA-pD9Lh7ZgW_2A",
+            concat!(
+                "eyJhbGciOiJkaXIiLCJraWQiOiI3N2M3ZTJiOC02ZTEzLTQ1Y2YtODY3Mi02MT",
+                "diNWI0NTI0M2EiLCJlbmMiOiJBMTI4R0NNIn0",
+                "..",
+                "refa467QzzKx6QAB",
+                ".",
+                "JW_i_f52hww_ELQPGaYyeAB6HYGcR559l9TYnSovc23XJoBcW29rHP8yZOZG7Y",
+                "hLpT1bjFuvZPjQS-m0IFtVcXkZXdH_lr_FrdYt9HRUYkshtrMmIUAyGmUnd9zM",
+                "DB2n0cRDIHAzFVeJUDxkUwVAE7_YGRPdcqMyiBoCO-FBdE-Nceb4h3-FtBP-c_",
+                "BIwCPTjb9o0SbdcdREEMJMyZBH8ySWMVi1gPD9yxi-aQpGbSv_F9N4IZAxscj5",
+                "g-NJsUPbjk29-s7LJAGb15wEBtXphVCgyy53CoIKLHHeJHXex45Uz9aKZSRSIn",
+                "ZI-wjsY0yu3cT4_aQ3i1o-tiE-F8Ios61EKgyIQ4CWao8PFMj8TTnp",
+                ".",
+                "vbb32Xvllea2OtmHAdccRQ",
+            ),
+        );
+        assert_eq!(plaintext, PLAINTEXT.as_bytes());
+    }
+
+    #[test]
+    fn s5_8() {
+        let plaintext = compact(
+            Encryption::A128Gcm,
+            "aY5_Ghmk9KxWPBLu_glx1w",
+            concat!(
+                "eyJhbGciOiJBMTI4S1ciLCJraWQiOiI4MWIyMDk2NS04MzMyLTQzZDktYTQ2OC",
+                "04MjE2MGFkOTFhYzgiLCJlbmMiOiJBMTI4R0NNIn0",
+                ".",
+                "CBI6oDw8MydIx1IBntf_lQcw2MmJKIQx",
+                ".",
+                "Qx0pmsDa8KnJc9Jo",
+                ".",
+                "AwliP-KmWgsZ37BvzCefNen6VTbRK3QMA4TkvRkH0tP1bTdhtFJgJxeVmJkLD6",
+                "1A1hnWGetdg11c9ADsnWgL56NyxwSYjU1ZEHcGkd3EkU0vjHi9gTlb90qSYFfe",
+                "F0LwkcTtjbYKCsiNJQkcIp1yeM03OmuiYSoYJVSpf7ej6zaYcMv3WwdxDFl8RE",
+                "wOhNImk2Xld2JXq6BR53TSFkyT7PwVLuq-1GwtGHlQeg7gDT6xW0JqHDPn_H-p",
+                "uQsmthc9Zg0ojmJfqqFvETUxLAF-KjcBTS5dNy6egwkYtOt8EIHK-oEsKYtZRa",
+                "a8Z7MOZ7UGxGIMvEmxrGCPeJa14slv2-gaqK0kEThkaSqdYw0FkQZF",
+                ".",
+                "ER7MWJZ1FBI_NKvn7Zb1Lw",
+            ),
+        );
+        assert_eq!(plaintext, PLAINTEXT.as_bytes());
+    }
+
+    #[cfg(all(feature = "aes-kw", feature = "deflate"))]
+    #[test]
+    fn s5_9() {
+        use jose::b64::Update;
+        use jose::jwe::crypto::{DecryptingKey, Decryptor};
+        use jose_jwk::crypto::rcrypto::Key;
+
+        const JWE: &str = concat!(
+            "eyJhbGciOiJBMTI4S1ciLCJraWQiOiI4MWIyMDk2NS04MzMyLTQzZDktYTQ2OC",
+            "04MjE2MGFkOTFhYzgiLCJlbmMiOiJBMTI4R0NNIiwiemlwIjoiREVGIn0",
+            ".",
+            "5vUT2WOtQxKWcekM_IzVQwkGgzlFDwPi",
+            ".",
+            "p9pUq6XHY0jfEZIl",
+            ".",
+            "HbDtOsdai1oYziSx25KEeTxmwnh8L8jKMFNc1k3zmMI6VB8hry57tDZ61jXyez",
+            "SPt0fdLVfe6Jf5y5-JaCap_JQBcb5opbmT60uWGml8blyiMQmOn9J--XhhlYg0",
+            "m-BHaqfDO5iTOWxPxFMUedx7WCy8mxgDHj0aBMG6152PsM-w5E_o2B3jDbrYBK",
+            "hpYA7qi3AyijnCJ7BP9rr3U8kxExCpG3mK420TjOw",
+            ".",
+            "VILuUwuIxaLVmh5X-T7kmA",
+        );
+
+        // The plaintext is compressed (i.e. `"zip":"DEF"`) before encryption.
+        let compressed = compact(Encryption::A128Gcm, "hC-MpLZSuwWv8sexS6ydfw", JWE);
+        let inflated = miniz_oxide::inflate::decompress_to_vec(&compressed).unwrap();
+        assert_eq!(inflated, PLAINTEXT.as_bytes());
+
+        // The key encryption key unwraps the CEK, and the decrypted content
+        // is inflated.
+        let key = Base64UrlUnpadded::decode_vec("GZy6sIZ6wl9NJOKB-jnmVQ").unwrap();
+        let key = Key::Oct(key.into_boxed_slice().into());
+        let jwe = Flattened::from(JWE.parse::<Compact>().unwrap());
+
+        let mut rng = rand::thread_rng();
+        let dec = key.decrypt(&mut rng, &jwe).unwrap();
+        let plaintext = dec.chain(&jwe.ciphertext).unwrap().finish().unwrap();
+        assert_eq!(plaintext, PLAINTEXT.as_bytes());
+    }
+
+    #[test]
+    fn s5_10() {
+        // The additional authenticated data (i.e. `aad`) requires a JSON
+        // serialization.
+        let plaintext = flattened(
+            Encryption::A128Gcm,
+            "75m1ALsYv10pZTKPWrsqdg",
+            json!({
+                "protected": concat!(
+                    "eyJhbGciOiJBMTI4S1ciLCJraWQiOiI4MWIyMDk2NS04MzMyLTQzZDktYTQ2OC",
+                    "04MjE2MGFkOTFhYzgiLCJlbmMiOiJBMTI4R0NNIn0",
+                ),
+                "encrypted_key": "4YiiQ_ZzH76TaIkJmYfRFgOV9MIpnx4X",
+                "aad": concat!(
+                    "WyJ2Y2FyZCIsW1sidmVyc2lvbiIse30sInRleHQiLCI0LjAiXSxbImZuIix7fS",
+                    "widGV4dCIsIk1lcmlhZG9jIEJyYW5keWJ1Y2siXSxbIm4iLHt9LCJ0ZXh0Iixb",
+                    "IkJyYW5keWJ1Y2siLCJNZXJpYWRvYyIsIk1yLiIsIiJdXSxbImJkYXkiLHt9LC",
+                    "J0ZXh0IiwiVEEgMjk4MiJdLFsiZ2VuZGVyIix7fSwidGV4dCIsIk0iXV1d",
+                ),
+                "iv": "veCx9ece2orS7c_N",
+                "ciphertext": concat!(
+                    "Z_3cbr0k3bVM6N3oSNmHz7Lyf3iPppGf3Pj17wNZqteJ0Ui8p74SchQP8xygM1",
+                    "oFRWCNzeIa6s6BcEtp8qEFiqTUEyiNkOWDNoF14T_4NFqF-p2Mx8zkbKxI7oPK",
+                    "8KNarFbyxIDvICNqBLba-v3uzXBdB89fzOI-Lv4PjOFAQGHrgv1rjXAmKbgkft",
+                    "9cB4WeyZw8MldbBhc-V_KWZslrsLNygon_JJWd_ek6LQn5NRehvApqf9ZrxB4a",
+                    "q3FXBxOxCys35PhCdaggy2kfUfl2OkwKnWUbgXVD1C6HxLIlqHhCwXDG59weHr",
+                    "RDQeHyMRoBljoV3X_bUTJDnKBFOod7nLz-cj48JMx3SnCZTpbQAkFV",
+                ),
+                "tag": "vOaH_Rajnpy_3hOtqvZHRA",
+            }),
+        );
+        assert_eq!(plaintext, PLAINTEXT.as_bytes());
+    }
+}